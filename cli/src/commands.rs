@@ -0,0 +1,383 @@
+use {
+    crate::{Cli, Command, DecodeState, InitPool, Liquidity, Quote, SwapArgs},
+    bank_of_zion::{
+        instructions::{InitializeParams, ZionInstruction},
+        return_data::ReturnData,
+    },
+    bank_of_zion_client::{
+        decode::decode_swap_state,
+        pda::find_swap_state_address, PoolAccounts, SwapDirection, TokenAccounts, TransactionBuilder,
+        UserAccounts,
+    },
+    solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig},
+    solana_program::{
+        instruction::Instruction, program_pack::Pack, pubkey::Pubkey, system_instruction,
+    },
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::Transaction,
+    },
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent,
+    std::{error::Error, path::{Path, PathBuf}, str::FromStr},
+};
+
+type CommandResult<T = ()> = Result<T, Box<dyn Error>>;
+
+///Parse a swap direction, a-to-b or b-to-a
+pub fn parse_direction(direction: &str) -> Result<SwapDirection, String> {
+    match direction {
+        "a-to-b" => Ok(SwapDirection::AToB),
+        "b-to-a" => Ok(SwapDirection::BToA),
+        _ => Err(format!("invalid direction {}, expected a-to-b or b-to-a", direction)),
+    }
+}
+
+fn read_keypair(path: &Path) -> CommandResult<Keypair> {
+    read_keypair_file(path).map_err(|err| format!("failed to read keypair {}: {}", path.display(), err).into())
+}
+
+fn default_keypair_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".config/solana/id.json")
+}
+
+///Cluster connection, fee payer and options shared by every command
+struct Context {
+    rpc: RpcClient,
+    payer: Keypair,
+    pool: Pubkey,
+    dry_run: bool,
+}
+
+impl Context {
+    ///keypair at path, or the fee payer when no path is given
+    fn signer(&self, path: &Option<PathBuf>) -> CommandResult<Keypair> {
+        match path {
+            Some(path) => read_keypair(path),
+            None => Ok(Keypair::from_bytes(&self.payer.to_bytes())?),
+        }
+    }
+
+    ///Accounts of the pool, read from its swap state
+    fn pool_accounts(&self) -> CommandResult<PoolAccounts> {
+        let data = self.rpc.get_account_data(&self.pool)?;
+        let mut pool = PoolAccounts::from_account_data(&data)?;
+        //the program only accepts its pda, keep the given address so a wrong one fails loudly
+        pool.swap_state = self.pool;
+        Ok(pool)
+    }
+
+    ///Sign the instructions with the fee payer and signers, then send them or print them for a dry run
+    fn send(&self, instructions: Vec<Instruction>, signers: &[&Keypair]) -> CommandResult {
+        let mut all_signers = vec![&self.payer];
+        for signer in signers {
+            if !all_signers.iter().any(|added| added.pubkey() == signer.pubkey()) {
+                all_signers.push(signer);
+            }
+        }
+
+        let recent_blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = TransactionBuilder::new()
+            .instructions(instructions)
+            .sign(&self.payer.pubkey(), &all_signers, recent_blockhash)?;
+
+        if self.dry_run {
+            println!("{}", serialize(&transaction)?);
+            return Ok(());
+        }
+
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {}", signature);
+        Ok(())
+    }
+}
+
+///base64 of the wire format of the transaction
+fn serialize(transaction: &Transaction) -> CommandResult<String> {
+    Ok(base64::encode(bincode::serialize(transaction)?))
+}
+
+///Run a parsed command line
+pub fn run(cli: Cli) -> CommandResult {
+    let Cli { url, keypair, pool, dry_run, command } = cli;
+
+    //decoding doesn't sign anything, don't require a keypair
+    let command = match command {
+        Command::DecodeState(args) => return decode_state(&url, pool, &args),
+        command => command,
+    };
+
+    let context = Context {
+        rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+        payer: read_keypair(&keypair.unwrap_or_else(default_keypair_path))?,
+        pool: pool.unwrap_or_else(|| find_swap_state_address().0),
+        dry_run,
+    };
+
+    match command {
+        Command::InitPool(args) => init_pool(&context, args),
+        Command::AdminDeposit(args) => admin_deposit(&context, args),
+        Command::Deposit(args) => deposit(&context, args),
+        Command::Withdraw(args) => withdraw(&context, args),
+        Command::Swap(args) => swap(&context, args),
+        Command::ClosePool(args) => {
+            let admin = context.signer(&args.admin)?;
+            let pool = context.pool_accounts()?;
+            context.send(vec![pool.close_pool(&admin.pubkey())], &[&admin])
+        },
+        Command::ShowPool => show_pool(&context),
+        Command::Quote(args) => quote(&context, args.quote),
+        Command::DecodeState(_) => unreachable!("decoded above"),
+    }
+}
+
+///Create the accounts init-pool wasn't given, one transaction per account
+fn init_pool(context: &Context, args: InitPool) -> CommandResult {
+    let admin = context.signer(&args.admin)?;
+    let rpc = &context.rpc;
+    let swap_authority = bank_of_zion_client::pda::find_swap_authority_address().0;
+    let payer = context.payer.pubkey();
+
+    let mint_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+    let account_rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+
+    let create_mint = |mint: Option<Pubkey>| -> CommandResult<Pubkey> {
+        if let Some(mint) = mint {
+            return Ok(mint);
+        }
+        let keypair = Keypair::new();
+        context.send(
+            vec![
+                system_instruction::create_account(&payer, &keypair.pubkey(), mint_rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+                spl_token::instruction::initialize_mint(&spl_token::id(), &keypair.pubkey(), &swap_authority, None, args.decimals)?,
+            ],
+            &[&keypair],
+        )?;
+        println!("Created mint {}", keypair.pubkey());
+        Ok(keypair.pubkey())
+    };
+    let create_vault = |vault: Option<Pubkey>, mint: &Pubkey| -> CommandResult<Pubkey> {
+        if let Some(vault) = vault {
+            return Ok(vault);
+        }
+        let keypair = Keypair::new();
+        context.send(
+            vec![
+                system_instruction::create_account(&payer, &keypair.pubkey(), account_rent, spl_token::state::Account::LEN as u64, &spl_token::id()),
+                spl_token::instruction::initialize_account(&spl_token::id(), &keypair.pubkey(), mint, &swap_authority)?,
+            ],
+            &[&keypair],
+        )?;
+        println!("Created token account {} of mint {}", keypair.pubkey(), mint);
+        Ok(keypair.pubkey())
+    };
+
+    let swap_mint = create_mint(args.swap_mint)?;
+    let token_a_mint = create_mint(args.token_a_mint)?;
+    let token_a = TokenAccounts::new(
+        token_a_mint,
+        create_vault(args.token_a_vault, &token_a_mint)?,
+        create_vault(args.token_a_fee_vault, &token_a_mint)?,
+        args.token_a_oracle,
+    );
+    let token_b_mint = create_mint(args.token_b_mint)?;
+    let token_b = TokenAccounts::new(
+        token_b_mint,
+        create_vault(args.token_b_vault, &token_b_mint)?,
+        create_vault(args.token_b_fee_vault, &token_b_mint)?,
+        args.token_b_oracle,
+    );
+
+    let pool = PoolAccounts::new(swap_mint, token_a, token_b);
+    let params = InitializeParams {
+        program_fee: args.program_fee,
+        swap_fee: args.swap_fee,
+        curve_type: args.curve_type,
+        amp: args.amp,
+        premium_slope_bps: args.premium_slope_bps,
+        max_premium_bps: args.max_premium_bps,
+        max_discount_bps: args.max_discount_bps,
+        dead_band_bps: args.dead_band_bps,
+        max_trade_bps: args.max_trade_bps,
+        max_price_deviation_bps: args.max_price_deviation_bps,
+        breaker_max_jump_bps: args.breaker_max_jump_bps,
+        breaker_window_slots: args.breaker_window_slots,
+        breaker_cooldown_slots: args.breaker_cooldown_slots,
+        price_mode: args.price_mode,
+        twap_window_secs: args.twap_window_secs,
+        token_a_product: args.token_a_product.unwrap_or_default(),
+        token_b_product: args.token_b_product.unwrap_or_default(),
+        ..InitializeParams::default()
+    };
+    params.validate()?;
+
+    context.send(vec![pool.initialize(&admin.pubkey(), params)], &[&admin])?;
+    println!("Pool {}", pool.swap_state);
+    Ok(())
+}
+
+///Swap token wallet of the owner, created in the same transaction when it doesn't exist yet
+fn user_accounts(context: &Context, owner: &Keypair, pool: &PoolAccounts) -> (UserAccounts, Instruction) {
+    let user = UserAccounts::associated(&owner.pubkey(), pool);
+    let create_swap_wallet = create_associated_token_account_idempotent(
+        &context.payer.pubkey(),
+        &owner.pubkey(),
+        &pool.swap_mint,
+        &spl_token::id(),
+    );
+    (user, create_swap_wallet)
+}
+
+fn admin_deposit(context: &Context, args: Liquidity) -> CommandResult {
+    let admin = context.signer(&args.owner)?;
+    let pool = context.pool_accounts()?;
+    let (user, create_swap_wallet) = user_accounts(context, &admin, &pool);
+
+    context.send(
+        vec![create_swap_wallet, pool.admin_deposit(&user, args.amount_a, args.amount_b)],
+        &[&admin],
+    )
+}
+
+fn deposit(context: &Context, args: Liquidity) -> CommandResult {
+    let owner = context.signer(&args.owner)?;
+    let pool = context.pool_accounts()?;
+    let (user, create_swap_wallet) = user_accounts(context, &owner, &pool);
+
+    context.send(
+        vec![create_swap_wallet, pool.deposit(&user, args.amount_a, args.amount_b)],
+        &[&owner],
+    )
+}
+
+fn withdraw(context: &Context, args: Liquidity) -> CommandResult {
+    let owner = context.signer(&args.owner)?;
+    let pool = context.pool_accounts()?;
+    let user = UserAccounts::associated(&owner.pubkey(), &pool);
+
+    context.send(vec![pool.withdraw(&user, args.amount_a, args.amount_b)], &[&owner])
+}
+
+fn swap(context: &Context, args: SwapArgs) -> CommandResult {
+    let owner = context.signer(&args.owner)?;
+    let pool = context.pool_accounts()?;
+    let user = UserAccounts::associated(&owner.pubkey(), &pool);
+
+    context.send(vec![pool.swap(&user, args.direction, args.amount)], &[&owner])
+}
+
+fn show_pool(context: &Context) -> CommandResult {
+    let pool = context.pool_accounts()?;
+    let balance = |vault: &Pubkey| {
+        context
+            .rpc
+            .get_token_account_balance(vault)
+            .map(|balance| balance.ui_amount_string)
+            .unwrap_or_else(|err| format!("unavailable ({})", err))
+    };
+
+    println!("Swap state: {}", pool.swap_state);
+    println!("Swap authority: {} (bump {})", pool.swap_authority, pool.swap_authority_bump);
+    println!("Swap mint: {}", pool.swap_mint);
+    for (name, token) in [("Token A", &pool.token_a), ("Token B", &pool.token_b)] {
+        println!("{}:", name);
+        println!("  Mint: {}", token.mint);
+        println!("  Vault: {} ({})", token.vault, balance(&token.vault));
+        println!("  Fee vault: {} ({})", token.fee_vault, balance(&token.fee_vault));
+        println!("  Oracle: {}", token.oracle);
+        if let Some(fallback_oracle) = token.fallback_oracle {
+            println!("  Fallback oracle: {}", fallback_oracle);
+        }
+        for extra_oracle in &token.extra_oracles {
+            println!("  Extra oracle: {}", extra_oracle);
+        }
+    }
+    Ok(())
+}
+
+///Simulate the quote instruction and decode its return data, nothing is signed
+fn quote(context: &Context, quote: Quote) -> CommandResult {
+    let pool = context.pool_accounts()?;
+    let instruction = match quote {
+        Quote::Swap { direction, amount } => pool.quote(direction, amount),
+        Quote::Deposit { amount_a, amount_b } => pool.quote_deposit(amount_a, amount_b),
+        Quote::Withdraw { amount_a, amount_b } => pool.quote_withdraw(amount_a, amount_b),
+    };
+    let zion_instruction = ZionInstruction::unpack(&instruction.data)?;
+    let transaction = TransactionBuilder::new()
+        .instruction(instruction)
+        .unsigned(&context.payer.pubkey());
+
+    if context.dry_run {
+        println!("{}", serialize(&transaction)?);
+        return Ok(());
+    }
+
+    let result = context
+        .rpc
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    if let Some(err) = result.err {
+        for log in result.logs.unwrap_or_default() {
+            eprintln!("{}", log);
+        }
+        return Err(format!("quote failed: {}", err).into());
+    }
+
+    let (program_id, data) = match result.return_data {
+        Some(return_data) => (Pubkey::from_str(&return_data.program_id)?, base64::decode(&return_data.data.0)?),
+        None => (bank_of_zion::id(), Vec::new()),
+    };
+    match ReturnData::decode(&zion_instruction, &program_id, &data)? {
+        Some(ReturnData::Swap(quote)) => {
+            println!("Amount in: {}", quote.amount_in);
+            println!("Amount out: {}", quote.amount_out);
+            println!("Program fee: {}", quote.program_fee);
+            println!("Swap fee: {}", quote.swap_fee);
+            println!("Source price: {}", quote.source_price);
+            println!("Destination price: {}", quote.destination_price);
+            println!("Protocol price: {}", quote.protocol_price);
+            println!("Premium: {} bps", quote.premium_bps);
+        },
+        Some(ReturnData::Deposit(quote)) | Some(ReturnData::Withdraw(quote)) => {
+            println!("Token A amount: {}", quote.token_a_amount);
+            println!("Token B amount: {}", quote.token_b_amount);
+            println!("Swap tokens: {}", quote.swap_tokens);
+            println!("Program fee: {}", quote.program_fee);
+            println!("Swap fee: {}", quote.swap_fee);
+            println!("Token A price: {}", quote.token_a_price);
+            println!("Token B price: {}", quote.token_b_price);
+        },
+        None => return Err("the quote returned no data".into()),
+    }
+    Ok(())
+}
+
+///Decode raw swap state data from a file, base64 or the pool's account
+fn decode_state(url: &str, pool: Option<Pubkey>, args: &DecodeState) -> CommandResult {
+    let (data, address) = match (&args.file, &args.base64) {
+        (Some(file), _) => (std::fs::read(file)?, pool),
+        (None, Some(data)) => (base64::decode(data.trim())?, pool),
+        (None, None) => {
+            let pool = pool.unwrap_or_else(|| find_swap_state_address().0);
+            let rpc = RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed());
+            (rpc.get_account_data(&pool)?, Some(pool))
+        },
+    };
+
+    let decoded = decode_swap_state(&data, address.as_ref())?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&decoded.to_json())?);
+    } else {
+        print!("{}", decoded.to_text());
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! zion, command line tool to operate a bank_of_zion pool
+
+mod commands;
+
+use {
+    clap::{Args, Parser, Subcommand},
+    solana_program::pubkey::Pubkey,
+    std::{path::PathBuf, process::exit},
+};
+
+///Operate a bank_of_zion pool
+#[derive(Debug, Parser)]
+#[clap(name = "zion", version)]
+pub struct Cli {
+    ///JSON RPC url of the cluster
+    #[clap(long, short = 'u', global = true, default_value = "http://localhost:8899")]
+    pub url: String,
+
+    ///keypair paying the fees, defaults to the solana cli keypair
+    #[clap(long, short = 'k', global = true)]
+    pub keypair: Option<PathBuf>,
+
+    ///swap state of the pool, defaults to the program's swap state pda
+    #[clap(long, global = true)]
+    pub pool: Option<Pubkey>,
+
+    ///print the signed transactions as base64 instead of sending them
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+//parsed once per run, boxing the larger arguments saves nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    ///Create the pool, creating the mints and vaults that aren't given
+    InitPool(InitPool),
+    ///Deposit the admin's tokens at the market price
+    AdminDeposit(Liquidity),
+    ///Deposit tokens at the protocol price
+    Deposit(Liquidity),
+    ///Withdraw tokens of token a and token b for swap tokens
+    Withdraw(Liquidity),
+    ///Swap one of the pool's tokens for the other
+    Swap(SwapArgs),
+    ///Close the pool and refund its rent to the admin
+    ClosePool(Admin),
+    ///Print the pool's accounts and vault balances
+    ShowPool,
+    ///Simulate a quote instruction and print what the instruction would move
+    Quote(QuoteArgs),
+    ///Print every field of a swap state and flag inconsistencies, reads the pool from the cluster unless raw data is given
+    DecodeState(DecodeState),
+}
+
+#[derive(Debug, Args)]
+pub struct Admin {
+    ///admin keypair, defaults to the fee payer
+    #[clap(long)]
+    pub admin: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct InitPool {
+    ///admin keypair, defaults to the fee payer
+    #[clap(long)]
+    pub admin: Option<PathBuf>,
+
+    ///primary oracle of token a
+    #[clap(long)]
+    pub token_a_oracle: Pubkey,
+    ///primary oracle of token b
+    #[clap(long)]
+    pub token_b_oracle: Pubkey,
+    ///product token a's oracle has to price, any product when left out
+    #[clap(long)]
+    pub token_a_product: Option<Pubkey>,
+    ///product token b's oracle has to price, any product when left out
+    #[clap(long)]
+    pub token_b_product: Option<Pubkey>,
+
+    ///swap mint, its mint authority has to be the swap authority
+    #[clap(long)]
+    pub swap_mint: Option<Pubkey>,
+    ///token a mint, its mint authority has to be the swap authority
+    #[clap(long)]
+    pub token_a_mint: Option<Pubkey>,
+    ///token a vault owned by the swap authority
+    #[clap(long)]
+    pub token_a_vault: Option<Pubkey>,
+    ///token a fee vault owned by the swap authority
+    #[clap(long)]
+    pub token_a_fee_vault: Option<Pubkey>,
+    ///token b mint, its mint authority has to be the swap authority
+    #[clap(long)]
+    pub token_b_mint: Option<Pubkey>,
+    ///token b vault owned by the swap authority
+    #[clap(long)]
+    pub token_b_vault: Option<Pubkey>,
+    ///token b fee vault owned by the swap authority
+    #[clap(long)]
+    pub token_b_fee_vault: Option<Pubkey>,
+    ///decimals of the mints that are created
+    #[clap(long, default_value = "6")]
+    pub decimals: u8,
+
+    ///basis point fee given to the admin
+    #[clap(long, default_value = "0")]
+    pub program_fee: u64,
+    ///basis point fee given to the liquidity providers
+    #[clap(long, default_value = "0")]
+    pub swap_fee: u64,
+    ///pricing curve, 0 oracle premium, 1 constant product, 2 stable swap
+    #[clap(long, default_value = "0")]
+    pub curve_type: u8,
+    ///amplification coefficient of the StableSwap curve
+    #[clap(long, default_value = "0")]
+    pub amp: u64,
+    ///OraclePremium curve: basis points of the deviation from balance applied to the price
+    #[clap(long, default_value = "0")]
+    pub premium_slope_bps: u64,
+    ///OraclePremium curve: largest premium in basis points
+    #[clap(long, default_value = "0")]
+    pub max_premium_bps: u64,
+    ///OraclePremium curve: largest discount in basis points
+    #[clap(long, default_value = "0")]
+    pub max_discount_bps: u64,
+    ///OraclePremium curve: ignored deviation from balance in basis points
+    #[clap(long, default_value = "0")]
+    pub dead_band_bps: u64,
+    ///largest swap payout in basis points of the destination vault
+    #[clap(long, default_value = "0")]
+    pub max_trade_bps: u64,
+    ///largest deviation of the swap price from the oracle price in basis points
+    #[clap(long, default_value = "0")]
+    pub max_price_deviation_bps: u64,
+    ///circuit breaker: largest oracle price move in basis points
+    #[clap(long, default_value = "0")]
+    pub breaker_max_jump_bps: u64,
+    ///circuit breaker: slots a move has to happen in to count as a jump
+    #[clap(long, default_value = "0")]
+    pub breaker_window_slots: u64,
+    ///circuit breaker: slots after tripping the pool resumes on its own
+    #[clap(long, default_value = "0")]
+    pub breaker_cooldown_slots: u64,
+    ///price mode, 0 spot, 1 conservative, 2 ema
+    #[clap(long, default_value = "0")]
+    pub price_mode: u8,
+    ///seconds the TWAP of the conservative price mode is taken over, at most a day
+    #[clap(long, default_value = "0")]
+    pub twap_window_secs: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct Liquidity {
+    ///owner of the wallets, defaults to the fee payer
+    #[clap(long)]
+    pub owner: Option<PathBuf>,
+    ///tokens of token a
+    #[clap(long, default_value = "0")]
+    pub amount_a: u64,
+    ///tokens of token b
+    #[clap(long, default_value = "0")]
+    pub amount_b: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct SwapArgs {
+    ///owner of the wallets, defaults to the fee payer
+    #[clap(long)]
+    pub owner: Option<PathBuf>,
+    ///a-to-b or b-to-a
+    #[clap(long, parse(try_from_str = commands::parse_direction))]
+    pub direction: bank_of_zion_client::SwapDirection,
+    ///source tokens to pay in
+    #[clap(long)]
+    pub amount: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct QuoteArgs {
+    #[clap(subcommand)]
+    pub quote: Quote,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Quote {
+    ///Quote a swap
+    Swap {
+        ///a-to-b or b-to-a
+        #[clap(long, parse(try_from_str = commands::parse_direction))]
+        direction: bank_of_zion_client::SwapDirection,
+        ///source tokens to pay in
+        #[clap(long)]
+        amount: u64,
+    },
+    ///Quote a deposit
+    Deposit {
+        ///tokens of token a
+        #[clap(long, default_value = "0")]
+        amount_a: u64,
+        ///tokens of token b
+        #[clap(long, default_value = "0")]
+        amount_b: u64,
+    },
+    ///Quote a withdraw
+    Withdraw {
+        ///tokens of token a
+        #[clap(long, default_value = "0")]
+        amount_a: u64,
+        ///tokens of token b
+        #[clap(long, default_value = "0")]
+        amount_b: u64,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct DecodeState {
+    ///file holding the raw account data
+    #[clap(long, conflicts_with = "base64")]
+    pub file: Option<PathBuf>,
+    ///raw account data as base64
+    #[clap(long)]
+    pub base64: Option<String>,
+    ///print JSON instead of text
+    #[clap(long)]
+    pub json: bool,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = commands::run(cli) {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
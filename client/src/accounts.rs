@@ -0,0 +1,193 @@
+use {
+    crate::pda::{find_price_history_address, find_swap_authority_address, find_swap_state_address},
+    bank_of_zion::state::{OracleConfig, PriceMode, SwapState, Token},
+    solana_program::{program_error::ProgramError, pubkey::Pubkey},
+    spl_associated_token_account::get_associated_token_address,
+};
+
+///Accounts of one of the pool's tokens
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenAccounts {
+    ///mint of the token
+    pub mint: Pubkey,
+    ///vault holding the pool's tokens
+    pub vault: Pubkey,
+    ///vault holding the fees taken in the token
+    pub fee_vault: Pubkey,
+    ///primary oracle of the token
+    pub oracle: Pubkey,
+    ///oracle used when the primary oracle isn't fresh
+    pub fallback_oracle: Option<Pubkey>,
+    ///oracles the token's price is aggregated over with the primary oracle
+    pub extra_oracles: Vec<Pubkey>,
+}
+
+impl TokenAccounts {
+    ///Token priced by a single oracle
+    pub fn new(mint: Pubkey, vault: Pubkey, fee_vault: Pubkey, oracle: Pubkey) -> Self {
+        Self {
+            mint,
+            vault,
+            fee_vault,
+            oracle,
+            ..Self::default()
+        }
+    }
+
+    ///Token accounts stored in a swap state
+    pub fn from_swap_state(token: &Token, fallback_oracle: &Pubkey, oracle_config: &OracleConfig) -> Self {
+        Self {
+            mint: token.mint,
+            vault: token.vault,
+            fee_vault: token.fee_vault,
+            oracle: token.oracle,
+            fallback_oracle: Some(*fallback_oracle).filter(|oracle| oracle != &Pubkey::default()),
+            extra_oracles: oracle_config.extra_oracles().to_vec(),
+        }
+    }
+
+    ///Oracles the program reads from the accounts passed after the fixed ones, the fallback first
+    pub fn remaining_oracles(&self) -> impl Iterator<Item = &Pubkey> {
+        self.fallback_oracle.iter().chain(self.extra_oracles.iter())
+    }
+}
+
+///Accounts of a pool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoolAccounts {
+    ///swap state pda
+    pub swap_state: Pubkey,
+    ///bump of the swap state pda
+    pub swap_state_bump: u8,
+    ///swap authority pda
+    pub swap_authority: Pubkey,
+    ///bump of the swap authority pda
+    pub swap_authority_bump: u8,
+    ///mint of the swap tokens
+    pub swap_mint: Pubkey,
+    ///accounts of token a
+    pub token_a: TokenAccounts,
+    ///accounts of token b
+    pub token_b: TokenAccounts,
+    ///price history passed to the instructions that record or read it, None to leave it out
+    pub price_history: Option<Pubkey>,
+}
+
+impl PoolAccounts {
+    ///Accounts of a pool that doesn't exist yet, the pdas are derived
+    pub fn new(swap_mint: Pubkey, token_a: TokenAccounts, token_b: TokenAccounts) -> Self {
+        let (swap_state, swap_state_bump) = find_swap_state_address();
+        let (swap_authority, swap_authority_bump) = find_swap_authority_address();
+
+        Self {
+            swap_state,
+            swap_state_bump,
+            swap_authority,
+            swap_authority_bump,
+            swap_mint,
+            token_a,
+            token_b,
+            price_history: None,
+        }
+    }
+
+    ///Accounts of an existing pool. The price history is included when the pool's price mode requires it
+    pub fn from_swap_state(swap_state: &SwapState) -> Self {
+        let (swap_state_key, _) = find_swap_state_address();
+        let price_history = match PriceMode::try_from(swap_state.price_mode) {
+            Ok(PriceMode::Conservative) => Some(find_price_history_address().0),
+            _ => None,
+        };
+
+        Self {
+            swap_state: swap_state_key,
+            swap_state_bump: swap_state.bump,
+            swap_authority: swap_state.swap_authority,
+            swap_authority_bump: swap_state.swap_authority_bump,
+            swap_mint: swap_state.swap_mint,
+            token_a: TokenAccounts::from_swap_state(&swap_state.token_a, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles),
+            token_b: TokenAccounts::from_swap_state(&swap_state.token_b, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles),
+            price_history,
+        }
+    }
+
+    ///Accounts of an existing pool read from the swap state's account data
+    pub fn from_account_data(data: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self::from_swap_state(SwapState::load(data)?))
+    }
+
+    ///Pass the price history to the instructions that record or read it, it has to be created by the crank first
+    pub fn with_price_history(mut self) -> Self {
+        self.price_history = Some(find_price_history_address().0);
+        self
+    }
+
+    ///Accounts of one of the pool's tokens
+    pub fn token(&self, token: PoolToken) -> &TokenAccounts {
+        match token {
+            PoolToken::A => &self.token_a,
+            PoolToken::B => &self.token_b,
+        }
+    }
+
+    ///Accounts of the token swapped in and the token swapped out
+    pub fn swap_tokens(&self, direction: SwapDirection) -> (&TokenAccounts, &TokenAccounts) {
+        match direction {
+            SwapDirection::AToB => (&self.token_a, &self.token_b),
+            SwapDirection::BToA => (&self.token_b, &self.token_a),
+        }
+    }
+}
+
+///Wallets of a user trading with or providing liquidity to a pool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UserAccounts {
+    ///owner and signer
+    pub owner: Pubkey,
+    ///swap token account
+    pub swap_wallet: Pubkey,
+    ///token a account
+    pub token_a_wallet: Pubkey,
+    ///token b account
+    pub token_b_wallet: Pubkey,
+}
+
+impl UserAccounts {
+    ///The owner's associated token accounts for the pool's mints
+    pub fn associated(owner: &Pubkey, pool: &PoolAccounts) -> Self {
+        Self {
+            owner: *owner,
+            swap_wallet: get_associated_token_address(owner, &pool.swap_mint),
+            token_a_wallet: get_associated_token_address(owner, &pool.token_a.mint),
+            token_b_wallet: get_associated_token_address(owner, &pool.token_b.mint),
+        }
+    }
+
+    ///Wallets of the token swapped in and the token swapped out
+    pub fn swap_wallets(&self, direction: SwapDirection) -> (&Pubkey, &Pubkey) {
+        match direction {
+            SwapDirection::AToB => (&self.token_a_wallet, &self.token_b_wallet),
+            SwapDirection::BToA => (&self.token_b_wallet, &self.token_a_wallet),
+        }
+    }
+}
+
+///Direction of a swap
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapDirection {
+    ///pay token a, receive token b
+    AToB = 0,
+    ///pay token b, receive token a
+    BToA = 1,
+}
+
+///One of the pool's tokens, for the admin instructions configuring a single token
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolToken {
+    ///token a
+    A = 0,
+    ///token b
+    B = 1,
+}
@@ -0,0 +1,350 @@
+use {
+    crate::pda::{find_swap_state_address, swap_authority_address},
+    bank_of_zion::{
+        error::ZionError,
+        instructions::InitializeParams,
+        state::{AcceptedPrice, OracleConfig, SwapState, Token},
+    },
+    num_traits::FromPrimitive,
+    serde_json::{json, Map, Value},
+    solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey},
+};
+
+///A swap state decoded from raw account data, with the inconsistencies found in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedSwapState {
+    ///address the data was read from, if known
+    pub address: Option<Pubkey>,
+    ///the decoded state
+    pub swap_state: SwapState,
+    ///inconsistencies between the fields, empty for a healthy pool
+    pub issues: Vec<String>,
+}
+
+///Decode a swap state from its account data and check it for inconsistencies. Data still in the v1 layout
+///is decoded as well and flagged, the program only reads it after a MigrateState
+pub fn decode_swap_state(data: &[u8], address: Option<&Pubkey>) -> Result<DecodedSwapState, ProgramError> {
+    let mut issues = Vec::new();
+    let swap_state = if data.len() == SwapState::LEN_V1 {
+        issues.push("the account is in the v1 layout, MigrateState has to rewrite it before the program reads it".to_string());
+        SwapState::unpack_v1(data)?
+    } else {
+        SwapState::unpack_from_slice(data)?
+    };
+
+    check_swap_state(&swap_state, address, &mut issues);
+
+    Ok(DecodedSwapState {
+        address: address.copied(),
+        swap_state,
+        issues,
+    })
+}
+
+///describe a program error, with the message of the program's own errors
+fn describe(err: ProgramError) -> String {
+    match err {
+        ProgramError::Custom(code) => match ZionError::from_u32(code) {
+            Some(err) => err.to_string(),
+            None => format!("custom program error {}", code),
+        },
+        err => err.to_string(),
+    }
+}
+
+fn check_swap_state(swap_state: &SwapState, address: Option<&Pubkey>, issues: &mut Vec<String>) {
+    if !bool::from(swap_state.is_initialized) {
+        issues.push("the pool isn't initialized".to_string());
+    }
+
+    //pdas
+    let (swap_state_key, swap_state_bump) = find_swap_state_address();
+    if let Some(address) = address {
+        if address != &swap_state_key {
+            issues.push(format!("address {} isn't the swap state pda {}", address, swap_state_key));
+        }
+    }
+    if swap_state.bump != swap_state_bump {
+        issues.push(format!("bump {} isn't the bump of the swap state pda, expected {}", swap_state.bump, swap_state_bump));
+    }
+    match swap_authority_address(swap_state.swap_authority_bump) {
+        Ok(swap_authority) if swap_authority != swap_state.swap_authority => issues.push(format!(
+            "swap authority {} doesn't match the pda {} derived from swap_authority_bump {}",
+            swap_state.swap_authority, swap_authority, swap_state.swap_authority_bump,
+        )),
+        Ok(_) => {},
+        Err(_) => issues.push(format!(
+            "swap_authority_bump {} doesn't derive a valid swap authority",
+            swap_state.swap_authority_bump,
+        )),
+    }
+
+    //accounts
+    if swap_state.token_a.mint == swap_state.token_b.mint {
+        issues.push("token a and token b have the same mint".to_string());
+    }
+    if swap_state.token_a.oracle == swap_state.token_b.oracle {
+        issues.push("token a and token b have the same oracle".to_string());
+    }
+    let vaults = [
+        ("token a vault", &swap_state.token_a.vault),
+        ("token a fee vault", &swap_state.token_a.fee_vault),
+        ("token b vault", &swap_state.token_b.vault),
+        ("token b fee vault", &swap_state.token_b.fee_vault),
+    ];
+    for (i, (name, vault)) in vaults.iter().enumerate() {
+        if let Some((other, _)) = vaults[i + 1..].iter().find(|(_, other)| other == vault) {
+            issues.push(format!("the {} is also the {}", name, other));
+        }
+    }
+    for (name, key) in [
+        ("swap mint", &swap_state.swap_mint),
+        ("token a mint", &swap_state.token_a.mint),
+        ("token b mint", &swap_state.token_b.mint),
+        ("token a oracle", &swap_state.token_a.oracle),
+        ("token b oracle", &swap_state.token_b.oracle),
+    ] {
+        if key == &Pubkey::default() {
+            issues.push(format!("the {} isn't set", name));
+        }
+    }
+
+    //oracles
+    for (name, token, oracles, fallback_oracle) in [
+        ("token a", &swap_state.token_a, &swap_state.token_a_oracles, &swap_state.token_a_fallback_oracle),
+        ("token b", &swap_state.token_b, &swap_state.token_b_oracles, &swap_state.token_b_fallback_oracle),
+    ] {
+        if let Err(err) = oracles.validate(&token.oracle) {
+            issues.push(format!("{} oracle config: {}", name, err));
+        }
+        if fallback_oracle == &token.oracle {
+            issues.push(format!("the {} fallback oracle is its primary oracle", name));
+        }
+        if oracles.extra_oracles().contains(fallback_oracle) {
+            issues.push(format!("the {} fallback oracle is one of its extra oracles", name));
+        }
+        if fallback_oracle != &Pubkey::default() && u64::from(oracles.max_age_secs) == 0 {
+            issues.push(format!("the {} fallback oracle is never read, its oracles have no max age", name));
+        }
+    }
+
+    //settings, the same checks the pool was initialized with
+    let settings = InitializeParams {
+        program_fee: swap_state.program_fee.into(),
+        swap_fee: swap_state.swap_fee.into(),
+        curve_type: swap_state.curve_type,
+        amp: swap_state.amp.into(),
+        premium_slope_bps: swap_state.premium.slope_bps.into(),
+        max_premium_bps: swap_state.premium.max_premium_bps.into(),
+        max_discount_bps: swap_state.premium.max_discount_bps.into(),
+        dead_band_bps: swap_state.premium.dead_band_bps.into(),
+        max_trade_bps: swap_state.max_trade_bps.into(),
+        max_price_deviation_bps: swap_state.max_price_deviation_bps.into(),
+        price_mode: swap_state.price_mode,
+        twap_window_secs: swap_state.twap_window_secs.into(),
+        ..InitializeParams::default()
+    };
+    if let Err(err) = settings.validate() {
+        issues.push(format!("settings: {}", describe(err)));
+    }
+
+    if swap_state.reserved.iter().any(|byte| *byte != 0) {
+        issues.push("the reserved space isn't zero".to_string());
+    }
+}
+
+fn token_json(token: &Token, oracles: &OracleConfig, fallback_oracle: &Pubkey) -> Value {
+    json!({
+        "mint": token.mint.to_string(),
+        "vault": token.vault.to_string(),
+        "fee_vault": token.fee_vault.to_string(),
+        "oracle": token.oracle.to_string(),
+        "fallback_oracle": fallback_oracle.to_string(),
+        "extra_oracles": oracles.extra_oracles().iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+        "num_extra_oracles": oracles.num_extra_oracles,
+        "quorum": oracles.quorum,
+        "max_age_secs": u64::from(oracles.max_age_secs),
+        "max_confidence_bps": u64::from(oracles.max_confidence_bps),
+    })
+}
+
+fn accepted_price_json(accepted_price: &AcceptedPrice) -> Value {
+    json!({
+        "price": u64::from(accepted_price.price),
+        "slot": u64::from(accepted_price.slot),
+    })
+}
+
+impl DecodedSwapState {
+    ///Every field of the swap state and the issues found, in layout order
+    pub fn to_json(&self) -> Value {
+        let swap_state = &self.swap_state;
+        let breaker = &swap_state.breaker;
+
+        json!({
+            "address": self.address.map(|address| address.to_string()),
+            "version": swap_state.version,
+            "admin": swap_state.admin.to_string(),
+            "bump": swap_state.bump,
+            "is_initialized": bool::from(swap_state.is_initialized),
+            "swap_authority": swap_state.swap_authority.to_string(),
+            "swap_authority_bump": swap_state.swap_authority_bump,
+            "swap_mint": swap_state.swap_mint.to_string(),
+            "token_a": token_json(&swap_state.token_a, &swap_state.token_a_oracles, &swap_state.token_a_fallback_oracle),
+            "token_b": token_json(&swap_state.token_b, &swap_state.token_b_oracles, &swap_state.token_b_fallback_oracle),
+            "program_fee": u64::from(swap_state.program_fee),
+            "swap_fee": u64::from(swap_state.swap_fee),
+            "curve_type": swap_state.curve_type,
+            "amp": u64::from(swap_state.amp),
+            "premium": {
+                "slope_bps": u64::from(swap_state.premium.slope_bps),
+                "max_premium_bps": u64::from(swap_state.premium.max_premium_bps),
+                "max_discount_bps": u64::from(swap_state.premium.max_discount_bps),
+                "dead_band_bps": u64::from(swap_state.premium.dead_band_bps),
+            },
+            "max_trade_bps": u64::from(swap_state.max_trade_bps),
+            "max_price_deviation_bps": u64::from(swap_state.max_price_deviation_bps),
+            "breaker": {
+                "max_jump_bps": u64::from(breaker.max_jump_bps),
+                "window_slots": u64::from(breaker.window_slots),
+                "cooldown_slots": u64::from(breaker.cooldown_slots),
+                "tripped_slot": u64::from(breaker.tripped_slot),
+                "is_tripped": bool::from(breaker.is_tripped),
+                "token_a_price": accepted_price_json(&breaker.token_a_price),
+                "token_b_price": accepted_price_json(&breaker.token_b_price),
+            },
+            "price_mode": swap_state.price_mode,
+            "twap_window_secs": u64::from(swap_state.twap_window_secs),
+            "issues": self.issues,
+        })
+    }
+
+    ///Every field of the swap state and the issues found as indented text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Value::Object(fields) = self.to_json() {
+            write_text(&mut text, &fields, 0);
+        }
+        text
+    }
+}
+
+fn write_text(text: &mut String, fields: &Map<String, Value>, indent: usize) {
+    let pad = " ".repeat(indent);
+    for (name, value) in fields {
+        match value {
+            Value::Object(fields) => {
+                text.push_str(&format!("{}{}:\n", pad, name));
+                write_text(text, fields, indent + 2);
+            },
+            Value::Array(values) if values.is_empty() => text.push_str(&format!("{}{}: none\n", pad, name)),
+            Value::Array(values) => {
+                text.push_str(&format!("{}{}:\n", pad, name));
+                for value in values {
+                    text.push_str(&format!("{}  - {}\n", pad, plain(value)));
+                }
+            },
+            value => text.push_str(&format!("{}{}: {}\n", pad, name, plain(value))),
+        }
+    }
+}
+
+///strings without their quotes
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => "none".to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::decode_swap_state,
+        crate::pda::{find_swap_authority_address, find_swap_state_address},
+        bank_of_zion::{
+            pod::PodU64,
+            state::{SwapState, Token},
+        },
+        solana_program::{program_pack::Pack, pubkey::Pubkey},
+    };
+
+    fn token() -> Token {
+        Token {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+        }
+    }
+
+    fn swap_state() -> SwapState {
+        let (swap_authority, swap_authority_bump) = find_swap_authority_address();
+        SwapState {
+            version: SwapState::VERSION,
+            admin: Pubkey::new_unique(),
+            bump: find_swap_state_address().1,
+            is_initialized: true.into(),
+            swap_authority,
+            swap_authority_bump,
+            swap_mint: Pubkey::new_unique(),
+            token_a: token(),
+            token_b: token(),
+            program_fee: PodU64::from(100),
+            swap_fee: PodU64::from(30),
+            ..SwapState::default()
+        }
+    }
+
+    fn pack(swap_state: &SwapState) -> Vec<u8> {
+        let mut data = vec![0u8; SwapState::LEN];
+        swap_state.pack_into_slice(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_decode_healthy_pool() {
+        let expected = swap_state();
+        let address = find_swap_state_address().0;
+        let decoded = decode_swap_state(&pack(&expected), Some(&address)).unwrap();
+        assert_eq!(decoded.swap_state, expected);
+        assert_eq!(decoded.issues, Vec::<String>::new());
+
+        let json = decoded.to_json();
+        assert_eq!(json["swap_authority"], expected.swap_authority.to_string());
+        assert_eq!(json["token_b"]["fee_vault"], expected.token_b.fee_vault.to_string());
+        assert_eq!(json["program_fee"], 100);
+
+        let text = decoded.to_text();
+        assert!(text.contains(&format!("admin: {}\n", expected.admin)));
+        assert!(text.contains(&format!("token_a:\n  mint: {}\n", expected.token_a.mint)));
+        assert!(text.ends_with("issues: none\n"));
+    }
+
+    #[test]
+    fn test_decode_flags_inconsistencies() {
+        let mut swap_state = swap_state();
+        swap_state.swap_authority = Pubkey::new_unique();
+        swap_state.token_b.fee_vault = swap_state.token_a.vault;
+        swap_state.program_fee = PodU64::from(10_001);
+
+        let decoded = decode_swap_state(&pack(&swap_state), Some(&Pubkey::new_unique())).unwrap();
+        assert_eq!(decoded.issues.len(), 4, "{:?}", decoded.issues);
+        assert!(decoded.issues[0].starts_with("address"));
+        assert!(decoded.issues[1].starts_with(&format!("swap authority {} doesn't match the pda", swap_state.swap_authority)));
+        assert_eq!(decoded.issues[2], "the token a vault is also the token b fee vault");
+        assert!(decoded.issues[3].starts_with("settings:"));
+        assert!(decoded.to_text().contains(&format!("issues:\n  - {}\n", decoded.issues[0])));
+    }
+
+    #[test]
+    fn test_decode_v1_layout() {
+        let swap_state = swap_state();
+        let data = pack(&swap_state);
+        let decoded = decode_swap_state(&data[1..1 + SwapState::LEN_V1], None).unwrap();
+        assert_eq!(decoded.swap_state.version, SwapState::VERSION_V1);
+        assert_eq!(decoded.swap_state.admin, swap_state.admin);
+        assert!(decoded.issues[0].contains("v1 layout"));
+    }
+}
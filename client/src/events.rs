@@ -0,0 +1,87 @@
+use {
+    bank_of_zion::events::Event,
+    solana_program::{program_error::ProgramError, pubkey::Pubkey},
+    std::str::FromStr,
+};
+
+///Log prefix of the data logged with sol_log_data
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+///Events logged by the program in a transaction's log messages, in order.
+///Data logged by other programs, including programs the program invokes, is skipped
+pub fn decode_logs<S: AsRef<str>>(logs: &[S]) -> Result<Vec<Event>, ProgramError> {
+    let program_id = bank_of_zion::id();
+    let mut invoked: Vec<Pubkey> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        let log = log.as_ref();
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if invoked.last() == Some(&program_id) {
+                for field in data.split(' ') {
+                    let field = base64::decode(field).map_err(|_| ProgramError::InvalidAccountData)?;
+                    events.push(Event::unpack(&field)?);
+                }
+            }
+            continue;
+        }
+
+        //"Program <id> invoke [<depth>]" pushes, "Program <id> success" and "Program <id> failed: <error>" pop
+        let mut words = log.split(' ');
+        if words.next() != Some("Program") {
+            continue;
+        }
+        let id = match words.next().map(Pubkey::from_str) {
+            Some(Ok(id)) => id,
+            _ => continue,
+        };
+        match words.next() {
+            Some("invoke") => invoked.push(id),
+            Some("success") | Some("failed:") => {
+                invoked.pop();
+            },
+            _ => {},
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::decode_logs,
+        bank_of_zion::{
+            events::{Event, Swapped, EVENT_VERSION},
+            pod::PodU64,
+        },
+        solana_program::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_decode_logs() {
+        let program_id = bank_of_zion::id();
+        let token_program_id = spl_token::id();
+        let swapped = Event::Swapped(Swapped {
+            user: Pubkey::new_unique(),
+            amount_in: PodU64::from(10),
+            amount_out: PodU64::from(9),
+            ..Swapped::default()
+        });
+        let data = base64::encode(swapped.pack());
+
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Instruction: Swap".to_string(),
+            format!("Program {} invoke [2]", token_program_id),
+            format!("Program data: {}", base64::encode([EVENT_VERSION, 42])),
+            format!("Program {} success", token_program_id),
+            format!("Program data: {}", data),
+            format!("Program return: {} AAAA", program_id),
+            format!("Program {} consumed 100 of 200000 compute units", program_id),
+            format!("Program {} success", program_id),
+            format!("Program data: {}", data),
+        ];
+        assert_eq!(decode_logs(&logs), Ok(vec![swapped]));
+    }
+}
@@ -0,0 +1,319 @@
+use {
+    crate::{
+        accounts::{PoolAccounts, PoolToken, SwapDirection, UserAccounts},
+        pda::find_price_history_address,
+    },
+    bank_of_zion::instructions::{self, InitializeParams},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+};
+
+impl PoolAccounts {
+    ///fallback and extra oracles of both tokens, passed after the fixed accounts
+    fn append_oracles(&self, mut instruction: Instruction) -> Instruction {
+        let mut appended: Vec<Pubkey> = Vec::new();
+        for oracle in self.token_a.remaining_oracles().chain(self.token_b.remaining_oracles()) {
+            if !appended.contains(oracle) {
+                appended.push(*oracle);
+                instruction.accounts.push(AccountMeta::new_readonly(*oracle, false));
+            }
+        }
+        instruction
+    }
+
+    ///price history passed after the oracles when the pool has one
+    fn append_price_history(&self, mut instruction: Instruction, is_writable: bool) -> Instruction {
+        if let Some(price_history) = self.price_history {
+            instruction.accounts.push(AccountMeta {
+                pubkey: price_history,
+                is_signer: false,
+                is_writable,
+            });
+        }
+        instruction
+    }
+
+    ///Creates an 'initialize' instruction, the bumps of the params are set to the pool's bumps
+    pub fn initialize(&self, admin: &Pubkey, mut params: InitializeParams) -> Instruction {
+        params.bump = self.swap_state_bump;
+        params.swap_authority_bump = self.swap_authority_bump;
+
+        instructions::initialize(
+            admin,
+            &self.swap_authority,
+            &self.swap_mint,
+            &self.swap_state,
+            &self.token_a.mint,
+            &self.token_a.vault,
+            &self.token_a.fee_vault,
+            &self.token_a.oracle,
+            &self.token_b.mint,
+            &self.token_b.vault,
+            &self.token_b.fee_vault,
+            &self.token_b.oracle,
+            params,
+        )
+    }
+
+    ///Creates an 'admin_deposit' instruction depositing from the admin's wallets
+    pub fn admin_deposit(&self, admin: &UserAccounts, token_a_deposit: u64, token_b_deposit: u64) -> Instruction {
+        self.append_oracles(instructions::admin_deposit(
+            &admin.owner,
+            &self.swap_authority,
+            &self.swap_mint,
+            &self.swap_state,
+            &admin.swap_wallet,
+            &self.token_a.mint,
+            &admin.token_a_wallet,
+            &self.token_a.vault,
+            &self.token_a.oracle,
+            &self.token_b.mint,
+            &admin.token_b_wallet,
+            &self.token_b.vault,
+            &self.token_b.oracle,
+            token_a_deposit,
+            token_b_deposit,
+        ))
+    }
+
+    ///Creates a 'deposit' instruction, the oracle prices are recorded in the price history when the pool has one
+    pub fn deposit(&self, user: &UserAccounts, token_a_deposit: u64, token_b_deposit: u64) -> Instruction {
+        let instruction = self.append_oracles(instructions::deposit(
+            &user.owner,
+            &self.swap_state,
+            &self.swap_authority,
+            &self.swap_mint,
+            &user.swap_wallet,
+            &user.token_a_wallet,
+            &self.token_a.vault,
+            &self.token_a.fee_vault,
+            &self.token_a.oracle,
+            &user.token_b_wallet,
+            &self.token_b.vault,
+            &self.token_b.fee_vault,
+            &self.token_b.oracle,
+            token_a_deposit,
+            token_b_deposit,
+        ));
+        self.append_price_history(instruction, true)
+    }
+
+    ///Creates a 'withdraw' instruction
+    pub fn withdraw(&self, user: &UserAccounts, token_a_withdraw: u64, token_b_withdraw: u64) -> Instruction {
+        self.append_oracles(instructions::withdraw(
+            &user.owner,
+            &self.swap_state,
+            &self.swap_authority,
+            &self.swap_mint,
+            &user.swap_wallet,
+            &user.token_a_wallet,
+            &self.token_a.vault,
+            &self.token_a.fee_vault,
+            &self.token_a.oracle,
+            &user.token_b_wallet,
+            &self.token_b.vault,
+            &self.token_b.fee_vault,
+            &self.token_b.oracle,
+            token_a_withdraw,
+            token_b_withdraw,
+        ))
+    }
+
+    ///Creates a 'swap' instruction paying amount source tokens, the oracle prices are recorded in the price history when the pool has one
+    pub fn swap(&self, user: &UserAccounts, direction: SwapDirection, amount: u64) -> Instruction {
+        let (source, destination) = self.swap_tokens(direction);
+        let (source_wallet, destination_wallet) = user.swap_wallets(direction);
+
+        let instruction = self.append_oracles(instructions::swap(
+            &user.owner,
+            &self.swap_state,
+            &self.swap_authority,
+            source_wallet,
+            &source.vault,
+            &source.fee_vault,
+            &source.oracle,
+            destination_wallet,
+            &destination.vault,
+            &destination.fee_vault,
+            &destination.oracle,
+            amount,
+        ));
+        self.append_price_history(instruction, true)
+    }
+
+    ///Creates a 'close_pool' instruction
+    pub fn close_pool(&self, admin: &Pubkey) -> Instruction {
+        instructions::close_pool(admin, &self.swap_state, &self.swap_authority)
+    }
+
+    ///Creates a 'migrate_state' instruction
+    pub fn migrate_state(&self, admin: &Pubkey) -> Instruction {
+        instructions::migrate_state(admin, &self.swap_state)
+    }
+
+    ///Creates a 'reset_circuit_breaker' instruction
+    pub fn reset_circuit_breaker(&self, admin: &Pubkey) -> Instruction {
+        instructions::reset_circuit_breaker(admin, &self.swap_state)
+    }
+
+    ///Creates a 'crank' instruction, the price history is derived even when the pool doesn't pass it to other instructions
+    pub fn crank(&self, payer: &Pubkey) -> Instruction {
+        let (price_history, _) = find_price_history_address();
+
+        self.append_oracles(instructions::crank(
+            payer,
+            &self.swap_state,
+            &price_history,
+            &self.token_a.oracle,
+            &self.token_b.oracle,
+        ))
+    }
+
+    ///Creates a 'set_oracle_config' instruction
+    pub fn set_oracle_config(
+        &self,
+        admin: &Pubkey,
+        token: PoolToken,
+        extra_oracles: &[Pubkey],
+        quorum: u8,
+        max_age_secs: u64,
+        max_confidence_bps: u64,
+    ) -> Instruction {
+        instructions::set_oracle_config(
+            admin,
+            &self.swap_state,
+            extra_oracles,
+            token as u8,
+            quorum,
+            max_age_secs,
+            max_confidence_bps,
+        )
+    }
+
+    ///Creates a 'set_fallback_oracle' instruction, None removes the token's fallback oracle
+    pub fn set_fallback_oracle(&self, admin: &Pubkey, token: PoolToken, fallback_oracle: Option<&Pubkey>) -> Instruction {
+        instructions::set_fallback_oracle(admin, &self.swap_state, fallback_oracle, token as u8)
+    }
+
+    ///Creates a 'quote' instruction for a swap of amount source tokens
+    pub fn quote(&self, direction: SwapDirection, amount: u64) -> Instruction {
+        let instruction = self.append_oracles(instructions::quote(
+            &self.swap_state,
+            &self.token_a.vault,
+            &self.token_a.oracle,
+            &self.token_b.vault,
+            &self.token_b.oracle,
+            direction as u8,
+            amount,
+        ));
+        self.append_price_history(instruction, false)
+    }
+
+    ///Creates a 'quote_deposit' instruction
+    pub fn quote_deposit(&self, token_a_deposit: u64, token_b_deposit: u64) -> Instruction {
+        self.append_oracles(instructions::quote_deposit(
+            &self.swap_state,
+            &self.swap_mint,
+            &self.token_a.vault,
+            &self.token_a.fee_vault,
+            &self.token_a.oracle,
+            &self.token_b.vault,
+            &self.token_b.fee_vault,
+            &self.token_b.oracle,
+            token_a_deposit,
+            token_b_deposit,
+        ))
+    }
+
+    ///Creates a 'quote_withdraw' instruction
+    pub fn quote_withdraw(&self, token_a_withdraw: u64, token_b_withdraw: u64) -> Instruction {
+        self.append_oracles(instructions::quote_withdraw(
+            &self.swap_state,
+            &self.swap_mint,
+            &self.token_a.vault,
+            &self.token_a.fee_vault,
+            &self.token_a.oracle,
+            &self.token_b.vault,
+            &self.token_b.fee_vault,
+            &self.token_b.oracle,
+            token_a_withdraw,
+            token_b_withdraw,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            accounts::{PoolAccounts, SwapDirection, TokenAccounts, UserAccounts},
+            pda::find_price_history_address,
+        },
+        bank_of_zion::instructions::{self, Swap, ZionInstruction},
+        solana_program::{instruction::AccountMeta, pubkey::Pubkey},
+    };
+
+    fn token() -> TokenAccounts {
+        TokenAccounts::new(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique())
+    }
+
+    #[test]
+    fn test_swap_accounts() {
+        let fallback_oracle = Pubkey::new_unique();
+        let extra_oracle = Pubkey::new_unique();
+        let mut pool = PoolAccounts::new(Pubkey::new_unique(), token(), token());
+        pool.token_a.extra_oracles = vec![extra_oracle];
+        pool.token_b.fallback_oracle = Some(fallback_oracle);
+        //an oracle shared by both tokens is passed once
+        pool.token_b.extra_oracles = vec![extra_oracle];
+        let pool = pool.with_price_history();
+        let user = UserAccounts::associated(&Pubkey::new_unique(), &pool);
+
+        let instruction = pool.swap(&user, SwapDirection::BToA, 100);
+        let expected = instructions::swap(
+            &user.owner,
+            &pool.swap_state,
+            &pool.swap_authority,
+            &user.token_b_wallet,
+            &pool.token_b.vault,
+            &pool.token_b.fee_vault,
+            &pool.token_b.oracle,
+            &user.token_a_wallet,
+            &pool.token_a.vault,
+            &pool.token_a.fee_vault,
+            &pool.token_a.oracle,
+            100,
+        );
+        assert_eq!(ZionInstruction::unpack(&instruction.data), Ok(ZionInstruction::Swap(Swap { amount: 100 })));
+        assert_eq!(instruction.accounts[..expected.accounts.len()], expected.accounts[..]);
+        assert_eq!(
+            instruction.accounts[expected.accounts.len()..],
+            [
+                AccountMeta::new_readonly(extra_oracle, false),
+                AccountMeta::new_readonly(fallback_oracle, false),
+                AccountMeta::new(find_price_history_address().0, false),
+            ]
+        );
+
+        //quotes only read the price history
+        let quote = pool.quote(SwapDirection::AToB, 100);
+        assert_eq!(quote.accounts.last(), Some(&AccountMeta::new_readonly(find_price_history_address().0, false)));
+    }
+
+    #[test]
+    fn test_initialize_bumps() {
+        let pool = PoolAccounts::new(Pubkey::new_unique(), token(), token());
+        let instruction = pool.initialize(&Pubkey::new_unique(), Default::default());
+        match ZionInstruction::unpack(&instruction.data) {
+            Ok(ZionInstruction::Initialize(params)) => {
+                assert_eq!(params.bump, pool.swap_state_bump);
+                assert_eq!(params.swap_authority_bump, pool.swap_authority_bump);
+            },
+            other => panic!("unexpected instruction {:?}", other),
+        }
+        assert_eq!(instruction.accounts[1].pubkey, pool.swap_authority);
+        assert_eq!(instruction.accounts[3].pubkey, pool.swap_state);
+    }
+}
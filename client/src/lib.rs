@@ -0,0 +1,25 @@
+#![deny(missing_docs)]
+
+//! Client for the bank_of_zion program, builds its instructions from a pool's accounts
+//! instead of positional pubkeys and assembles them into transactions without an RPC client
+
+///PDA derivation of the program's accounts
+pub mod pda;
+///Accounts of a pool and of the users trading with it
+pub mod accounts;
+///Typed builders for every instruction of the program
+pub mod instructions;
+///Assembles instructions into transactions
+pub mod transaction;
+///Decodes swap state account data for inspection
+pub mod decode;
+///Prices swaps, deposits and withdraws off-chain with the program's math
+pub mod simulator;
+///Decodes the events the program logs from transaction logs
+pub mod events;
+
+pub use accounts::{PoolAccounts, PoolToken, SwapDirection, TokenAccounts, UserAccounts};
+pub use transaction::TransactionBuilder;
+
+// Export the program for downstream users building with a different program version
+pub use bank_of_zion;
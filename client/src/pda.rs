@@ -0,0 +1,27 @@
+use {
+    bank_of_zion::state::{PriceHistory, SwapState, AUTHORITY_PREFIX},
+    solana_program::pubkey::{Pubkey, PubkeyError},
+};
+
+///Address and bump of the swap state, there is one pool per program
+pub fn find_swap_state_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SwapState::PREFIX.as_bytes()], &bank_of_zion::id())
+}
+
+///Address and bump of the swap authority, owner of the vaults and mint authority of the swap mint
+pub fn find_swap_authority_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_PREFIX.as_bytes()], &bank_of_zion::id())
+}
+
+///Address of the swap authority for a given bump, the way the program derives it from the swap state's bump
+pub fn swap_authority_address(swap_authority_bump: u8) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[AUTHORITY_PREFIX.as_bytes(), &[swap_authority_bump]],
+        &bank_of_zion::id(),
+    )
+}
+
+///Address and bump of the price history created by the crank
+pub fn find_price_history_address() -> (Pubkey, u8) {
+    PriceHistory::find_address()
+}
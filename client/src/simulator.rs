@@ -0,0 +1,240 @@
+use {
+    crate::accounts::SwapDirection,
+    bank_of_zion::{
+        error::ZionError,
+        return_data::{LiquidityQuote, SwapQuote},
+        state::{SwapState, MAX_BPS},
+    },
+};
+
+///Balances and oracle prices of a pool at one point in time
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PoolSnapshot {
+    ///balance of the token a vault
+    pub token_a_vault: u64,
+    ///balance of the token a fee vault
+    pub token_a_fee_vault: u64,
+    ///balance of the token b vault
+    pub token_b_vault: u64,
+    ///balance of the token b fee vault
+    pub token_b_fee_vault: u64,
+    ///supply of the swap mint
+    pub swap_supply: u64,
+    ///oracle price of token a
+    pub token_a_price: u64,
+    ///oracle price of token b
+    pub token_b_price: u64,
+    ///TWAP or EMA of token a for the Conservative and Ema price modes, ignored in Spot mode
+    pub token_a_reference_price: u64,
+    ///TWAP or EMA of token b for the Conservative and Ema price modes, ignored in Spot mode
+    pub token_b_reference_price: u64,
+}
+
+impl PoolSnapshot {
+    ///value of the vaults and fee vaults at the oracle prices
+    pub fn value(&self) -> u128 {
+        (self.token_a_vault as u128 + self.token_a_fee_vault as u128) * self.token_a_price as u128
+            + (self.token_b_vault as u128 + self.token_b_fee_vault as u128) * self.token_b_price as u128
+    }
+}
+
+///Outcome of a simulated swap
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapSimulation {
+    ///amounts and prices, the same the Swap instruction returns
+    pub quote: SwapQuote,
+    ///value lost against the oracle prices in basis points of the value paid in, negative when the pool pays a premium
+    pub price_impact_bps: i64,
+}
+
+///Prices swaps, deposits and withdraws off-chain with the same SwapState math the program runs, so the
+///amounts match the instructions' to the token given the same balances and prices.
+///
+///The circuit breaker, the oracle freshness checks and the withdraw's swap token balance check are left to the caller
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolSimulator {
+    ///settings of the pool
+    pub swap_state: SwapState,
+    ///balances and prices the pool is priced at
+    pub snapshot: PoolSnapshot,
+}
+
+impl PoolSimulator {
+    ///Simulator for a pool with the given settings and balances
+    pub fn new(swap_state: SwapState, snapshot: PoolSnapshot) -> Self {
+        Self { swap_state, snapshot }
+    }
+
+    ///Move the oracle prices, the reference prices follow unless they are set separately
+    pub fn set_prices(&mut self, token_a_price: u64, token_b_price: u64) {
+        self.snapshot.token_a_price = token_a_price;
+        self.snapshot.token_b_price = token_b_price;
+        self.snapshot.token_a_reference_price = token_a_price;
+        self.snapshot.token_b_reference_price = token_b_price;
+    }
+
+    ///Amounts a swap of amount source tokens would move, without moving them
+    pub fn quote_swap(&self, direction: SwapDirection, amount: u64) -> Result<SwapSimulation, ZionError> {
+        let snapshot = &self.snapshot;
+        let (source_supply, source_price, source_reference_price, destination_supply, destination_price, destination_reference_price) = match direction {
+            SwapDirection::AToB => (
+                snapshot.token_a_vault, snapshot.token_a_price, snapshot.token_a_reference_price,
+                snapshot.token_b_vault, snapshot.token_b_price, snapshot.token_b_reference_price,
+            ),
+            SwapDirection::BToA => (
+                snapshot.token_b_vault, snapshot.token_b_price, snapshot.token_b_reference_price,
+                snapshot.token_a_vault, snapshot.token_a_price, snapshot.token_a_reference_price,
+            ),
+        };
+        //the Swap instruction fails on an empty destination vault before pricing
+        if destination_supply == 0 {
+            return Err(ZionError::InvalidSupply);
+        }
+
+        let quote = self.swap_state.quote_swap(
+            source_price,
+            source_reference_price,
+            destination_price,
+            destination_reference_price,
+            source_supply,
+            destination_supply,
+            amount,
+        )?;
+
+        //measured at the oracle prices whatever the price mode
+        let value_in = amount as i128 * source_price as i128;
+        let value_out = quote.amount_out as i128 * destination_price as i128;
+        let price_impact_bps = if value_in == 0 {
+            0
+        } else {
+            (value_in - value_out) * MAX_BPS as i128 / value_in
+        };
+
+        Ok(SwapSimulation {
+            quote,
+            price_impact_bps: i64::try_from(price_impact_bps).unwrap_or(i64::MIN),
+        })
+    }
+
+    ///Swap tokens a deposit would mint, without depositing
+    pub fn quote_deposit(&self, token_a_deposit: u64, token_b_deposit: u64) -> Result<LiquidityQuote, ZionError> {
+        let snapshot = &self.snapshot;
+        self.swap_state.quote_deposit(
+            token_a_deposit,
+            token_b_deposit,
+            snapshot.token_a_vault,
+            snapshot.token_a_fee_vault,
+            snapshot.token_b_vault,
+            snapshot.token_b_fee_vault,
+            snapshot.swap_supply,
+            snapshot.token_a_price,
+            snapshot.token_b_price,
+        )
+    }
+
+    ///Swap tokens a withdraw would burn, without withdrawing
+    pub fn quote_withdraw(&self, token_a_withdraw: u64, token_b_withdraw: u64) -> Result<LiquidityQuote, ZionError> {
+        let snapshot = &self.snapshot;
+        self.swap_state.quote_withdraw(
+            token_a_withdraw,
+            token_b_withdraw,
+            snapshot.token_a_vault,
+            snapshot.token_a_fee_vault,
+            snapshot.token_b_vault,
+            snapshot.token_b_fee_vault,
+            snapshot.swap_supply,
+            snapshot.token_a_price,
+            snapshot.token_b_price,
+        )
+    }
+
+    ///Swap and update the balances the way the Swap instruction does
+    pub fn swap(&mut self, direction: SwapDirection, amount: u64) -> Result<SwapSimulation, ZionError> {
+        let simulation = self.quote_swap(direction, amount)?;
+        let snapshot = &mut self.snapshot;
+        let (source_vault, destination_vault) = match direction {
+            SwapDirection::AToB => (&mut snapshot.token_a_vault, &mut snapshot.token_b_vault),
+            SwapDirection::BToA => (&mut snapshot.token_b_vault, &mut snapshot.token_a_vault),
+        };
+        *destination_vault = destination_vault.checked_sub(simulation.quote.amount_out).ok_or(ZionError::InvalidSupply)?;
+        *source_vault = source_vault.checked_add(amount).ok_or(ZionError::MathOverflow)?;
+        Ok(simulation)
+    }
+
+    ///Deposit and update the balances the way the Deposit instruction does
+    pub fn deposit(&mut self, token_a_deposit: u64, token_b_deposit: u64) -> Result<LiquidityQuote, ZionError> {
+        let quote = self.quote_deposit(token_a_deposit, token_b_deposit)?;
+        let snapshot = &mut self.snapshot;
+        snapshot.token_a_vault = snapshot.token_a_vault.checked_add(token_a_deposit).ok_or(ZionError::MathOverflow)?;
+        snapshot.token_b_vault = snapshot.token_b_vault.checked_add(token_b_deposit).ok_or(ZionError::MathOverflow)?;
+        snapshot.swap_supply = snapshot.swap_supply.checked_add(quote.swap_tokens).ok_or(ZionError::MathOverflow)?;
+        Ok(quote)
+    }
+
+    ///Withdraw and update the balances the way the Withdraw instruction does
+    pub fn withdraw(&mut self, token_a_withdraw: u64, token_b_withdraw: u64) -> Result<LiquidityQuote, ZionError> {
+        let quote = self.quote_withdraw(token_a_withdraw, token_b_withdraw)?;
+        let snapshot = &mut self.snapshot;
+        snapshot.token_a_vault = snapshot.token_a_vault.checked_sub(token_a_withdraw).ok_or(ZionError::InvalidTokenAmount)?;
+        snapshot.token_b_vault = snapshot.token_b_vault.checked_sub(token_b_withdraw).ok_or(ZionError::InvalidTokenAmount)?;
+        snapshot.swap_supply = snapshot.swap_supply.checked_sub(quote.swap_tokens).ok_or(ZionError::InsufficientSwapTokens)?;
+        Ok(quote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{PoolSimulator, PoolSnapshot},
+        crate::accounts::SwapDirection,
+        bank_of_zion::{error::ZionError, state::{CurveType, SwapState}},
+    };
+
+    fn simulator(curve_type: CurveType) -> PoolSimulator {
+        let swap_state = SwapState {
+            version: SwapState::VERSION,
+            curve_type: curve_type as u8,
+            ..SwapState::default()
+        };
+        let mut simulator = PoolSimulator::new(
+            swap_state,
+            PoolSnapshot {
+                token_a_vault: 1_000_000,
+                token_b_vault: 1_000_000,
+                swap_supply: 2_000_000,
+                ..PoolSnapshot::default()
+            },
+        );
+        simulator.set_prices(100, 100);
+        simulator
+    }
+
+    #[test]
+    fn test_swap_updates_balances() {
+        let mut simulator = simulator(CurveType::ConstantProduct);
+        let quote = simulator.quote_swap(SwapDirection::AToB, 10_000).unwrap();
+        let swapped = simulator.swap(SwapDirection::AToB, 10_000).unwrap();
+        assert_eq!(quote, swapped);
+        assert_eq!(simulator.snapshot.token_a_vault, 1_010_000);
+        assert_eq!(simulator.snapshot.token_b_vault, 1_000_000 - swapped.quote.amount_out);
+
+        //a constant product swap pays less than the oracle price, the larger the swap the more
+        assert!(swapped.price_impact_bps > 0);
+        let larger = simulator.quote_swap(SwapDirection::AToB, 100_000).unwrap();
+        assert!(larger.price_impact_bps > swapped.price_impact_bps);
+    }
+
+    #[test]
+    fn test_deposit_then_withdraw() {
+        let mut simulator = simulator(CurveType::OraclePremium);
+        let deposit = simulator.deposit(10_000, 0).unwrap();
+        assert_eq!(simulator.snapshot.swap_supply, 2_000_000 + deposit.swap_tokens);
+
+        let withdraw = simulator.quote_withdraw(10_000, 0).unwrap();
+        assert!(withdraw.swap_tokens >= deposit.swap_tokens);
+        simulator.withdraw(10_000, 0).unwrap();
+        assert_eq!(simulator.snapshot.token_a_vault, 1_000_000);
+
+        assert_eq!(simulator.withdraw(2_000_000, 0), Err(ZionError::InvalidTokenAmount));
+    }
+}
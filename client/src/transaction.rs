@@ -0,0 +1,61 @@
+use {
+    solana_program::{hash::Hash, instruction::Instruction, message::Message, pubkey::Pubkey},
+    solana_sdk::{
+        signature::SignerError,
+        signer::signers::Signers,
+        transaction::Transaction,
+    },
+};
+
+///Instructions of a transaction, signed once the caller fetched a recent blockhash however it talks to the cluster
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransactionBuilder {
+    instructions: Vec<Instruction>,
+}
+
+impl TransactionBuilder {
+    ///Transaction without instructions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Append an instruction
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    ///Append instructions
+    pub fn instructions<I: IntoIterator<Item = Instruction>>(mut self, instructions: I) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    ///Instructions added so far
+    pub fn get_instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    ///Message paid by payer
+    pub fn message(&self, payer: &Pubkey) -> Message {
+        Message::new(&self.instructions, Some(payer))
+    }
+
+    ///Unsigned transaction paid by payer, to be signed or simulated by the caller
+    pub fn unsigned(&self, payer: &Pubkey) -> Transaction {
+        Transaction::new_unsigned(self.message(payer))
+    }
+
+    ///Transaction paid by payer and signed by signers, which have to include the payer and every
+    ///other signer the instructions require
+    pub fn sign<T: Signers>(
+        &self,
+        payer: &Pubkey,
+        signers: &T,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction, SignerError> {
+        let mut transaction = self.unsigned(payer);
+        transaction.try_sign(signers, recent_blockhash)?;
+        Ok(transaction)
+    }
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use {
+    bank_of_zion::{
+        error::ZionError,
+        pod::PodU64,
+        state::{AcceptedPrice, CurveType, SwapState},
+    },
+    bank_of_zion_client::{simulator::PoolSnapshot, SwapDirection, TransactionBuilder},
+    common::{snapshot, token_balance, PoolFixture},
+    solana_program::{instruction::InstructionError, program_pack::Pack},
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn pool() -> PoolSnapshot {
+    PoolSnapshot {
+        token_a_vault: 5_000_000,
+        token_a_fee_vault: 0,
+        token_b_vault: 5_000_000,
+        token_b_fee_vault: 0,
+        swap_supply: 10_000_000,
+        token_a_price: 2_000,
+        token_b_price: 2_000,
+        token_a_reference_price: 2_000,
+        token_b_reference_price: 2_000,
+    }
+}
+
+///the breaker trips on the oracle price of token a, half of the price it last accepted
+fn tripping_fixture() -> PoolFixture {
+    let mut fixture = PoolFixture::new(CurveType::OraclePremium, 0, pool());
+    fixture.swap_state.breaker.max_jump_bps = PodU64::from(1_000);
+    fixture.swap_state.breaker.token_a_price = AcceptedPrice { price: PodU64::from(4_000), slot: PodU64::from(0) };
+    fixture.swap_state.breaker.token_b_price = AcceptedPrice { price: PodU64::from(2_000), slot: PodU64::from(0) };
+    fixture
+}
+
+///a swap that trips the breaker fails and moves nothing, a crank on the same prices saves the trip and pauses swaps
+#[tokio::test]
+async fn test_swap_trips_breaker() {
+    let fixture = tripping_fixture();
+    let pool = &fixture.pool;
+    let owner = Keypair::new();
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    let user = fixture.add_user(&mut program_test, &owner.pubkey(), 1_000_000, 1_000_000, 1_000_000);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.swap(&user, SwapDirection::AToB, 10_000))
+        .sign(&payer.pubkey(), &[&payer, &owner], recent_blockhash)
+        .unwrap();
+    let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(ZionError::BreakerTripped as u32))
+    );
+
+    assert_eq!(snapshot(&mut banks_client, &fixture).await, fixture.snapshot);
+    assert_eq!(token_balance(&mut banks_client, &user.token_a_wallet).await, 1_000_000);
+    let account = banks_client.get_account(pool.swap_state).await.unwrap().unwrap();
+    assert!(!bool::from(SwapState::unpack_from_slice(&account.data).unwrap().breaker.is_tripped));
+
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.crank(&payer.pubkey()))
+        .sign(&payer.pubkey(), &[&payer], recent_blockhash)
+        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
+    let account = banks_client.get_account(pool.swap_state).await.unwrap().unwrap();
+    assert!(bool::from(SwapState::unpack_from_slice(&account.data).unwrap().breaker.is_tripped));
+
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.swap(&user, SwapDirection::AToB, 10_001))
+        .sign(&payer.pubkey(), &[&payer, &owner], recent_blockhash)
+        .unwrap();
+    let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(ZionError::PoolPaused as u32))
+    );
+}
+
+///withdraws aren't paused, the one that trips the breaker still pays out
+#[tokio::test]
+async fn test_withdraw_trips_breaker_and_pays_out() {
+    let fixture = tripping_fixture();
+    let pool = &fixture.pool;
+    let owner = Keypair::new();
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    let user = fixture.add_user(&mut program_test, &owner.pubkey(), 0, 0, 1_000_000);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.withdraw(&user, 5_000, 2_000))
+        .sign(&payer.pubkey(), &[&payer, &owner], recent_blockhash)
+        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(token_balance(&mut banks_client, &user.token_a_wallet).await, 5_000);
+    assert_eq!(token_balance(&mut banks_client, &user.token_b_wallet).await, 2_000);
+    assert!(token_balance(&mut banks_client, &user.swap_wallet).await < 1_000_000);
+    let account = banks_client.get_account(pool.swap_state).await.unwrap().unwrap();
+    assert!(bool::from(SwapState::unpack_from_slice(&account.data).unwrap().breaker.is_tripped));
+}
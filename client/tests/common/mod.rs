@@ -0,0 +1,238 @@
+//! Pools written straight into a ProgramTest, with mock pyth oracles, so instructions can run without Initialize
+
+#![allow(dead_code)]
+
+use {
+    bank_of_zion::{
+        pod::PodU64,
+        processor::Processor,
+        state::{CurveType, SwapState, Token},
+    },
+    bank_of_zion_client::{
+        pda::{find_swap_authority_address, find_swap_state_address},
+        simulator::PoolSnapshot,
+        PoolAccounts, UserAccounts,
+    },
+    pyth_sdk_solana::state::{AccountType, PriceAccount, PriceStatus, MAGIC, VERSION_2},
+    solana_program::{program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent},
+    solana_program_test::{processor, BanksClient, ProgramTest},
+    solana_sdk::account::Account,
+    spl_token::state::{Account as TokenAccount, AccountState, Mint},
+};
+
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "bank_of_zion",
+        bank_of_zion::id(),
+        processor!(Processor::process),
+    );
+    program_test.add_program("spl_token", spl_token::id(), processor!(spl_token::processor::Processor::process));
+    program_test
+}
+
+fn packed_account<T: Pack>(state: T, owner: &Pubkey) -> Account {
+    let mut data = vec![0u8; T::LEN];
+    T::pack(state, &mut data).unwrap();
+
+    Account {
+        lamports: Rent::default().minimum_balance(T::LEN),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub fn mint_account(authority: &Pubkey, supply: u64) -> Account {
+    packed_account(
+        Mint {
+            mint_authority: COption::Some(*authority),
+            supply,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        &spl_token::id(),
+    )
+}
+
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    packed_account(
+        TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        },
+        &spl_token::id(),
+    )
+}
+
+///pyth price account trading at price
+pub fn oracle_account(price: i64, oracle_program: &Pubkey) -> Account {
+    price_account(price, PriceStatus::Trading, 0, oracle_program)
+}
+
+///pyth price account at price, with the status and publish time of its aggregate price
+pub fn price_account(price: i64, status: PriceStatus, publish_time: i64, oracle_program: &Pubkey) -> Account {
+    let mut price_account: PriceAccount = bytemuck::Zeroable::zeroed();
+    price_account.magic = MAGIC;
+    price_account.ver = VERSION_2;
+    price_account.atype = AccountType::Price as u32;
+    price_account.size = std::mem::size_of::<PriceAccount>() as u32;
+    price_account.agg.price = price;
+    price_account.agg.status = status;
+    price_account.timestamp = publish_time;
+    price_account.prev_price = price;
+    price_account.prev_timestamp = publish_time;
+    price_account.ema_price.val = price;
+
+    let data = bytemuck::bytes_of(&price_account).to_vec();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *oracle_program,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+///pool off balance at the oracle prices, with fees in the fee vaults, so the curves have work to do
+pub fn unbalanced_pool() -> PoolSnapshot {
+    PoolSnapshot {
+        token_a_vault: 5_000_000,
+        token_a_fee_vault: 1_000,
+        token_b_vault: 3_000_000,
+        token_b_fee_vault: 500,
+        swap_supply: 8_000_000,
+        token_a_price: 2_000,
+        token_b_price: 3_100,
+        token_a_reference_price: 2_000,
+        token_b_reference_price: 3_100,
+    }
+}
+
+///A pool with its vaults, mints and oracles, ready to be added to a ProgramTest
+pub struct PoolFixture {
+    pub swap_state: SwapState,
+    pub pool: PoolAccounts,
+    pub snapshot: PoolSnapshot,
+    pub oracle_program: Pubkey,
+}
+
+impl PoolFixture {
+    ///Pool with the balances and spot prices of the snapshot
+    pub fn new(curve_type: CurveType, amp: u64, snapshot: PoolSnapshot) -> Self {
+        let (_, bump) = find_swap_state_address();
+        let (swap_authority, swap_authority_bump) = find_swap_authority_address();
+        let token = || Token {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+        };
+
+        let swap_state = SwapState {
+            version: SwapState::VERSION,
+            admin: Pubkey::new_unique(),
+            bump,
+            is_initialized: true.into(),
+            swap_authority,
+            swap_authority_bump,
+            swap_mint: Pubkey::new_unique(),
+            token_a: token(),
+            token_b: token(),
+            curve_type: curve_type as u8,
+            amp: PodU64::from(amp),
+            ..SwapState::default()
+        };
+
+        Self {
+            pool: PoolAccounts::from_swap_state(&swap_state),
+            oracle_program: bank_of_zion::oracle_program::id(),
+            swap_state,
+            snapshot,
+        }
+    }
+
+    ///Add the swap state, mints, vaults and oracles
+    pub fn add_to(&self, program_test: &mut ProgramTest) {
+        let swap_state = &self.swap_state;
+
+        let mut data = vec![0u8; SwapState::LEN];
+        swap_state.pack_into_slice(&mut data);
+        program_test.add_account(
+            self.pool.swap_state,
+            Account {
+                lamports: Rent::default().minimum_balance(SwapState::LEN),
+                data,
+                owner: bank_of_zion::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        self.add_tokens_to(program_test);
+    }
+
+    ///Add the mints, vaults and oracles without the swap state, for a pool Initialize creates
+    pub fn add_tokens_to(&self, program_test: &mut ProgramTest) {
+        let swap_state = &self.swap_state;
+        let snapshot = &self.snapshot;
+        let authority = &swap_state.swap_authority;
+
+        program_test.add_account(swap_state.swap_mint, mint_account(authority, snapshot.swap_supply));
+        for (token, vault, fee_vault, price) in [
+            (&swap_state.token_a, snapshot.token_a_vault, snapshot.token_a_fee_vault, snapshot.token_a_price),
+            (&swap_state.token_b, snapshot.token_b_vault, snapshot.token_b_fee_vault, snapshot.token_b_price),
+        ] {
+            program_test.add_account(token.mint, mint_account(authority, vault + fee_vault));
+            program_test.add_account(token.vault, token_account(&token.mint, authority, vault));
+            program_test.add_account(token.fee_vault, token_account(&token.mint, authority, fee_vault));
+            program_test.add_account(token.oracle, oracle_account(price as i64, &self.oracle_program));
+        }
+    }
+
+    ///Add the wallets of a user holding the given balances
+    pub fn add_user(&self, program_test: &mut ProgramTest, owner: &Pubkey, token_a: u64, token_b: u64, swap_tokens: u64) -> UserAccounts {
+        let user = UserAccounts {
+            owner: *owner,
+            swap_wallet: Pubkey::new_unique(),
+            token_a_wallet: Pubkey::new_unique(),
+            token_b_wallet: Pubkey::new_unique(),
+        };
+        program_test.add_account(user.swap_wallet, token_account(&self.swap_state.swap_mint, owner, swap_tokens));
+        program_test.add_account(user.token_a_wallet, token_account(&self.swap_state.token_a.mint, owner, token_a));
+        program_test.add_account(user.token_b_wallet, token_account(&self.swap_state.token_b.mint, owner, token_b));
+        user
+    }
+}
+
+pub async fn token_balance(banks_client: &mut BanksClient, token_account: &Pubkey) -> u64 {
+    banks_client
+        .get_packed_account_data::<TokenAccount>(*token_account)
+        .await
+        .unwrap()
+        .amount
+}
+
+pub async fn mint_supply(banks_client: &mut BanksClient, mint: &Pubkey) -> u64 {
+    banks_client
+        .get_packed_account_data::<Mint>(*mint)
+        .await
+        .unwrap()
+        .supply
+}
+
+///Balances of the pool read back from the bank, with the fixture's prices
+pub async fn snapshot(banks_client: &mut BanksClient, fixture: &PoolFixture) -> PoolSnapshot {
+    let pool = &fixture.pool;
+    PoolSnapshot {
+        token_a_vault: token_balance(banks_client, &pool.token_a.vault).await,
+        token_a_fee_vault: token_balance(banks_client, &pool.token_a.fee_vault).await,
+        token_b_vault: token_balance(banks_client, &pool.token_b.vault).await,
+        token_b_fee_vault: token_balance(banks_client, &pool.token_b.fee_vault).await,
+        swap_supply: mint_supply(banks_client, &pool.swap_mint).await,
+        ..fixture.snapshot
+    }
+}
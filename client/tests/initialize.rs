@@ -0,0 +1,109 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use {
+    bank_of_zion::{
+        error::ZionError,
+        instructions::InitializeParams,
+        state::{CurveType, SwapState},
+    },
+    bank_of_zion_client::{simulator::PoolSnapshot, TransactionBuilder},
+    common::{oracle_account, PoolFixture},
+    pyth_sdk_solana::state::PriceAccount,
+    solana_program::{instruction::InstructionError, program_pack::Pack, pubkey::Pubkey},
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn pool() -> PoolSnapshot {
+    PoolSnapshot {
+        token_a_vault: 0,
+        token_a_fee_vault: 0,
+        token_b_vault: 0,
+        token_b_fee_vault: 0,
+        swap_supply: 0,
+        token_a_price: 2_000,
+        token_b_price: 2_000,
+        token_a_reference_price: 2_000,
+        token_b_reference_price: 2_000,
+    }
+}
+
+///pyth price account of the oracle program pricing the product
+fn product_oracle_account(price: i64, product: &Pubkey) -> Account {
+    let mut account = oracle_account(price, &bank_of_zion::oracle_program::id());
+    let mut price_account: PriceAccount = bytemuck::pod_read_unaligned(&account.data);
+    price_account.prod = *product;
+    account.data = bytemuck::bytes_of(&price_account).to_vec();
+    account
+}
+
+///Initialize a pool on the fixture's mints and vaults, with token a's oracle replaced by the given account
+async fn initialize(token_a_oracle: Account, params: InitializeParams) -> Result<(), TransactionError> {
+    let fixture = PoolFixture::new(CurveType::OraclePremium, 0, pool());
+    let admin = Keypair::new();
+    let mut program_test = common::program_test();
+    program_test.add_account(admin.pubkey(), Account { lamports: 1_000_000_000, ..Account::default() });
+    fixture.add_tokens_to(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    context.set_account(&fixture.pool.token_a.oracle, &token_a_oracle.into());
+
+    let transaction = TransactionBuilder::new()
+        .instruction(fixture.pool.initialize(&admin.pubkey(), params))
+        .sign(&context.payer.pubkey(), &[&context.payer, &admin], context.last_blockhash)
+        .unwrap();
+    context.banks_client.process_transaction(transaction).await.map_err(|err| err.unwrap())?;
+
+    let account = context.banks_client.get_account(fixture.pool.swap_state).await.unwrap().unwrap();
+    let swap_state = SwapState::unpack_from_slice(&account.data).unwrap();
+    assert!(bool::from(swap_state.is_initialized));
+    Ok(())
+}
+
+fn zion_error(err: ZionError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(err as u32))
+}
+
+#[tokio::test]
+async fn test_initialize() {
+    let oracle = oracle_account(2_000, &bank_of_zion::oracle_program::id());
+    assert_eq!(initialize(oracle, InitializeParams::default()).await, Ok(()));
+}
+
+///an oracle owned by any other program than the cluster's pyth program is refused
+#[tokio::test]
+async fn test_initialize_oracle_wrong_owner() {
+    let oracle = oracle_account(2_000, &Pubkey::new_unique());
+    assert_eq!(
+        initialize(oracle, InitializeParams::default()).await,
+        Err(zion_error(ZionError::OracleWrongOwner))
+    );
+}
+
+///an account of the pyth program that isn't a price account is refused
+#[tokio::test]
+async fn test_initialize_oracle_not_price_account() {
+    let mut oracle = oracle_account(2_000, &bank_of_zion::oracle_program::id());
+    oracle.data = vec![0u8; oracle.data.len()];
+    assert_eq!(
+        initialize(oracle, InitializeParams::default()).await,
+        Err(zion_error(ZionError::InvalidOracle))
+    );
+}
+
+///a price account of another product than the one the pool was set up for is refused
+#[tokio::test]
+async fn test_initialize_oracle_product_mismatch() {
+    let product = Pubkey::new_unique();
+    let params = InitializeParams { token_a_product: product, ..InitializeParams::default() };
+
+    let oracle = product_oracle_account(2_000, &Pubkey::new_unique());
+    assert_eq!(initialize(oracle, params.clone()).await, Err(zion_error(ZionError::OracleProductMismatch)));
+
+    let oracle = product_oracle_account(2_000, &product);
+    assert_eq!(initialize(oracle, params).await, Ok(()));
+}
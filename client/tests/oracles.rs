@@ -0,0 +1,249 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use {
+    bank_of_zion::{
+        error::ZionError,
+        pod::PodU64,
+        return_data::SwapQuote,
+        state::{CurveType, PriceMode},
+    },
+    bank_of_zion_client::{simulator::PoolSnapshot, PoolAccounts, PoolToken, SwapDirection, TransactionBuilder},
+    common::{oracle_account, price_account, PoolFixture},
+    pyth_sdk_solana::state::{PriceAccount, PriceStatus},
+    solana_program::{clock::Clock, instruction::InstructionError, program_pack::Pack, pubkey::Pubkey},
+    solana_program_test::{BanksClient, ProgramTestBanksClientExt},
+    solana_sdk::{
+        account::Account,
+        hash::Hash,
+        instruction::Instruction,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn pool() -> PoolSnapshot {
+    PoolSnapshot {
+        token_a_vault: 5_000_000,
+        token_a_fee_vault: 0,
+        token_b_vault: 5_000_000,
+        token_b_fee_vault: 0,
+        swap_supply: 10_000_000,
+        token_a_price: 2_000,
+        token_b_price: 2_000,
+        token_a_reference_price: 2_000,
+        token_b_reference_price: 2_000,
+    }
+}
+
+async fn process(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    signer: Option<&Keypair>,
+    recent_blockhash: Hash,
+    instruction: Instruction,
+) -> Result<(), TransactionError> {
+    let mut signers: Vec<&dyn Signer> = vec![payer];
+    signers.extend(signer.map(|signer| signer as &dyn Signer));
+    let transaction = TransactionBuilder::new()
+        .instruction(instruction)
+        .sign(&payer.pubkey(), &signers, recent_blockhash)
+        .unwrap();
+    banks_client.process_transaction(transaction).await.map_err(|err| err.unwrap())
+}
+
+fn zion_error(err: ZionError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(err as u32))
+}
+
+///an additional oracle that is configured has to be passed in, leaving it out doesn't drop its feed
+#[tokio::test]
+async fn test_missing_extra_oracle() {
+    let mut fixture = PoolFixture::new(CurveType::OraclePremium, 0, pool());
+    let extra_oracle = Pubkey::new_unique();
+    fixture.swap_state.token_a_oracles.extra_oracles[0] = extra_oracle;
+    fixture.swap_state.token_a_oracles.num_extra_oracles = 1;
+    fixture.swap_state.token_a_oracles.quorum = 1;
+
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    program_test.add_account(extra_oracle, oracle_account(2_000, &fixture.oracle_program));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    //the fixture's accounts were made before the oracle was configured
+    let quote = fixture.pool.quote(SwapDirection::AToB, 10_000);
+    assert_eq!(
+        process(&mut banks_client, &payer, None, recent_blockhash, quote).await,
+        Err(zion_error(ZionError::MissingOracle))
+    );
+
+    let pool = PoolAccounts::from_swap_state(&fixture.swap_state);
+    assert_eq!(pool.token_a.extra_oracles, vec![extra_oracle]);
+    let quote = pool.quote(SwapDirection::AToB, 10_000);
+    assert_eq!(process(&mut banks_client, &payer, None, recent_blockhash, quote).await, Ok(()));
+}
+
+///Quote a swap of token a on a pool whose primary and fallback oracle of token a have the given status and
+///were published the given number of seconds before the bank's clock. Returns the price token a was valued at,
+///2000 from the primary oracle or 2100 from the fallback oracle
+async fn quote_with_fallback(primary: (PriceStatus, i64), fallback: (PriceStatus, i64)) -> Result<u64, TransactionError> {
+    let mut fixture = PoolFixture::new(CurveType::OraclePremium, 0, pool());
+    let fallback_oracle = Pubkey::new_unique();
+    fixture.swap_state.token_a_fallback_oracle = fallback_oracle;
+    fixture.swap_state.token_a_oracles.max_age_secs = PodU64::from(60);
+    let pool = PoolAccounts::from_swap_state(&fixture.swap_state);
+
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+
+    let (status, age) = primary;
+    context.set_account(&pool.token_a.oracle, &price_account(2_000, status, now - age, &fixture.oracle_program).into());
+    let (status, age) = fallback;
+    context.set_account(&fallback_oracle, &price_account(2_100, status, now - age, &fixture.oracle_program).into());
+
+    let quote = simulate_quote(&mut context.banks_client, &context.payer, context.last_blockhash, pool.quote(SwapDirection::AToB, 10_000)).await?;
+    Ok(quote.source_price)
+}
+
+///SwapQuote a quote instruction returns
+async fn simulate_quote(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    instruction: Instruction,
+) -> Result<SwapQuote, TransactionError> {
+    let transaction = TransactionBuilder::new()
+        .instruction(instruction)
+        .sign(&payer.pubkey(), &[payer], recent_blockhash)
+        .unwrap();
+    //a quote doesn't write anything, simulating it returns the same return data
+    let simulation = banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap()?;
+
+    //the runtime drops trailing zero bytes of the return data
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap().data;
+    let mut data = [0u8; SwapQuote::LEN];
+    data[..return_data.len()].copy_from_slice(&return_data);
+    Ok(SwapQuote::unpack_from_slice(&data).unwrap())
+}
+
+#[tokio::test]
+async fn test_fresh_primary_oracle_ignores_fallback() {
+    assert_eq!(quote_with_fallback((PriceStatus::Trading, 0), (PriceStatus::Trading, 0)).await, Ok(2_000));
+}
+
+#[tokio::test]
+async fn test_fallback_stands_in_for_stale_primary_oracle() {
+    assert_eq!(quote_with_fallback((PriceStatus::Trading, 120), (PriceStatus::Trading, 0)).await, Ok(2_100));
+}
+
+///a feed that isn't trading carries its last trading price with a recent publish time, it's stale all the same
+#[tokio::test]
+async fn test_fallback_stands_in_for_halted_primary_oracle() {
+    assert_eq!(quote_with_fallback((PriceStatus::Halted, 0), (PriceStatus::Trading, 0)).await, Ok(2_100));
+    assert_eq!(quote_with_fallback((PriceStatus::Unknown, 0), (PriceStatus::Trading, 0)).await, Ok(2_100));
+}
+
+#[tokio::test]
+async fn test_stale_primary_and_fallback_oracle() {
+    assert_eq!(
+        quote_with_fallback((PriceStatus::Trading, 120), (PriceStatus::Halted, 0)).await,
+        Err(zion_error(ZionError::OracleQuorumNotMet))
+    );
+}
+
+///pyth price account trading at price with an EMA of ema_price, published the given number of seconds before now
+fn ema_price_account(price: i64, ema_price: i64, age: i64, now: i64, oracle_program: &Pubkey) -> Account {
+    let mut account = price_account(price, PriceStatus::Trading, now - age, oracle_program);
+    let mut price_account: PriceAccount = bytemuck::pod_read_unaligned(&account.data);
+    price_account.ema_price.val = ema_price;
+    account.data = bytemuck::bytes_of(&price_account).to_vec();
+    account
+}
+
+///Quote a swap of token a on an Ema pool whose primary and fallback oracle of token a are given as
+///(price, EMA, age in seconds), returns the quote and the quote expected at the fallback oracle's prices
+async fn quote_ema(primary: (i64, i64, i64), fallback: (i64, i64, i64)) -> (Result<SwapQuote, TransactionError>, SwapQuote) {
+    let mut fixture = PoolFixture::new(CurveType::OraclePremium, 0, pool());
+    fixture.swap_state.price_mode = PriceMode::Ema as u8;
+    let fallback_oracle = Pubkey::new_unique();
+    fixture.swap_state.token_a_fallback_oracle = fallback_oracle;
+    fixture.swap_state.token_a_oracles.max_age_secs = PodU64::from(60);
+    let pool = PoolAccounts::from_swap_state(&fixture.swap_state);
+
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+
+    let (price, ema_price, age) = primary;
+    context.set_account(&pool.token_a.oracle, &ema_price_account(price, ema_price, age, now, &fixture.oracle_program).into());
+    let (price, ema_price, age) = fallback;
+    context.set_account(&fallback_oracle, &ema_price_account(price, ema_price, age, now, &fixture.oracle_program).into());
+
+    let snapshot = &fixture.snapshot;
+    let expected = fixture.swap_state.quote_swap(
+        price as u64,
+        ema_price as u64,
+        snapshot.token_b_price,
+        snapshot.token_b_price,
+        snapshot.token_a_vault,
+        snapshot.token_b_vault,
+        10_000,
+    ).unwrap();
+
+    let quote = simulate_quote(&mut context.banks_client, &context.payer, context.last_blockhash, pool.quote(SwapDirection::AToB, 10_000)).await;
+    (quote, expected)
+}
+
+///an Ema pool reads the EMA of the feed the price came from, the stale primary oracle's EMA isn't used
+#[tokio::test]
+async fn test_ema_from_fallback_for_stale_primary_oracle() {
+    let (quote, expected) = quote_ema((2_000, 1_500, 120), (2_100, 2_050, 0)).await;
+    assert_eq!(quote, Ok(expected));
+    assert_eq!(expected.source_price, 2_050);
+}
+
+///an EMA that doesn't pass the oracle checks fails the quote instead of pricing it
+#[tokio::test]
+async fn test_ema_rejected_fails_quote() {
+    let (quote, _) = quote_ema((2_000, 0, 0), (2_100, 2_050, 120)).await;
+    assert_eq!(quote, Err(zion_error(ZionError::OracleQuorumNotMet)));
+}
+
+///without a max age the primary oracle is never stale, a fallback oracle would never be read
+#[tokio::test]
+async fn test_fallback_oracle_requires_max_age() {
+    let admin = Keypair::new();
+    let mut fixture = PoolFixture::new(CurveType::OraclePremium, 0, pool());
+    fixture.swap_state.admin = admin.pubkey();
+    let fallback_oracle = Pubkey::new_unique();
+
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    program_test.add_account(fallback_oracle, oracle_account(2_100, &fixture.oracle_program));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let pool = &fixture.pool;
+
+    let set_fallback_oracle = pool.set_fallback_oracle(&admin.pubkey(), PoolToken::A, Some(&fallback_oracle));
+    assert_eq!(
+        process(&mut banks_client, &payer, Some(&admin), recent_blockhash, set_fallback_oracle.clone()).await,
+        Err(zion_error(ZionError::InvalidOracleConfig))
+    );
+
+    let set_oracle_config = pool.set_oracle_config(&admin.pubkey(), PoolToken::A, &[], 1, 60, 0);
+    assert_eq!(process(&mut banks_client, &payer, Some(&admin), recent_blockhash, set_oracle_config).await, Ok(()));
+    //the failed transaction is already processed, it needs a new blockhash to run again
+    let recent_blockhash = banks_client.get_new_latest_blockhash(&recent_blockhash).await.unwrap();
+    assert_eq!(process(&mut banks_client, &payer, Some(&admin), recent_blockhash, set_fallback_oracle).await, Ok(()));
+
+    //the max age can't be taken away while the fallback oracle is set
+    let set_oracle_config = pool.set_oracle_config(&admin.pubkey(), PoolToken::A, &[], 1, 0, 0);
+    assert_eq!(
+        process(&mut banks_client, &payer, Some(&admin), recent_blockhash, set_oracle_config).await,
+        Err(zion_error(ZionError::InvalidOracleConfig))
+    );
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "test-bpf")]
+
+use {
+    bank_of_zion::{
+        error::ZionError,
+        processor::Processor,
+        state::{SwapState, Token},
+    },
+    bank_of_zion_client::{
+        pda::{find_swap_authority_address, find_swap_state_address},
+        PoolAccounts, TransactionBuilder,
+    },
+    solana_program::{
+        instruction::InstructionError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+    solana_program_test::{processor, ProgramTest, ProgramTestBanksClientExt},
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "bank_of_zion",
+        bank_of_zion::id(),
+        processor!(Processor::process),
+    )
+}
+
+fn token() -> Token {
+    Token {
+        mint: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        fee_vault: Pubkey::new_unique(),
+        oracle: Pubkey::new_unique(),
+    }
+}
+
+///pool paused by the circuit breaker
+fn tripped_swap_state(admin: &Pubkey) -> SwapState {
+    let (_, bump) = find_swap_state_address();
+    let (swap_authority, swap_authority_bump) = find_swap_authority_address();
+
+    let mut swap_state = SwapState {
+        version: SwapState::VERSION,
+        admin: *admin,
+        bump,
+        is_initialized: true.into(),
+        swap_authority,
+        swap_authority_bump,
+        swap_mint: Pubkey::new_unique(),
+        token_a: token(),
+        token_b: token(),
+        ..SwapState::default()
+    };
+    swap_state.breaker.is_tripped = true.into();
+    swap_state
+}
+
+fn swap_state_account(swap_state: &SwapState) -> Account {
+    let mut data = vec![0u8; SwapState::LEN];
+    swap_state.pack_into_slice(&mut data);
+
+    Account {
+        lamports: Rent::default().minimum_balance(SwapState::LEN),
+        data,
+        owner: bank_of_zion::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_reset_and_close_pool() {
+    let admin = Keypair::new();
+    let swap_state = tripped_swap_state(&admin.pubkey());
+    let pool = PoolAccounts::from_swap_state(&swap_state);
+    assert_eq!(pool.swap_state, find_swap_state_address().0);
+    assert_eq!(pool.token_a.mint, swap_state.token_a.mint);
+    assert_eq!(pool.price_history, None);
+
+    let mut program_test = program_test();
+    program_test.add_account(pool.swap_state, swap_state_account(&swap_state));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.reset_circuit_breaker(&admin.pubkey()))
+        .sign(&payer.pubkey(), &[&payer, &admin], recent_blockhash)
+        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = banks_client.get_account(pool.swap_state).await.unwrap().unwrap();
+    let reset = SwapState::unpack_from_slice(&account.data).unwrap();
+    assert!(!bool::from(reset.breaker.is_tripped));
+    //the pool read back from the account is the same pool
+    assert_eq!(PoolAccounts::from_account_data(&account.data).unwrap(), pool);
+
+    //closing the pool refunds its rent to the admin
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.close_pool(&admin.pubkey()))
+        .sign(
+            &payer.pubkey(),
+            &[&payer, &admin],
+            banks_client.get_new_latest_blockhash(&recent_blockhash).await.unwrap(),
+        )
+        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(banks_client.get_account(pool.swap_state).await.unwrap(), None);
+    let admin_account = banks_client.get_account(admin.pubkey()).await.unwrap().unwrap();
+    assert_eq!(admin_account.lamports, Rent::default().minimum_balance(SwapState::LEN));
+}
+
+#[tokio::test]
+async fn test_close_pool_must_be_admin() {
+    let admin = Keypair::new();
+    let attacker = Keypair::new();
+    let swap_state = tripped_swap_state(&admin.pubkey());
+    let pool = PoolAccounts::from_swap_state(&swap_state);
+
+    let mut program_test = program_test();
+    program_test.add_account(pool.swap_state, swap_state_account(&swap_state));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.close_pool(&attacker.pubkey()))
+        .sign(&payer.pubkey(), &[&payer, &attacker], recent_blockhash)
+        .unwrap();
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ZionError::MustBeAdmin as u32)
+        )
+    );
+}
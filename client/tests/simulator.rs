@@ -0,0 +1,90 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use {
+    bank_of_zion::state::CurveType,
+    bank_of_zion_client::{
+        simulator::PoolSimulator,
+        SwapDirection, TransactionBuilder,
+    },
+    common::{snapshot, token_balance, unbalanced_pool, PoolFixture},
+    solana_sdk::signature::{Keypair, Signer},
+};
+
+///run a swap, a deposit and a withdraw on-chain and check every balance against the simulator
+async fn check_curve(curve_type: CurveType, amp: u64) {
+    let fixture = PoolFixture::new(curve_type, amp, unbalanced_pool());
+    let mut simulator = PoolSimulator::new(fixture.swap_state, fixture.snapshot);
+    let pool = &fixture.pool;
+
+    let owner = Keypair::new();
+    let mut program_test = common::program_test();
+    fixture.add_to(&mut program_test);
+    let user = fixture.add_user(&mut program_test, &owner.pubkey(), 1_000_000, 1_000_000, 1_000_000);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut token_a = 1_000_000;
+    let mut token_b = 1_000_000;
+    let mut swap_tokens = 1_000_000;
+
+    for (direction, amount) in [(SwapDirection::AToB, 12_345), (SwapDirection::BToA, 54_321)] {
+        let simulation = simulator.swap(direction, amount).unwrap();
+        let transaction = TransactionBuilder::new()
+            .instruction(pool.swap(&user, direction, amount))
+            .sign(&payer.pubkey(), &[&payer, &owner], recent_blockhash)
+            .unwrap();
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        match direction {
+            SwapDirection::AToB => {
+                token_a -= amount;
+                token_b += simulation.quote.amount_out;
+            },
+            SwapDirection::BToA => {
+                token_b -= amount;
+                token_a += simulation.quote.amount_out;
+            },
+        }
+        assert_eq!(snapshot(&mut banks_client, &fixture).await, simulator.snapshot, "{:?} swap", curve_type);
+        assert_eq!(token_balance(&mut banks_client, &user.token_a_wallet).await, token_a);
+        assert_eq!(token_balance(&mut banks_client, &user.token_b_wallet).await, token_b);
+    }
+
+    let deposit = simulator.deposit(10_000, 7_000).unwrap();
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.deposit(&user, 10_000, 7_000))
+        .sign(&payer.pubkey(), &[&payer, &owner], recent_blockhash)
+        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
+    swap_tokens += deposit.swap_tokens;
+    assert_eq!(snapshot(&mut banks_client, &fixture).await, simulator.snapshot, "{:?} deposit", curve_type);
+    assert_eq!(token_balance(&mut banks_client, &user.swap_wallet).await, swap_tokens);
+
+    let withdraw = simulator.withdraw(5_000, 2_000).unwrap();
+    let transaction = TransactionBuilder::new()
+        .instruction(pool.withdraw(&user, 5_000, 2_000))
+        .sign(&payer.pubkey(), &[&payer, &owner], recent_blockhash)
+        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
+    swap_tokens -= withdraw.swap_tokens;
+    assert_eq!(snapshot(&mut banks_client, &fixture).await, simulator.snapshot, "{:?} withdraw", curve_type);
+    assert_eq!(token_balance(&mut banks_client, &user.swap_wallet).await, swap_tokens);
+    assert_eq!(token_balance(&mut banks_client, &user.token_a_wallet).await, token_a - 10_000 + 5_000);
+    assert_eq!(token_balance(&mut banks_client, &user.token_b_wallet).await, token_b - 7_000 + 2_000);
+}
+
+#[tokio::test]
+async fn test_simulator_matches_oracle_premium() {
+    check_curve(CurveType::OraclePremium, 0).await;
+}
+
+#[tokio::test]
+async fn test_simulator_matches_constant_product() {
+    check_curve(CurveType::ConstantProduct, 0).await;
+}
+
+#[tokio::test]
+async fn test_simulator_matches_stable_swap() {
+    check_curve(CurveType::StableSwap, 100).await;
+}
@@ -0,0 +1,67 @@
+#![no_main]
+
+use {
+    arbitrary::Arbitrary,
+    bank_of_zion_fuzz::FuzzCurve,
+    libfuzzer_sys::fuzz_target,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    curve: FuzzCurve,
+    deposit: u64,
+    more: u64,
+    vault_a: u64,
+    price_a: u64,
+    fee_vault_a: u64,
+    vault_b: u64,
+    price_b: u64,
+    fee_vault_b: u64,
+    swap_supply: u64,
+}
+
+fuzz_target!(|input: Input| {
+    let swap_state = match input.curve.swap_state() {
+        Some(swap_state) => swap_state,
+        None => return,
+    };
+    let swap_tokens = |deposit: u64| swap_state.calculate_swap_tokens(
+        deposit,
+        input.vault_a,
+        input.price_a,
+        input.fee_vault_a,
+        input.vault_b,
+        input.price_b,
+        input.fee_vault_b,
+        input.swap_supply,
+    );
+
+    //any input either prices or fails, never panics
+    let minted = match swap_tokens(input.deposit) {
+        Ok(minted) => minted,
+        Err(_) => return,
+    };
+
+    //depositing more never mints fewer swap tokens
+    if let Ok(more_minted) = swap_tokens(input.deposit.saturating_add(input.more)) {
+        assert!(more_minted >= minted, "{:?}: depositing more minted {} instead of {}", input, more_minted, minted);
+    }
+
+    //withdrawing the deposit again burns at least what it minted
+    let (vault_a, swap_supply) = match (input.vault_a.checked_add(input.deposit), input.swap_supply.checked_add(minted)) {
+        (Some(vault_a), Some(swap_supply)) => (vault_a, swap_supply),
+        _ => return,
+    };
+    if let Ok(burned) = swap_state.calculate_withdraw_swap_tokens(
+        input.deposit,
+        vault_a,
+        input.price_a,
+        input.fee_vault_a,
+        input.vault_b,
+        input.price_b,
+        input.fee_vault_b,
+        swap_supply,
+    ) {
+        assert!(burned >= minted, "{:?}: minted {} but withdrawing burned {}", input, minted, burned);
+    }
+});
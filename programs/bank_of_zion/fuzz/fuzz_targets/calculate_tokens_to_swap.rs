@@ -0,0 +1,70 @@
+#![no_main]
+
+use {
+    arbitrary::Arbitrary,
+    bank_of_zion::state::CurveType,
+    bank_of_zion_fuzz::FuzzCurve,
+    libfuzzer_sys::fuzz_target,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    curve: FuzzCurve,
+    amount: u64,
+    more: u64,
+    source_supply: u64,
+    source_price: u64,
+    destination_price: u64,
+    destination_supply: u64,
+}
+
+fuzz_target!(|input: Input| {
+    let swap_state = match input.curve.swap_state() {
+        Some(swap_state) => swap_state,
+        None => return,
+    };
+    let tokens_to_swap = |amount: u64| swap_state.calculate_tokens_to_swap(
+        input.source_supply,
+        input.source_price,
+        input.destination_price,
+        input.destination_supply,
+        amount,
+    );
+
+    //any input either prices or fails, never panics
+    let destination_tokens = match tokens_to_swap(input.amount) {
+        Ok(destination_tokens) => destination_tokens,
+        Err(_) => return,
+    };
+
+    //swapping more never pays out less
+    if let Ok(more_tokens) = tokens_to_swap(input.amount.saturating_add(input.more)) {
+        assert!(more_tokens >= destination_tokens, "{:?}: swapping more paid {} instead of {}", input, more_tokens, destination_tokens);
+    }
+
+    //the invariant curves never empty the destination vault
+    if swap_state.curve_type != CurveType::OraclePremium as u8 {
+        assert!(
+            destination_tokens < input.destination_supply || input.destination_supply == 0,
+            "{:?}: paid out {} of {}", input, destination_tokens, input.destination_supply
+        );
+    }
+
+    //swapping the output back never returns more than was swapped in
+    if destination_tokens > input.destination_supply {
+        return;
+    }
+    let source_supply = match input.source_supply.checked_add(input.amount) {
+        Some(source_supply) => source_supply,
+        None => return,
+    };
+    if let Ok(source_tokens) = swap_state.calculate_tokens_to_swap(
+        input.destination_supply - destination_tokens,
+        input.destination_price,
+        input.source_price,
+        source_supply,
+        destination_tokens,
+    ) {
+        assert!(source_tokens <= input.amount, "{:?}: swapped {} but got back {}", input, input.amount, source_tokens);
+    }
+});
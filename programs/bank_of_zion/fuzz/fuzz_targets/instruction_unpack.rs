@@ -0,0 +1,17 @@
+#![no_main]
+
+use {bank_of_zion::instructions::ZionInstruction, libfuzzer_sys::fuzz_target};
+
+fuzz_target!(|data: &[u8]| {
+    //any bytes either unpack or fail, never panic
+    let instruction = match ZionInstruction::unpack(data) {
+        Ok(instruction) => instruction,
+        Err(_) => return,
+    };
+
+    //packing gives back the bytes the instruction was read from, trailing bytes are ignored by unpack
+    let packed = instruction.pack();
+    assert!(packed.len() <= data.len(), "{:?} packed to more bytes than it was read from", instruction);
+    assert_eq!(&data[..packed.len()], &packed[..], "{:?} doesn't pack to the bytes it was read from", instruction);
+    assert_eq!(ZionInstruction::unpack(&packed).unwrap(), instruction);
+});
@@ -0,0 +1,30 @@
+#![no_main]
+
+use {
+    bank_of_zion::state::SwapState,
+    libfuzzer_sys::fuzz_target,
+    solana_program::program_pack::Pack,
+};
+
+fuzz_target!(|data: &[u8]| {
+    //the original layout is only read by unpack_v1 and has to be migrated first
+    if let Ok(swap_state) = SwapState::unpack_v1(data) {
+        assert_eq!(swap_state.version, SwapState::VERSION_V1);
+        assert!(SwapState::unpack_from_slice(data).is_err());
+    }
+
+    //any bytes either unpack or fail, never panic
+    let swap_state = match SwapState::unpack_from_slice(data) {
+        Ok(swap_state) => swap_state,
+        Err(_) => return,
+    };
+
+    //packing gives back the bytes the state was read from, bytes past the layout are ignored
+    let mut packed = vec![0u8; SwapState::LEN];
+    swap_state.pack_into_slice(&mut packed);
+    assert_eq!(&data[..SwapState::LEN], &packed[..]);
+    assert_eq!(SwapState::unpack_from_slice(&packed).unwrap(), swap_state);
+
+    //and a state that unpacks always has a curve to price with
+    assert!(swap_state.curve().is_ok());
+});
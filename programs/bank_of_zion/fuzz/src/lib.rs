@@ -0,0 +1,42 @@
+//! Inputs shared by the fuzz targets
+
+use {
+    arbitrary::Arbitrary,
+    bank_of_zion::{
+        pod::PodU64,
+        state::{PremiumParams, SwapState},
+    },
+};
+
+///Curve settings of a pool, the curve type is taken modulo the number of curves
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub struct FuzzCurve {
+    pub curve_type: u8,
+    pub amp: u64,
+    pub slope_bps: u64,
+    pub max_premium_bps: u64,
+    pub max_discount_bps: u64,
+    pub dead_band_bps: u64,
+}
+
+impl FuzzCurve {
+    ///swap state priced by the curve, None when Initialize would reject the premium params
+    pub fn swap_state(&self) -> Option<SwapState> {
+        let premium = PremiumParams {
+            slope_bps: self.slope_bps.into(),
+            max_premium_bps: self.max_premium_bps.into(),
+            max_discount_bps: self.max_discount_bps.into(),
+            dead_band_bps: self.dead_band_bps.into(),
+        };
+        premium.validate().ok()?;
+
+        Some(SwapState {
+            version: SwapState::VERSION,
+            is_initialized: true.into(),
+            curve_type: self.curve_type % 3,
+            amp: PodU64::from(self.amp),
+            premium,
+            ..SwapState::default()
+        })
+    }
+}
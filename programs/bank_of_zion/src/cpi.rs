@@ -89,6 +89,32 @@ pub fn create_pda_account<'a>(
     }
 }
 
+///CPI to system program to fund a program owned account before resizing it
+pub fn realloc_account<'a>(
+    payer: &AccountInfo<'a>,
+    rent: &Rent,
+    new_len: usize,
+    system_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+) -> ProgramResult {
+    let required_lamports = rent
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, required_lamports),
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+
+    account.realloc(new_len, true)
+}
+
 ///CPI to spl_token program to issue a spl_token `Burn` instruction.
 pub fn token_burn<'a>(
     token_program: &AccountInfo<'a>,
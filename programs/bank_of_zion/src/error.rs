@@ -84,6 +84,46 @@ pub enum ZionError {
     InvalidSigner,
     #[error("Insufficient swap tokens")]
     InsufficientSwapTokens,
+    #[error("Fee must be at most 10000 basis points")]
+    InvalidFee,
+    #[error("The Swap State version is not supported")]
+    InvalidStateVersion,
+    #[error("The Swap State must be migrated")]
+    StateMigrationRequired,
+    #[error("Math operation overflowed")]
+    MathOverflow,
+    #[error("Conversion to a token amount failed")]
+    ConversionFailed,
+    #[error("The curve type or its parameters are invalid")]
+    InvalidCurve,
+    #[error("The curve can't price an empty vault")]
+    EmptyVault,
+    #[error("The premium parameters are out of range")]
+    InvalidPremiumParams,
+    #[error("Trade limits must be at most 10000 basis points")]
+    InvalidTradeLimit,
+    #[error("The swap pays out more of the destination vault than the pool allows")]
+    TradeTooLarge,
+    #[error("The swap price deviates from the oracle price more than the pool allows")]
+    PriceDeviationTooHigh,
+    #[error("The pool is paused by the circuit breaker")]
+    PoolPaused,
+    #[error("The price mode is invalid")]
+    InvalidPriceMode,
+    #[error("The price history account is missing or invalid")]
+    InvalidPriceHistory,
+    #[error("The oracle configuration is invalid")]
+    InvalidOracleConfig,
+    #[error("Too few fresh oracle feeds to price the token")]
+    OracleQuorumNotMet,
+    #[error("The oracle isn't owned by the oracle program")]
+    OracleWrongOwner,
+    #[error("The oracle doesn't price the expected product")]
+    OracleProductMismatch,
+    #[error("A configured oracle account wasn't passed in")]
+    MissingOracle,
+    #[error("The oracle price jumped and tripped the circuit breaker")]
+    BreakerTripped,
 
 }
 
@@ -199,6 +239,66 @@ impl PrintProgramError for ZionError {
             ZionError::InsufficientSwapTokens=> {
                 msg!("Insufficient swap tokens")
             }
+            ZionError::InvalidFee=> {
+                msg!("Fee must be at most 10000 basis points")
+            }
+            ZionError::InvalidStateVersion=> {
+                msg!("The Swap State version is not supported")
+            }
+            ZionError::StateMigrationRequired=> {
+                msg!("The Swap State must be migrated")
+            }
+            ZionError::MathOverflow=> {
+                msg!("Math operation overflowed")
+            }
+            ZionError::ConversionFailed=> {
+                msg!("Conversion to a token amount failed")
+            }
+            ZionError::InvalidCurve=> {
+                msg!("The curve type or its parameters are invalid")
+            }
+            ZionError::EmptyVault=> {
+                msg!("The curve can't price an empty vault")
+            }
+            ZionError::InvalidPremiumParams=> {
+                msg!("The premium parameters are out of range")
+            }
+            ZionError::InvalidTradeLimit=> {
+                msg!("Trade limits must be at most 10000 basis points")
+            }
+            ZionError::TradeTooLarge=> {
+                msg!("The swap pays out more of the destination vault than the pool allows")
+            }
+            ZionError::PriceDeviationTooHigh=> {
+                msg!("The swap price deviates from the oracle price more than the pool allows")
+            }
+            ZionError::PoolPaused=> {
+                msg!("The pool is paused by the circuit breaker")
+            }
+            ZionError::InvalidPriceMode=> {
+                msg!("The price mode is invalid")
+            }
+            ZionError::InvalidPriceHistory=> {
+                msg!("The price history account is missing or invalid")
+            }
+            ZionError::InvalidOracleConfig=> {
+                msg!("The oracle configuration is invalid")
+            }
+            ZionError::OracleQuorumNotMet=> {
+                msg!("Too few fresh oracle feeds to price the token")
+            }
+            ZionError::OracleWrongOwner=> {
+                msg!("The oracle isn't owned by the oracle program")
+            }
+            ZionError::OracleProductMismatch=> {
+                msg!("The oracle doesn't price the expected product")
+            }
+            ZionError::MissingOracle=> {
+                msg!("A configured oracle account wasn't passed in")
+            }
+            ZionError::BreakerTripped=> {
+                msg!("The oracle price jumped and tripped the circuit breaker")
+            }
 
 
         }
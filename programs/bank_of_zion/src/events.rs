@@ -0,0 +1,223 @@
+use {
+    crate::pod::PodU64,
+    bytemuck::{Pod, Zeroable},
+    solana_program::{
+        log::sol_log_data,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+///Version byte every event starts with, bumped when an existing event layout changes
+pub const EVENT_VERSION: u8 = 1;
+
+///A pool was initialized
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PoolInitialized {
+    ///admin of the pool
+    pub admin: Pubkey,
+    ///mint of the swap tokens
+    pub swap_mint: Pubkey,
+    ///mint of token a
+    pub token_a_mint: Pubkey,
+    ///mint of token b
+    pub token_b_mint: Pubkey,
+    ///primary oracle of token a
+    pub token_a_oracle: Pubkey,
+    ///primary oracle of token b
+    pub token_b_oracle: Pubkey,
+    ///pricing curve of the pool, see [CurveType](../state/enum.CurveType.html)
+    pub curve_type: u8,
+}
+
+///Tokens were deposited into the pool, emitted by Deposit and AdminDeposit
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Deposited {
+    ///user that deposited
+    pub user: Pubkey,
+    ///tokens of token a deposited
+    pub token_a_amount: PodU64,
+    ///tokens of token b deposited
+    pub token_b_amount: PodU64,
+    ///swap tokens minted
+    pub swap_tokens: PodU64,
+    ///oracle price token a was valued at
+    pub token_a_price: PodU64,
+    ///oracle price token b was valued at
+    pub token_b_price: PodU64,
+}
+
+///Tokens were withdrawn from the pool
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Withdrew {
+    ///user that withdrew
+    pub user: Pubkey,
+    ///tokens of token a withdrawn
+    pub token_a_amount: PodU64,
+    ///tokens of token b withdrawn
+    pub token_b_amount: PodU64,
+    ///swap tokens burned
+    pub swap_tokens: PodU64,
+    ///oracle price token a was valued at
+    pub token_a_price: PodU64,
+    ///oracle price token b was valued at
+    pub token_b_price: PodU64,
+}
+
+///Tokens were swapped
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Swapped {
+    ///user that swapped
+    pub user: Pubkey,
+    ///mint of the tokens paid in
+    pub source_mint: Pubkey,
+    ///mint of the tokens paid out
+    pub destination_mint: Pubkey,
+    ///source tokens paid in
+    pub amount_in: PodU64,
+    ///destination tokens paid out
+    pub amount_out: PodU64,
+    ///source tokens taken as the program fee
+    pub program_fee: PodU64,
+    ///source tokens taken as the swap fee
+    pub swap_fee: PodU64,
+    ///oracle price the source tokens were valued at
+    pub source_price: PodU64,
+    ///oracle price the destination tokens were valued at
+    pub destination_price: PodU64,
+}
+
+///The circuit breaker tripped on a price jump, swaps and deposits stop until it's reset or cools down.
+///Swap and Deposit fail on the price that trips it, the trip is only saved by Crank, Withdraw and AdminDeposit
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct BreakerTripped {
+    ///oracle price of token a that tripped the breaker
+    pub token_a_price: PodU64,
+    ///oracle price of token b that tripped the breaker
+    pub token_b_price: PodU64,
+    ///slot the breaker tripped in
+    pub slot: PodU64,
+}
+
+///The pool was closed and the swap state's lamports paid out to the admin
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PoolClosed {
+    ///admin the lamports were paid to
+    pub admin: Pubkey,
+    ///mint of the swap tokens
+    pub swap_mint: Pubkey,
+    ///lamports of the swap state paid out
+    pub lamports: PodU64,
+}
+
+///Events the program logs with sol_log_data.
+///
+///Every event is logged as a single data field, the version byte, the event tag and then the event's
+///fields in their zero-copy layout. New events get new tags, existing layouts only change with the version
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    ///tag 0
+    PoolInitialized(PoolInitialized),
+    ///tag 1
+    Deposited(Deposited),
+    ///tag 2
+    Withdrew(Withdrew),
+    ///tag 3
+    Swapped(Swapped),
+    ///tag 4
+    BreakerTripped(BreakerTripped),
+    ///tag 5
+    PoolClosed(PoolClosed),
+}
+
+impl Event {
+    ///tag and fields of the event
+    fn parts(&self) -> (u8, &[u8]) {
+        match self {
+            Event::PoolInitialized(event) => (0, bytemuck::bytes_of(event)),
+            Event::Deposited(event) => (1, bytemuck::bytes_of(event)),
+            Event::Withdrew(event) => (2, bytemuck::bytes_of(event)),
+            Event::Swapped(event) => (3, bytemuck::bytes_of(event)),
+            Event::BreakerTripped(event) => (4, bytemuck::bytes_of(event)),
+            Event::PoolClosed(event) => (5, bytemuck::bytes_of(event)),
+        }
+    }
+
+    ///Packs the event into its binary encoding
+    pub fn pack(&self) -> Vec<u8> {
+        let (tag, fields) = self.parts();
+        let mut buf = Vec::with_capacity(2 + fields.len());
+        buf.push(EVENT_VERSION);
+        buf.push(tag);
+        buf.extend_from_slice(fields);
+        buf
+    }
+
+    ///Unpacks an event from its binary encoding
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&version, rest) = input.split_first().ok_or(ProgramError::InvalidAccountData)?;
+        if version != EVENT_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (&tag, fields) = rest.split_first().ok_or(ProgramError::InvalidAccountData)?;
+
+        Ok(match tag {
+            0 => Event::PoolInitialized(Self::fields(fields)?),
+            1 => Event::Deposited(Self::fields(fields)?),
+            2 => Event::Withdrew(Self::fields(fields)?),
+            3 => Event::Swapped(Self::fields(fields)?),
+            4 => Event::BreakerTripped(Self::fields(fields)?),
+            5 => Event::PoolClosed(Self::fields(fields)?),
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+
+    fn fields<T: Pod>(fields: &[u8]) -> Result<T, ProgramError> {
+        bytemuck::try_from_bytes(fields)
+            .copied()
+            .map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    ///Logs the event with sol_log_data
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deposited, Event, EVENT_VERSION};
+    use crate::pod::PodU64;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+    #[test]
+    fn test_event_round_trip() {
+        let event = Event::Deposited(Deposited {
+            user: Pubkey::new_unique(),
+            token_a_amount: PodU64::from(100),
+            swap_tokens: PodU64::from(90),
+            token_a_price: PodU64::from(5),
+            token_b_price: PodU64::from(7),
+            ..Deposited::default()
+        });
+        let data = event.pack();
+        assert_eq!(data[..2], [EVENT_VERSION, 1]);
+        assert_eq!(data.len(), 2 + 32 + 5 * 8);
+        assert_eq!(Event::unpack(&data), Ok(event));
+
+        //wrong version, unknown tag and truncated fields are rejected
+        let mut bad = data.clone();
+        bad[0] = EVENT_VERSION + 1;
+        assert_eq!(Event::unpack(&bad), Err(ProgramError::InvalidAccountData));
+        bad[0] = EVENT_VERSION;
+        bad[1] = 6;
+        assert_eq!(Event::unpack(&bad), Err(ProgramError::InvalidAccountData));
+        assert_eq!(Event::unpack(&data[..data.len() - 1]), Err(ProgramError::InvalidAccountData));
+    }
+}
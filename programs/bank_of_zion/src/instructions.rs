@@ -1,7 +1,7 @@
 use {
     crate::{
         error::ZionError,
-        state::SwapState
+        state::{CurveType, PremiumParams, PriceMode, StableSwapCurve, MAX_BPS, MAX_TWAP_WINDOW_SECS},
         
     },
     solana_program::{
@@ -9,12 +9,12 @@ use {
         program_error::ProgramError,
         pubkey::Pubkey,
         sysvar,
-        program_pack::Pack,
+        program_pack::{Pack, Sealed},
     },
     std::{
         mem::size_of,
     },
-    arrayref::{array_ref, array_refs}
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs}
 };
 
 
@@ -23,31 +23,248 @@ use {
 #[derive(Clone, Debug, PartialEq)]
 pub enum ZionInstruction {
     ///Initialize the swap pool
-    Initialize (Initialize),
+    Initialize (InitializeParams),
 
-    ///Deposit initial liquidity for pools
+    ///Deposit initial liquidity for pools, returns a LiquidityQuote with the amounts moved through the return data.
+    ///The tokens' fallback and additional oracles are passed after the token program
     AdminDeposit(AdminDeposit),
 
-    ///Deposit liquidity into pools
+    ///Deposit liquidity into pools, returns a LiquidityQuote with the amounts moved through the return data.
+    ///The tokens' fallback and additional oracles and the price history, to record the oracle prices, are passed after the token program
     Deposit(Deposit),
 
-    ///Withdraw liquidity from pools
+    ///Withdraw liquidity from pools, returns a LiquidityQuote with the amounts moved through the return data.
+    ///The tokens' fallback and additional oracles are passed after the token program
     Withdraw(Withdraw),
 
-    ///Swap tokens
+    ///Swap tokens, returns a SwapQuote with the amounts moved through the return data.
+    ///The tokens' fallback and additional oracles and the price history are passed after the token program,
+    ///the price history is required by the Conservative price mode
     Swap(Swap),
 
     ///Close pool
-    ClosePool()
+    ClosePool(),
+
+    ///Rewrite a swap state still in the v1 layout into the current layout
+    MigrateState(),
+
+    ///Resume a pool paused by the circuit breaker
+    ResetCircuitBreaker(),
+
+    ///Record the oracle prices in the price history, creating it on the first call, and feed them through the
+    ///circuit breaker. The tokens' fallback and additional oracles are passed after the system program
+    Crank(),
+
+    ///Set the additional oracles and freshness limits of a token, the oracles are passed as accounts
+    SetOracleConfig(SetOracleConfig),
+
+    ///Set the oracle used for a token when its primary oracle isn't fresh, no oracle account removes it
+    SetFallbackOracle(SetFallbackOracle),
+
+    ///Quote a swap without moving tokens, returns a SwapQuote through the return data
+    Quote(Quote),
+
+    ///Quote a deposit without moving tokens, returns a LiquidityQuote through the return data
+    QuoteDeposit(Deposit),
+
+    ///Quote a withdraw without moving tokens, returns a LiquidityQuote through the return data
+    QuoteWithdraw(Withdraw),
 }
 
-/// Initialize instruction data
+/// Initialize instruction data, everything else is taken from the accounts
 #[repr(C)]
-#[derive(Clone,Debug, PartialEq)]
-pub struct Initialize {
-    /// all swap fees
-    pub swap_state: SwapState,
+#[derive(Clone,Debug, Default, PartialEq)]
+pub struct InitializeParams {
+    ///bump of the SwapState pda
+    pub bump: u8,
+    ///bump of the swap authority pda
+    pub swap_authority_bump: u8,
+    ///basis point fee applied to transactions that are given to the admin
+    pub program_fee: u64,
+    ///basis point fee applied to transactions that are given to the user
+    pub swap_fee: u64,
+    ///pricing curve of the pool, see [CurveType](../state/enum.CurveType.html)
+    pub curve_type: u8,
+    ///amplification coefficient for the StableSwap curve, must be zero for the other curves
+    pub amp: u64,
+    ///OraclePremium curve: basis points of the deviation from balance applied to the price, zero is the same as 10000
+    pub premium_slope_bps: u64,
+    ///OraclePremium curve: largest premium above the market price in basis points, zero for no cap
+    pub max_premium_bps: u64,
+    ///OraclePremium curve: largest discount below the market price in basis points, zero for no cap
+    pub max_discount_bps: u64,
+    ///OraclePremium curve: deviation from balance in basis points that is ignored, zero for none
+    pub dead_band_bps: u64,
+    ///largest swap payout in basis points of the destination vault, zero for no limit
+    pub max_trade_bps: u64,
+    ///largest deviation of the swap price from the oracle price in basis points, zero for no limit
+    pub max_price_deviation_bps: u64,
+    ///circuit breaker: largest oracle price move in basis points, zero disables the breaker
+    pub breaker_max_jump_bps: u64,
+    ///circuit breaker: slots a move has to happen in to count as a jump, zero for any number of slots
+    pub breaker_window_slots: u64,
+    ///circuit breaker: slots after tripping the pool resumes on its own, zero to wait for the admin
+    pub breaker_cooldown_slots: u64,
+    ///prices swaps are priced at, see [PriceMode](../state/enum.PriceMode.html)
+    pub price_mode: u8,
+    ///seconds the TWAP of the Conservative price mode is taken over, at most a day
+    pub twap_window_secs: u64,
+    ///product account token a's oracle has to price, default to accept any product
+    pub token_a_product: Pubkey,
+    ///product account token b's oracle has to price, default to accept any product
+    pub token_b_product: Pubkey,
+}
+impl Sealed for InitializeParams {}
+impl Pack for InitializeParams {
+    const LEN: usize = 172;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 172];
+        let (
+            bump,
+            swap_authority_bump,
+            program_fee,
+            swap_fee,
+            curve_type,
+            amp,
+            premium_slope_bps,
+            max_premium_bps,
+            max_discount_bps,
+            dead_band_bps,
+            max_trade_bps,
+            max_price_deviation_bps,
+            breaker_max_jump_bps,
+            breaker_window_slots,
+            breaker_cooldown_slots,
+            price_mode,
+            twap_window_secs,
+            token_a_product,
+            token_b_product,
+        ) = mut_array_refs![output, 1, 1, 8, 8, 1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 8, 32, 32];
+        *bump = self.bump.to_le_bytes();
+        *swap_authority_bump = self.swap_authority_bump.to_le_bytes();
+        *program_fee = self.program_fee.to_le_bytes();
+        *swap_fee = self.swap_fee.to_le_bytes();
+        *curve_type = self.curve_type.to_le_bytes();
+        *amp = self.amp.to_le_bytes();
+        *premium_slope_bps = self.premium_slope_bps.to_le_bytes();
+        *max_premium_bps = self.max_premium_bps.to_le_bytes();
+        *max_discount_bps = self.max_discount_bps.to_le_bytes();
+        *dead_band_bps = self.dead_band_bps.to_le_bytes();
+        *max_trade_bps = self.max_trade_bps.to_le_bytes();
+        *max_price_deviation_bps = self.max_price_deviation_bps.to_le_bytes();
+        *breaker_max_jump_bps = self.breaker_max_jump_bps.to_le_bytes();
+        *breaker_window_slots = self.breaker_window_slots.to_le_bytes();
+        *breaker_cooldown_slots = self.breaker_cooldown_slots.to_le_bytes();
+        *price_mode = self.price_mode.to_le_bytes();
+        *twap_window_secs = self.twap_window_secs.to_le_bytes();
+        token_a_product.copy_from_slice(self.token_a_product.as_ref());
+        token_b_product.copy_from_slice(self.token_b_product.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 172];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            bump,
+            swap_authority_bump,
+            program_fee,
+            swap_fee,
+            curve_type,
+            amp,
+            premium_slope_bps,
+            max_premium_bps,
+            max_discount_bps,
+            dead_band_bps,
+            max_trade_bps,
+            max_price_deviation_bps,
+            breaker_max_jump_bps,
+            breaker_window_slots,
+            breaker_cooldown_slots,
+            price_mode,
+            twap_window_secs,
+            token_a_product,
+            token_b_product,
+        ) = array_refs![input, 1, 1, 8, 8, 1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 8, 32, 32];
+        Ok(Self {
+            bump: u8::from_le_bytes(*bump),
+            swap_authority_bump: u8::from_le_bytes(*swap_authority_bump),
+            program_fee: u64::from_le_bytes(*program_fee),
+            swap_fee: u64::from_le_bytes(*swap_fee),
+            curve_type: u8::from_le_bytes(*curve_type),
+            amp: u64::from_le_bytes(*amp),
+            premium_slope_bps: u64::from_le_bytes(*premium_slope_bps),
+            max_premium_bps: u64::from_le_bytes(*max_premium_bps),
+            max_discount_bps: u64::from_le_bytes(*max_discount_bps),
+            dead_band_bps: u64::from_le_bytes(*dead_band_bps),
+            max_trade_bps: u64::from_le_bytes(*max_trade_bps),
+            max_price_deviation_bps: u64::from_le_bytes(*max_price_deviation_bps),
+            breaker_max_jump_bps: u64::from_le_bytes(*breaker_max_jump_bps),
+            breaker_window_slots: u64::from_le_bytes(*breaker_window_slots),
+            breaker_cooldown_slots: u64::from_le_bytes(*breaker_cooldown_slots),
+            price_mode: u8::from_le_bytes(*price_mode),
+            twap_window_secs: u64::from_le_bytes(*twap_window_secs),
+            token_a_product: Pubkey::new_from_array(*token_a_product),
+            token_b_product: Pubkey::new_from_array(*token_b_product),
+        })
+    }
+}
+
+impl InitializeParams {
+    ///premium params as they are stored in the swap state
+    pub fn premium_params(&self) -> PremiumParams {
+        PremiumParams {
+            slope_bps: self.premium_slope_bps.into(),
+            max_premium_bps: self.max_premium_bps.into(),
+            max_discount_bps: self.max_discount_bps.into(),
+            dead_band_bps: self.dead_band_bps.into(),
+        }
+    }
+
+    ///validate the parameters before any account is touched
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        if self.program_fee > MAX_BPS || self.swap_fee > MAX_BPS {
+            return Err(ZionError::InvalidFee.into());
+        }
+
+        //combined fees can't take more than the whole trade
+        if self.program_fee + self.swap_fee > MAX_BPS {
+            return Err(ZionError::InvalidFee.into());
+        }
+
+        //only the StableSwap curve takes an amplification coefficient and only the OraclePremium curve a premium
+        let premium_params = self.premium_params();
+        let curve_valid = match CurveType::try_from(self.curve_type)? {
+            CurveType::StableSwap => {
+                (StableSwapCurve::MIN_AMP..=StableSwapCurve::MAX_AMP).contains(&self.amp)
+                    && premium_params == PremiumParams::default()
+            },
+            CurveType::ConstantProduct => self.amp == 0 && premium_params == PremiumParams::default(),
+            CurveType::OraclePremium => self.amp == 0,
+        };
+        if !curve_valid {
+            return Err(ZionError::InvalidCurve.into());
+        }
+        premium_params.validate()?;
+
+        if self.max_trade_bps > MAX_BPS || self.max_price_deviation_bps > MAX_BPS {
+            return Err(ZionError::InvalidTradeLimit.into());
+        }
+
+        //the Conservative mode needs a window to average over
+        if PriceMode::try_from(self.price_mode)? == PriceMode::Conservative && self.twap_window_secs == 0 {
+            return Err(ZionError::InvalidPriceMode.into());
+        }
+
+        //the price history has to cover the window with its spaced observations
+        if self.twap_window_secs > MAX_TWAP_WINDOW_SECS {
+            return Err(ZionError::InvalidPriceMode.into());
+        }
+
+        Ok(())
+    }
 }
+
 ///Admin to deposit initial liquidity
 #[repr(C)]
 #[derive(Clone,Debug, PartialEq)]
@@ -105,6 +322,54 @@ impl Swap {
     pub const LEN: usize = 8;
 }
 
+///Additional oracles and freshness limits of one of the pool's tokens
+#[repr(C)]
+#[derive(Clone,Debug, PartialEq)]
+pub struct SetOracleConfig {
+    /// zero for token a, one for token b
+    pub token: u8,
+
+    /// fresh feeds needed to price the token, zero is the same as one
+    pub quorum: u8,
+
+    /// largest age of a feed in seconds, zero for no limit
+    pub max_age_secs: u64,
+
+    /// largest confidence interval of a feed in basis points of its price, zero for no limit
+    pub max_confidence_bps: u64,
+}
+impl SetOracleConfig { 
+    ///length of SetOracleConfig struct
+    pub const LEN: usize = 18;
+}
+
+///Fallback oracle of one of the pool's tokens
+#[repr(C)]
+#[derive(Clone,Debug, PartialEq)]
+pub struct SetFallbackOracle {
+    /// zero for token a, one for token b
+    pub token: u8,
+}
+impl SetFallbackOracle { 
+    ///length of SetFallbackOracle struct
+    pub const LEN: usize = 1;
+}
+
+///Quote a swap in either direction
+#[repr(C)]
+#[derive(Clone,Debug, PartialEq)]
+pub struct Quote {
+    /// zero to swap token a for token b, one to swap token b for token a
+    pub direction: u8,
+
+    /// source tokens to swap
+    pub amount: u64,
+}
+impl Quote { 
+    ///length of Quote struct
+    pub const LEN: usize = 9;
+}
+
 impl ZionInstruction {
     /// Unpacks a byte buffer into a [ZionInstruction](enum.ZionInstruction.html).
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
@@ -117,13 +382,14 @@ impl ZionInstruction {
             
             Ok(match tag {
                 0 => {
-                    let swap_state = SwapState::unpack_from_slice(rest)?;
+                    let data = rest.get(..InitializeParams::LEN).ok_or(InvalidInstruction)?;
                     Self::Initialize (
-                        Initialize { swap_state}
+                        InitializeParams::unpack_from_slice(data)?
                     )
                 },
                 1 => {
-                    let input = array_ref![rest, 0, AdminDeposit::LEN];
+                    let data = rest.get(..AdminDeposit::LEN).ok_or(InvalidInstruction)?;
+                    let input = array_ref![data, 0, AdminDeposit::LEN];
                     
                     let (
                         token_a_deposit,
@@ -139,7 +405,8 @@ impl ZionInstruction {
                     )
                 },
                 2 => {
-                    let data = array_ref![rest, 0, Deposit::LEN];
+                    let data = rest.get(..Deposit::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, Deposit::LEN];
                     
                     let (
                         token_a_deposit,
@@ -155,7 +422,8 @@ impl ZionInstruction {
                     )
                 },
                 3 => {
-                    let data = array_ref![rest, 0, Withdraw::LEN];
+                    let data = rest.get(..Withdraw::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, Withdraw::LEN];
                     
                     let (
                         token_a_withdraw,
@@ -171,7 +439,8 @@ impl ZionInstruction {
                     )
                 },
                 4 => {
-                    let data = array_ref![rest, 0, Swap::LEN]; 
+                    let data = rest.get(..Swap::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, Swap::LEN]; 
 
                     Self::Swap (
                         Swap { 
@@ -180,6 +449,87 @@ impl ZionInstruction {
 
                     )
                 },
+                5 => Self::MigrateState(),
+                6 => Self::ResetCircuitBreaker(),
+                7 => Self::Crank(),
+                8 => {
+                    let data = rest.get(..SetOracleConfig::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, SetOracleConfig::LEN];
+
+                    let (
+                        token,
+                        quorum,
+                        max_age_secs,
+                        max_confidence_bps,
+                    ) = array_refs![data, 1, 1, 8, 8];
+
+                    Self::SetOracleConfig (
+                        SetOracleConfig {
+                            token: u8::from_le_bytes(*token),
+                            quorum: u8::from_le_bytes(*quorum),
+                            max_age_secs: u64::from_le_bytes(*max_age_secs),
+                            max_confidence_bps: u64::from_le_bytes(*max_confidence_bps),
+                        }
+                    )
+                },
+                9 => {
+                    let data = rest.get(..SetFallbackOracle::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, SetFallbackOracle::LEN];
+
+                    Self::SetFallbackOracle (
+                        SetFallbackOracle {
+                            token: u8::from_le_bytes(*data),
+                        }
+                    )
+                },
+                10 => {
+                    let data = rest.get(..Quote::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, Quote::LEN];
+
+                    let (
+                        direction,
+                        amount,
+                    ) = array_refs![data, 1, 8];
+
+                    Self::Quote (
+                        Quote {
+                            direction: u8::from_le_bytes(*direction),
+                            amount: u64::from_le_bytes(*amount),
+                        }
+                    )
+                },
+                11 => {
+                    let data = rest.get(..Deposit::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, Deposit::LEN];
+                    
+                    let (
+                        token_a_deposit,
+                        token_b_deposit,
+                    ) = array_refs![data, 8, 8];
+
+                    Self::QuoteDeposit (
+                        Deposit { 
+                            token_a_deposit: u64::from_le_bytes(*token_a_deposit),
+                            token_b_deposit: u64::from_le_bytes(*token_b_deposit)
+                        }
+                    )
+                },
+                12 => {
+                    let data = rest.get(..Withdraw::LEN).ok_or(InvalidInstruction)?;
+                    let data = array_ref![data, 0, Withdraw::LEN];
+                    
+                    let (
+                        token_a_withdraw,
+                        token_b_withdraw,
+                    ) = array_refs![data, 8, 8];
+
+                    Self::QuoteWithdraw (
+                        Withdraw { 
+                            token_a_withdraw: u64::from_le_bytes(*token_a_withdraw),
+                            token_b_withdraw: u64::from_le_bytes(*token_b_withdraw)
+                        }
+                    )
+                },
                 _ => return Err(ZionError::InvalidInstruction.into()),
 
             })
@@ -190,11 +540,11 @@ impl ZionInstruction {
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
         match &*self {
-            Self::Initialize(Initialize { swap_state }) => {
+            Self::Initialize(params) => {
                 buf.push(0);
-                let mut state_slice = [0u8; SwapState::LEN];
-                Pack::pack_into_slice(swap_state, &mut state_slice[..]);
-                buf.extend_from_slice(&state_slice);
+                let mut params_slice = [0u8; InitializeParams::LEN];
+                Pack::pack_into_slice(params, &mut params_slice[..]);
+                buf.extend_from_slice(&params_slice);
             },
             Self::AdminDeposit( AdminDeposit {token_a_deposit, token_b_deposit}) => {
                 buf.push(1);
@@ -215,40 +565,90 @@ impl ZionInstruction {
                 buf.push(4);
                 buf.extend_from_slice(&amount.to_le_bytes());
             },
-            Self::ClosePool() => {}
+            Self::ClosePool() => {},
+            Self::MigrateState() => {
+                buf.push(5);
+            },
+            Self::ResetCircuitBreaker() => {
+                buf.push(6);
+            },
+            Self::Crank() => {
+                buf.push(7);
+            },
+            Self::SetOracleConfig( SetOracleConfig {token, quorum, max_age_secs, max_confidence_bps}) => {
+                buf.push(8);
+                buf.push(*token);
+                buf.push(*quorum);
+                buf.extend_from_slice(&max_age_secs.to_le_bytes());
+                buf.extend_from_slice(&max_confidence_bps.to_le_bytes());
+            },
+            Self::SetFallbackOracle( SetFallbackOracle {token}) => {
+                buf.push(9);
+                buf.push(*token);
+            },
+            Self::Quote( Quote {direction, amount}) => {
+                buf.push(10);
+                buf.push(*direction);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            },
+            Self::QuoteDeposit( Deposit {token_a_deposit, token_b_deposit}) => {
+                buf.push(11);
+                buf.extend_from_slice(&token_a_deposit.to_le_bytes());
+                buf.extend_from_slice(&token_b_deposit.to_le_bytes());
+            },
+            Self::QuoteWithdraw( Withdraw {token_a_withdraw, token_b_withdraw}) => {
+                buf.push(12);
+                buf.extend_from_slice(&token_a_withdraw.to_le_bytes());
+                buf.extend_from_slice(&token_b_withdraw.to_le_bytes());
+            },
         }
         buf
     }
 }
 
 /// Creates an 'initialize' instruction.
+#[allow(clippy::too_many_arguments)]
 pub fn initialize(
-    swap_state: SwapState,
-    swap_state_pubkey: &Pubkey
+    admin_pubkey: &Pubkey,
+    swap_authority_pubkey: &Pubkey,
+    swap_mint_pubkey: &Pubkey,
+    swap_state_pubkey: &Pubkey,
+
+    token_a_mint_pubkey: &Pubkey,
+    token_a_vault_pubkey: &Pubkey,
+    token_a_fee_vault_pubkey: &Pubkey,
+    token_a_oracle_pubkey: &Pubkey,
+
+    token_b_mint_pubkey: &Pubkey,
+    token_b_vault_pubkey: &Pubkey,
+    token_b_fee_vault_pubkey: &Pubkey,
+    token_b_oracle_pubkey: &Pubkey,
+
+    params: InitializeParams,
 ) -> Instruction {
     
     let accounts = vec![
-        AccountMeta::new(swap_state.admin, true),
-        AccountMeta::new(swap_state.swap_authority, false),
-        AccountMeta::new_readonly(swap_state.swap_mint, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*swap_authority_pubkey, false),
+        AccountMeta::new_readonly(*swap_mint_pubkey, false),
         AccountMeta::new(*swap_state_pubkey, false),
 
-        AccountMeta::new_readonly(swap_state.token_a.mint, false),
-        AccountMeta::new_readonly(swap_state.token_a.vault, false),
-        AccountMeta::new_readonly(swap_state.token_a.fee_vault, false),
-        AccountMeta::new_readonly(swap_state.token_a.oracle, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_a_fee_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_a_oracle_pubkey, false),
 
-        AccountMeta::new_readonly(swap_state.token_b.mint, false),
-        AccountMeta::new_readonly(swap_state.token_b.vault, false),
-        AccountMeta::new_readonly(swap_state.token_b.fee_vault, false),
-        AccountMeta::new_readonly(swap_state.token_b.oracle, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_b_fee_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_b_oracle_pubkey, false),
         
         AccountMeta::new_readonly(spl_token::ID, false),
         AccountMeta::new_readonly(sysvar::rent::id(), false),
         AccountMeta::new_readonly(solana_program::system_program::id(), false),
     ];
 
-    let init_data = ZionInstruction::Initialize(Initialize { swap_state });
+    let init_data = ZionInstruction::Initialize(params);
     let data = init_data.pack();
 
     Instruction {
@@ -281,7 +681,7 @@ pub fn admin_deposit(
         AccountMeta::new_readonly(*admin_pubkey, true),
         AccountMeta::new_readonly(*swap_authority_pubkey, false),
         AccountMeta::new(*swap_mint_pubkey, false),
-        AccountMeta::new_readonly(*swap_state_pubkey, false),
+        AccountMeta::new(*swap_state_pubkey, false),
         AccountMeta::new(*admin_swap_wallet_pubkey, false),
 
         AccountMeta::new(*token_a_mint_pubkey, false),
@@ -331,7 +731,7 @@ pub fn deposit(
     
     let accounts = vec![
         AccountMeta::new_readonly(*user_pubkey, true),
-        AccountMeta::new_readonly(*swap_state_pubkey, false),
+        AccountMeta::new(*swap_state_pubkey, false),
         AccountMeta::new_readonly(*swap_authority_pubkey, false),
         AccountMeta::new(*swap_mint_pubkey, false),
         AccountMeta::new(*user_swap_wallet_pubkey, false),
@@ -384,7 +784,7 @@ pub fn withdraw(
     
     let accounts = vec![
         AccountMeta::new_readonly(*user_pubkey, true),
-        AccountMeta::new_readonly(*swap_state_pubkey, false),
+        AccountMeta::new(*swap_state_pubkey, false),
         AccountMeta::new_readonly(*swap_authority_pubkey, false),
         AccountMeta::new(*swap_mint_pubkey, false),
         AccountMeta::new(*user_swap_wallet_pubkey, false),
@@ -433,7 +833,7 @@ pub fn swap(
     
     let accounts = vec![
         AccountMeta::new_readonly(*user_pubkey, true),
-        AccountMeta::new_readonly(*swap_state_pubkey, false),
+        AccountMeta::new(*swap_state_pubkey, false),
         AccountMeta::new_readonly(*swap_authority_pubkey, false),
         
         AccountMeta::new(*source_user_pubkey, false),
@@ -481,4 +881,270 @@ pub fn close_pool(
         accounts,
         data,
     }
-}
\ No newline at end of file
+}
+
+/// Creates an 'migrate_state' instruction.
+pub fn migrate_state(
+    admin_pubkey: &Pubkey,
+    swap_state_pubkey: &Pubkey,
+) -> Instruction {
+    
+    let accounts = vec![
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*swap_state_pubkey, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    let init_data = ZionInstruction::MigrateState();
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an 'reset_circuit_breaker' instruction.
+pub fn reset_circuit_breaker(
+    admin_pubkey: &Pubkey,
+    swap_state_pubkey: &Pubkey,
+) -> Instruction {
+    
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*swap_state_pubkey, false),
+    ];
+
+    let init_data = ZionInstruction::ResetCircuitBreaker();
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an 'crank' instruction.
+pub fn crank(
+    payer_pubkey: &Pubkey,
+    swap_state_pubkey: &Pubkey,
+    price_history_pubkey: &Pubkey,
+    token_a_oracle_pubkey: &Pubkey,
+    token_b_oracle_pubkey: &Pubkey,
+) -> Instruction {
+    
+    let accounts = vec![
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new(*swap_state_pubkey, false),
+        AccountMeta::new(*price_history_pubkey, false),
+        AccountMeta::new_readonly(*token_a_oracle_pubkey, false),
+        AccountMeta::new_readonly(*token_b_oracle_pubkey, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    let init_data = ZionInstruction::Crank();
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an 'set_oracle_config' instruction.
+pub fn set_oracle_config(
+    admin_pubkey: &Pubkey,
+    swap_state_pubkey: &Pubkey,
+    extra_oracle_pubkeys: &[Pubkey],
+
+    token: u8,
+    quorum: u8,
+    max_age_secs: u64,
+    max_confidence_bps: u64,
+) -> Instruction {
+    
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*swap_state_pubkey, false),
+    ];
+    accounts.extend(extra_oracle_pubkeys.iter().map(|oracle| AccountMeta::new_readonly(*oracle, false)));
+
+    let init_data = ZionInstruction::SetOracleConfig(SetOracleConfig { token, quorum, max_age_secs, max_confidence_bps });
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an 'set_fallback_oracle' instruction.
+pub fn set_fallback_oracle(
+    admin_pubkey: &Pubkey,
+    swap_state_pubkey: &Pubkey,
+    fallback_oracle_pubkey: Option<&Pubkey>,
+
+    token: u8,
+) -> Instruction {
+    
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*swap_state_pubkey, false),
+    ];
+    if let Some(fallback_oracle_pubkey) = fallback_oracle_pubkey {
+        accounts.push(AccountMeta::new_readonly(*fallback_oracle_pubkey, false));
+    }
+
+    let init_data = ZionInstruction::SetFallbackOracle(SetFallbackOracle { token });
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an 'quote' instruction.
+pub fn quote(
+    swap_state_pubkey: &Pubkey,
+
+    token_a_vault_pubkey: &Pubkey,
+    token_a_oracle_pubkey: &Pubkey,
+
+    token_b_vault_pubkey: &Pubkey,
+    token_b_oracle_pubkey: &Pubkey,
+
+    direction: u8,
+    amount: u64,
+) -> Instruction {
+    
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_state_pubkey, false),
+
+        AccountMeta::new_readonly(*token_a_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_a_oracle_pubkey, false),
+
+        AccountMeta::new_readonly(*token_b_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_b_oracle_pubkey, false),
+    ];
+
+    let init_data = ZionInstruction::Quote(Quote { direction, amount });
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+///accounts shared by the deposit and withdraw quotes
+#[allow(clippy::too_many_arguments)]
+fn quote_liquidity_accounts(
+    swap_state_pubkey: &Pubkey,
+    swap_mint_pubkey: &Pubkey,
+
+    token_a_vault_pubkey: &Pubkey,
+    token_a_fee_vault: &Pubkey,
+    token_a_oracle_pubkey: &Pubkey,
+
+    token_b_vault_pubkey: &Pubkey,
+    token_b_fee_vault: &Pubkey,
+    token_b_oracle_pubkey: &Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(*swap_state_pubkey, false),
+        AccountMeta::new_readonly(*swap_mint_pubkey, false),
+
+        AccountMeta::new_readonly(*token_a_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_a_fee_vault, false),
+        AccountMeta::new_readonly(*token_a_oracle_pubkey, false),
+
+        AccountMeta::new_readonly(*token_b_vault_pubkey, false),
+        AccountMeta::new_readonly(*token_b_fee_vault, false),
+        AccountMeta::new_readonly(*token_b_oracle_pubkey, false),
+    ]
+}
+
+/// Creates an 'quote_deposit' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn quote_deposit(
+    swap_state_pubkey: &Pubkey,
+    swap_mint_pubkey: &Pubkey,
+
+    token_a_vault_pubkey: &Pubkey,
+    token_a_fee_vault: &Pubkey,
+    token_a_oracle_pubkey: &Pubkey,
+
+    token_b_vault_pubkey: &Pubkey,
+    token_b_fee_vault: &Pubkey,
+    token_b_oracle_pubkey: &Pubkey,
+
+    token_a_deposit: u64,
+    token_b_deposit: u64,
+) -> Instruction {
+    
+    let accounts = quote_liquidity_accounts(
+        swap_state_pubkey,
+        swap_mint_pubkey,
+        token_a_vault_pubkey,
+        token_a_fee_vault,
+        token_a_oracle_pubkey,
+        token_b_vault_pubkey,
+        token_b_fee_vault,
+        token_b_oracle_pubkey,
+    );
+
+    let init_data = ZionInstruction::QuoteDeposit(Deposit { token_a_deposit, token_b_deposit });
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
+
+/// Creates an 'quote_withdraw' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn quote_withdraw(
+    swap_state_pubkey: &Pubkey,
+    swap_mint_pubkey: &Pubkey,
+
+    token_a_vault_pubkey: &Pubkey,
+    token_a_fee_vault: &Pubkey,
+    token_a_oracle_pubkey: &Pubkey,
+
+    token_b_vault_pubkey: &Pubkey,
+    token_b_fee_vault: &Pubkey,
+    token_b_oracle_pubkey: &Pubkey,
+
+    token_a_withdraw: u64,
+    token_b_withdraw: u64,
+) -> Instruction {
+    
+    let accounts = quote_liquidity_accounts(
+        swap_state_pubkey,
+        swap_mint_pubkey,
+        token_a_vault_pubkey,
+        token_a_fee_vault,
+        token_a_oracle_pubkey,
+        token_b_vault_pubkey,
+        token_b_fee_vault,
+        token_b_oracle_pubkey,
+    );
+
+    let init_data = ZionInstruction::QuoteWithdraw(Withdraw { token_a_withdraw, token_b_withdraw });
+    let data = init_data.pack();
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data,
+    }
+}
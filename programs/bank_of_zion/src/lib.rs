@@ -13,10 +13,26 @@ pub mod processor;
 pub mod state;
 ///contains all of the cross program invocations
 pub mod cpi;
+///Alignment 1 types used by the zero-copy account layouts
+pub mod pod;
+///Layouts of the data the program returns through set_return_data
+pub mod return_data;
+///Events the program logs with sol_log_data
+pub mod events;
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
 
+///Pyth program the pool's oracles have to be owned by, mainnet unless built with the devnet or localnet feature
+pub mod oracle_program {
+    #[cfg(not(any(feature = "devnet", feature = "localnet")))]
+    solana_program::declare_id!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+    #[cfg(all(feature = "devnet", not(feature = "localnet")))]
+    solana_program::declare_id!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+    #[cfg(feature = "localnet")]
+    solana_program::declare_id!("GS9ftm9H95koKobmomiJeThUY1zPwJpvikQJT6jiXFgB");
+}
+
 // Export current sdk types for downstream users building with a different sdk version
 pub use solana_program;
 
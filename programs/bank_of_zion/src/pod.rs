@@ -0,0 +1,52 @@
+use bytemuck::{Pod, Zeroable};
+
+///u64 stored as little endian bytes so account layouts keep an alignment of 1
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PodU64(pub [u8; 8]);
+impl From<u64> for PodU64 {
+    fn from(n: u64) -> Self {
+        Self(n.to_le_bytes())
+    }
+}
+impl From<PodU64> for u64 {
+    fn from(pod: PodU64) -> Self {
+        Self::from_le_bytes(pod.0)
+    }
+}
+
+///bool stored as a single byte, anything other than 0 or 1 is rejected when the account is loaded
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PodBool(pub u8);
+impl PodBool {
+    ///true if the byte holds a valid bool
+    pub fn is_valid(&self) -> bool {
+        self.0 <= 1
+    }
+}
+impl From<bool> for PodBool {
+    fn from(b: bool) -> Self {
+        Self(b as u8)
+    }
+}
+impl From<PodBool> for bool {
+    fn from(pod: PodBool) -> Self {
+        pod.0 != 0
+    }
+}
+
+///i64 stored as little endian bytes so account layouts keep an alignment of 1
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PodI64(pub [u8; 8]);
+impl From<i64> for PodI64 {
+    fn from(n: i64) -> Self {
+        Self(n.to_le_bytes())
+    }
+}
+impl From<PodI64> for i64 {
+    fn from(pod: PodI64) -> Self {
+        Self::from_le_bytes(pod.0)
+    }
+}
@@ -2,8 +2,10 @@ use solana_program::program_pack::Pack;
 
 use {
     crate::error::ZionError,
-    crate::state::{SwapState,Token, AUTHORITY_PREFIX},
-    crate::instructions::{ZionInstruction, Initialize, AdminDeposit, Deposit, Withdraw, Swap},
+    crate::state::{SwapState,Token, AUTHORITY_PREFIX, BreakerStatus, CircuitBreaker, OracleConfig, PriceHistory, PriceMode, RoundDirection, MAX_ORACLES},
+    crate::instructions::{ZionInstruction, InitializeParams, AdminDeposit, Deposit, Withdraw, Swap, SetOracleConfig, SetFallbackOracle, Quote},
+    crate::return_data::{LiquidityQuote, SwapQuote},
+    crate::events::{BreakerTripped, Deposited, Event, PoolClosed, PoolInitialized, Swapped, Withdrew},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
         pubkey::{Pubkey, PUBKEY_BYTES},
@@ -12,17 +14,19 @@ use {
         program_option::COption,
         program_error::ProgramError,
         program_memory::sol_memcmp,
-        sysvar::{rent::Rent, Sysvar},
+        program::set_return_data,
+        sysvar::{clock::Clock, rent::Rent, Sysvar},
         system_program,
     },
     crate::cpi::{
         create_pda_account,
+        realloc_account,
         token_burn,
         token_mint_to,
         token_transfer,
         token_transfer_signed,
     },
-    pyth_sdk_solana::{load_price_feed_from_account_info, PriceFeed, Price}
+    pyth_sdk_solana::{load_price_feed_from_account_info, state::{load_price_account, PriceStatus}}
 };
 
 
@@ -90,7 +94,7 @@ impl Processor {
                     &[swap_authority_bump],
                 ],
                 &crate::id(),
-            ).expect("Invalid swap authority");
+            ).map_err(|_| ZionError::InvalidSwapAuthority)?;
 
         if cmp_pubkeys(swap_authority.key, &authority) {
             return Ok(())
@@ -98,6 +102,297 @@ impl Processor {
         return Err(ZionError::InvalidSwapAuthority.into())
     }
 
+    ///Feed the oracle prices through the circuit breaker. Pausable instructions fail with BreakerTripped on the
+    ///price that trips it, which rolls the trip back, and with PoolPaused while it's tripped. The trip is saved by
+    ///the instructions the breaker doesn't pause: Crank, Withdraw and AdminDeposit
+    fn check_circuit_breaker(
+        swap_state: &mut SwapState,
+        token_a_price: u64,
+        token_b_price: u64,
+        pausable: bool,
+    ) -> ProgramResult {
+        let slot = Clock::get()?.slot;
+        match swap_state.breaker.observe(token_a_price, token_b_price, slot) {
+            BreakerStatus::Active => Ok(()),
+            BreakerStatus::Tripped => {
+                msg!("Oracle price jumped, circuit breaker tripped");
+                Event::BreakerTripped(BreakerTripped {
+                    token_a_price: token_a_price.into(),
+                    token_b_price: token_b_price.into(),
+                    slot: slot.into(),
+                }).emit();
+
+                if pausable {
+                    return Err(ZionError::BreakerTripped.into());
+                }
+                Ok(())
+            },
+            BreakerStatus::Paused if pausable => Err(ZionError::PoolPaused.into()),
+            BreakerStatus::Paused => Ok(()),
+        }
+    }
+
+    ///Price of a token, the median of its fresh oracle feeds. The fallback oracle stands in for the primary
+    ///oracle when it isn't fresh. Fallback and additional oracles are looked up by key in the remaining
+    ///accounts and have to be passed in whenever they are read, only feeds that aren't fresh drop out
+    fn get_token_price(
+        oracle_info: &AccountInfo,
+        fallback_oracle: &Pubkey,
+        oracle_config: &OracleConfig,
+        remaining_accounts: &[AccountInfo],
+        now: i64,
+    ) -> Result<u64, ProgramError> {
+        let (price, _) = Self::get_token_prices(oracle_info, fallback_oracle, oracle_config, remaining_accounts, now, false)?;
+        Ok(price)
+    }
+
+    ///Price of a token as get_token_price reads it and, when with_ema is set, the median EMA of the same feeds.
+    ///A feed whose EMA doesn't pass the oracle config's checks drops out of the EMA, which then has to meet the quorum
+    fn get_token_prices(
+        oracle_info: &AccountInfo,
+        fallback_oracle: &Pubkey,
+        oracle_config: &OracleConfig,
+        remaining_accounts: &[AccountInfo],
+        now: i64,
+        with_ema: bool,
+    ) -> Result<(u64, Option<u64>), ProgramError> {
+        let mut prices = Vec::with_capacity(MAX_ORACLES);
+        let mut ema_prices = Vec::with_capacity(MAX_ORACLES);
+        let mut push = |(price, ema_price): (u64, Option<u64>)| {
+            prices.push(price);
+            ema_prices.extend(ema_price);
+        };
+
+        match Self::load_oracle_price(oracle_info, oracle_config, now) {
+            Some(price) => push(price),
+            None if *fallback_oracle != Pubkey::default() => {
+                let fallback_oracle_info = Self::find_oracle(remaining_accounts, fallback_oracle)?;
+                let fallback_price = Self::load_oracle_price(fallback_oracle_info, oracle_config, now);
+                match fallback_price {
+                    Some(price) => {
+                        msg!("Oracle {} isn't fresh, priced from fallback oracle {}", oracle_info.key, fallback_oracle);
+                        push(price);
+                    },
+                    None => msg!("Oracle {} and fallback oracle {} aren't fresh", oracle_info.key, fallback_oracle),
+                }
+            },
+            None => {},
+        }
+        for oracle in oracle_config.extra_oracles() {
+            let extra_oracle_info = Self::find_oracle(remaining_accounts, oracle)?;
+            if let Some(price) = Self::load_oracle_price(extra_oracle_info, oracle_config, now) {
+                push(price);
+            }
+        }
+
+        let price = oracle_config.aggregate(&mut prices)?;
+        let ema_price = if with_ema {
+            Some(oracle_config.aggregate(&mut ema_prices)?)
+        } else {
+            None
+        };
+        Ok((price, ema_price))
+    }
+
+    ///configured oracle among the remaining accounts, leaving one out would let the caller pick the feeds
+    fn find_oracle<'a, 'b>(remaining_accounts: &'a [AccountInfo<'b>], oracle: &Pubkey) -> Result<&'a AccountInfo<'b>, ZionError> {
+        remaining_accounts
+            .iter()
+            .find(|account| cmp_pubkeys(account.key, oracle))
+            .ok_or(ZionError::MissingOracle)
+    }
+
+    ///price and EMA of a single feed, None if it can't be read, isn't trading or isn't fresh.
+    ///The EMA is None when it doesn't pass the same checks as the price
+    fn load_oracle_price(
+        oracle_info: &AccountInfo,
+        oracle_config: &OracleConfig,
+        now: i64,
+    ) -> Option<(u64, Option<u64>)> {
+        let oracle_data = oracle_info.try_borrow_data().ok()?;
+        let price_account = load_price_account(&oracle_data).ok()?;
+
+        //a feed that isn't trading only carries the last price it traded at
+        if price_account.agg.status != PriceStatus::Trading {
+            return None;
+        }
+
+        let price_feed = price_account.to_price_feed(oracle_info.key);
+        let price = price_feed.get_price_unchecked();
+        let price = oracle_config.accept(price.price, price.conf, price.publish_time, now)?;
+        let ema_price = price_feed.get_ema_price_unchecked();
+        let ema_price = oracle_config.accept(ema_price.price, ema_price.conf, ema_price.publish_time, now);
+        Some((price, ema_price))
+    }
+
+    ///Check that an oracle is owned by the cluster's oracle program, holds a price account and,
+    ///unless expected_product is the default pubkey, prices that product
+    fn validate_oracle_account(oracle_info: &AccountInfo, expected_product: &Pubkey) -> ProgramResult {
+        if !cmp_pubkeys(oracle_info.owner, &crate::oracle_program::id()) {
+            return Err(ZionError::OracleWrongOwner.into());
+        }
+
+        let oracle_data = oracle_info.try_borrow_data()?;
+        let price_account = load_price_account(&oracle_data).map_err(|_| ZionError::InvalidOracle)?;
+        if *expected_product != Pubkey::default() && !cmp_pubkeys(&price_account.prod, expected_product) {
+            return Err(ZionError::OracleProductMismatch.into());
+        }
+
+        Ok(())
+    }
+
+    ///A fallback oracle only stands in for a primary oracle that isn't fresh, without a max age a primary
+    ///oracle that stopped updating is fresh forever and the fallback is never read
+    fn validate_fallback_config(fallback_oracle: &Pubkey, oracle_config: &OracleConfig) -> ProgramResult {
+        if *fallback_oracle != Pubkey::default() && u64::from(oracle_config.max_age_secs) == 0 {
+            msg!("A fallback oracle needs a max age for the primary oracle");
+            return Err(ZionError::InvalidOracleConfig.into());
+        }
+
+        Ok(())
+    }
+
+    ///price history among the remaining accounts
+    fn find_price_history<'a, 'b>(remaining_accounts: &'a [AccountInfo<'b>]) -> Option<&'a AccountInfo<'b>> {
+        let (price_history_key, _) = PriceHistory::find_address();
+        remaining_accounts.iter().find(|account| cmp_pubkeys(account.key, &price_history_key))
+    }
+
+    ///Record the oracle prices in the price history if it was passed in, returns the TWAP over the pool's window
+    fn update_price_history(
+        price_history_info: Option<&AccountInfo>,
+        swap_state: &SwapState,
+        token_a_price: u64,
+        token_b_price: u64,
+    ) -> Result<Option<(u64, u64)>, ProgramError> {
+        let price_history_info = match price_history_info {
+            Some(price_history_info) => price_history_info,
+            None => return Ok(None),
+        };
+
+        //validate price history
+        PriceHistory::validate_price_history_key(price_history_info.key)?;
+        if price_history_info.owner != &crate::id() {
+            return Err(ZionError::InvalidPriceHistory.into());
+        }
+
+        let mut price_history_data = price_history_info.try_borrow_mut_data()?;
+        let price_history = PriceHistory::load_mut(&mut price_history_data)?;
+
+        let clock = Clock::get()?;
+        let min_gap_secs = PriceHistory::min_gap_secs(swap_state.twap_window_secs.into());
+        price_history.record(token_a_price, token_b_price, clock.slot, clock.unix_timestamp, min_gap_secs);
+
+        Ok(price_history.twap(swap_state.twap_window_secs.into(), clock.unix_timestamp))
+    }
+
+    ///Check the circuit breaker without changing it, a quote fails where the instruction wouldn't act
+    fn peek_circuit_breaker(
+        swap_state: &SwapState,
+        token_a_price: u64,
+        token_b_price: u64,
+        pausable: bool,
+    ) -> ProgramResult {
+        let mut breaker = swap_state.breaker;
+        match breaker.observe(token_a_price, token_b_price, Clock::get()?.slot) {
+            BreakerStatus::Active => Ok(()),
+            BreakerStatus::Tripped | BreakerStatus::Paused if !pausable => Ok(()),
+            BreakerStatus::Tripped => Err(ZionError::BreakerTripped.into()),
+            BreakerStatus::Paused => Err(ZionError::PoolPaused.into()),
+        }
+    }
+
+    ///TWAP a swap would be priced at, without recording the current prices. A swap records them first,
+    ///an observation at the current time adds nothing to the window so the TWAP is the same
+    fn read_price_history(
+        price_history_info: Option<&AccountInfo>,
+        swap_state: &SwapState,
+    ) -> Result<Option<(u64, u64)>, ProgramError> {
+        let price_history_info = match price_history_info {
+            Some(price_history_info) => price_history_info,
+            None => return Ok(None),
+        };
+
+        //validate price history
+        PriceHistory::validate_price_history_key(price_history_info.key)?;
+        if price_history_info.owner != &crate::id() {
+            return Err(ZionError::InvalidPriceHistory.into());
+        }
+
+        let price_history_data = price_history_info.try_borrow_data()?;
+        let price_history = PriceHistory::load(&price_history_data)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        Ok(price_history.twap(swap_state.twap_window_secs.into(), now))
+    }
+
+    ///Price a swap of amount source tokens at the reference prices of the pool's price mode. Swap and Quote both
+    ///go through here so a quote always matches the swap, the math is in [SwapState::quote_swap](../state/struct.SwapState.html#method.quote_swap)
+    #[allow(clippy::too_many_arguments)]
+    fn quote_swap(
+        swap_state: &SwapState,
+        source_is_token_a: bool,
+        token_a_price: u64,
+        token_b_price: u64,
+        twap: Option<(u64, u64)>,
+        ema: Option<(u64, u64)>,
+        source_supply: u64,
+        destination_supply: u64,
+        amount: u64,
+    ) -> Result<SwapQuote, ProgramError> {
+        let (source_price, destination_price) = if source_is_token_a {
+            (token_a_price, token_b_price)
+        } else {
+            (token_b_price, token_a_price)
+        };
+
+        //prices the swap is priced at, depending on the pool's price mode
+        let (source_reference_price, destination_reference_price) = match PriceMode::try_from(swap_state.price_mode)? {
+            PriceMode::Spot => (source_price, destination_price),
+            PriceMode::Conservative => {
+                let (token_a_twap, token_b_twap) = twap.ok_or(ZionError::InvalidPriceHistory)?;
+                if source_is_token_a {
+                    (token_a_twap, token_b_twap)
+                } else {
+                    (token_b_twap, token_a_twap)
+                }
+            },
+            PriceMode::Ema => {
+                let (token_a_ema, token_b_ema) = ema.ok_or(ZionError::InvalidOracle)?;
+                if source_is_token_a {
+                    (token_a_ema, token_b_ema)
+                } else {
+                    (token_b_ema, token_a_ema)
+                }
+            },
+        };
+        Ok(swap_state.quote_swap(
+            source_price,
+            source_reference_price,
+            destination_price,
+            destination_reference_price,
+            source_supply,
+            destination_supply,
+            amount,
+        )?)
+    }
+
+    ///Return a SwapQuote through the return data
+    fn set_swap_return_data(quote: SwapQuote) -> ProgramResult {
+        let mut return_data = [0u8; SwapQuote::LEN];
+        SwapQuote::pack(quote, &mut return_data)?;
+        set_return_data(&return_data);
+        Ok(())
+    }
+
+    ///Return a LiquidityQuote through the return data
+    fn set_liquidity_return_data(quote: LiquidityQuote) -> ProgramResult {
+        let mut return_data = [0u8; LiquidityQuote::LEN];
+        LiquidityQuote::pack(quote, &mut return_data)?;
+        set_return_data(&return_data);
+        Ok(())
+    }
+
     ///check if any data exists for account
     pub fn assert_uninitialized(account: &AccountInfo) -> ProgramResult {
         if !account.data_is_empty() {
@@ -111,9 +406,9 @@ impl Processor {
         let instruction = ZionInstruction::unpack(input)?;
 
         match instruction {
-            ZionInstruction::Initialize(Initialize { swap_state }) => {
+            ZionInstruction::Initialize(params) => {
                 msg!("Instruction: Initialize");
-                Self::process_initialize(program_id, swap_state, accounts)
+                Self::process_initialize(program_id, params, accounts)
             },
             ZionInstruction::AdminDeposit(AdminDeposit { token_a_deposit, token_b_deposit }) => {
                 msg!("Instruction: AdminDeposit");
@@ -134,16 +429,50 @@ impl Processor {
             ZionInstruction::ClosePool() => {
                 msg!("Instruction: ClosePool");
                 Self::process_close_pool(program_id, accounts)
-            }
+            },
+            ZionInstruction::MigrateState() => {
+                msg!("Instruction: MigrateState");
+                Self::process_migrate_state(program_id, accounts)
+            },
+            ZionInstruction::ResetCircuitBreaker() => {
+                msg!("Instruction: ResetCircuitBreaker");
+                Self::process_reset_circuit_breaker(program_id, accounts)
+            },
+            ZionInstruction::Crank() => {
+                msg!("Instruction: Crank");
+                Self::process_crank(program_id, accounts)
+            },
+            ZionInstruction::SetOracleConfig(params) => {
+                msg!("Instruction: SetOracleConfig");
+                Self::process_set_oracle_config(program_id, accounts, params)
+            },
+            ZionInstruction::SetFallbackOracle(SetFallbackOracle { token }) => {
+                msg!("Instruction: SetFallbackOracle");
+                Self::process_set_fallback_oracle(program_id, accounts, token)
+            },
+            ZionInstruction::Quote(Quote { direction, amount }) => {
+                msg!("Instruction: Quote");
+                Self::process_quote(program_id, accounts, direction, amount)
+            },
+            ZionInstruction::QuoteDeposit(Deposit { token_a_deposit, token_b_deposit }) => {
+                msg!("Instruction: QuoteDeposit");
+                Self::process_quote_liquidity(program_id, accounts, token_a_deposit, token_b_deposit, true)
+            },
+            ZionInstruction::QuoteWithdraw(Withdraw { token_a_withdraw, token_b_withdraw }) => {
+                msg!("Instruction: QuoteWithdraw");
+                Self::process_quote_liquidity(program_id, accounts, token_a_withdraw, token_b_withdraw, false)
+            },
         }
     }
     
     ///Initialize the swap pool
     pub fn process_initialize(
         _: &Pubkey,
-        swap_state: SwapState,
+        params: InitializeParams,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
+        params.validate()?;
+
         let account_info_iter = &mut accounts.iter();
         let admin_info = next_account_info(account_info_iter)?;
         let swap_authority_info = next_account_info(account_info_iter)?;
@@ -189,13 +518,14 @@ impl Processor {
         //validate swap authority key
         Self::validate_swap_authority_key(
             swap_authority_info,
-            swap_state.swap_authority_bump,
+            params.swap_authority_bump,
         )?;
+        let swap_authority = *swap_authority_info.key;
  
 
         //validate swap mint
         let swap_mint = Self::unpack_mint(swap_mint_info)?;
-        if swap_mint.mint_authority != COption::Some(swap_state.swap_authority) {
+        if swap_mint.mint_authority != COption::Some(swap_authority) {
             return Err(ZionError::InvalidAuthority.into());
         }
         if swap_mint.supply != 0 {
@@ -211,7 +541,7 @@ impl Processor {
         Processor::validate_mint_and_token_accounts(
             &[token_a_vault.owner, token_a_fee_vault.owner],
             &mint_a_authority,
-            &swap_state.swap_authority
+            &swap_authority
         )?;
         
         
@@ -225,13 +555,20 @@ impl Processor {
             &[token_b_vault.owner,
             token_b_fee_vault.owner],
             &mint_b_authority,
-            &swap_state.swap_authority
+            &swap_authority
         )?;
         
 
         if token_a_mint_info.key == token_b_mint_info.key {
             return Err(ZionError::IdenticalMints.into());
         }
+
+        //validate oracles
+        if token_a_oracle_info.key == token_b_oracle_info.key {
+            return Err(ZionError::InvalidOracle.into());
+        }
+        Self::validate_oracle_account(token_a_oracle_info, &params.token_a_product)?;
+        Self::validate_oracle_account(token_b_oracle_info, &params.token_b_product)?;
         
         //create swap state pda account
         create_pda_account(
@@ -241,7 +578,7 @@ impl Processor {
             &crate::id(),
             system_program_info,
             swap_state_info,
-            &[SwapState::PREFIX.as_bytes(), &[swap_state.bump]],
+            &[SwapState::PREFIX.as_bytes(), &[params.bump]],
         )?;
 
         //create swap authority pda account
@@ -252,15 +589,16 @@ impl Processor {
             &crate::id(),
             system_program_info,
             swap_authority_info,
-            &[AUTHORITY_PREFIX.as_bytes(), &[swap_state.swap_authority_bump]],
+            &[AUTHORITY_PREFIX.as_bytes(), &[params.swap_authority_bump]],
         )?;
 
         let obj = SwapState {
+            version: SwapState::VERSION,
             admin: *admin_info.key,
-            bump: swap_state.bump,
-            is_initialized: true,
-            swap_authority: swap_state.swap_authority,
-            swap_authority_bump: swap_state.swap_authority_bump,
+            bump: params.bump,
+            is_initialized: true.into(),
+            swap_authority,
+            swap_authority_bump: params.swap_authority_bump,
             swap_mint: *swap_mint_info.key,
             token_a: Token {
                 mint: token_a_mint_info.key.clone(),
@@ -274,11 +612,35 @@ impl Processor {
                 fee_vault: token_b_fee_vault_info.key.clone(),
                 oracle:  token_b_oracle_info.key.clone()
             },
-            program_fee: swap_state.program_fee,
-            swap_fee: swap_state.swap_fee,
+            program_fee: params.program_fee.into(),
+            swap_fee: params.swap_fee.into(),
+            curve_type: params.curve_type,
+            amp: params.amp.into(),
+            premium: params.premium_params(),
+            max_trade_bps: params.max_trade_bps.into(),
+            max_price_deviation_bps: params.max_price_deviation_bps.into(),
+            breaker: CircuitBreaker {
+                max_jump_bps: params.breaker_max_jump_bps.into(),
+                window_slots: params.breaker_window_slots.into(),
+                cooldown_slots: params.breaker_cooldown_slots.into(),
+                ..CircuitBreaker::default()
+            },
+            price_mode: params.price_mode,
+            twap_window_secs: params.twap_window_secs.into(),
+            ..SwapState::default()
         };
         SwapState::pack(obj, &mut swap_state_info.data.borrow_mut())?;
 
+        Event::PoolInitialized(PoolInitialized {
+            admin: *admin_info.key,
+            swap_mint: *swap_mint_info.key,
+            token_a_mint: *token_a_mint_info.key,
+            token_b_mint: *token_b_mint_info.key,
+            token_a_oracle: *token_a_oracle_info.key,
+            token_b_oracle: *token_b_oracle_info.key,
+            curve_type: params.curve_type,
+        }).emit();
+
        
         Ok(())
     }
@@ -309,6 +671,7 @@ impl Processor {
         let token_b_oracle_info = next_account_info(account_info_iter)?;
 
         let token_program_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
         let token_program_id = *token_program_info.key;
 
         //validate token program key
@@ -319,8 +682,8 @@ impl Processor {
         //validate swap state key
         SwapState::validate_swap_state_key(swap_state_info.key)?;
 
-        let swap_state_data = swap_state_info.try_borrow_data()?;
-        let swap_state = SwapState::unpack_from_slice(&swap_state_data)?;
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
         
         //validate signer
         if !admin_info.is_signer {
@@ -357,11 +720,12 @@ impl Processor {
         }
 
         //load oracle prices
-        let token_a_price_feed: PriceFeed = load_price_feed_from_account_info(&token_a_oracle_info ).unwrap();
-        let token_a_price = token_a_price_feed.get_price_unchecked().price.try_into().unwrap();
+        let now = Clock::get()?.unix_timestamp;
+        let token_a_price = Self::get_token_price(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now)?;
+        let token_b_price = Self::get_token_price(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now)?;
 
-        let token_b_price_feed: PriceFeed = load_price_feed_from_account_info(&token_b_oracle_info ).unwrap();
-        let token_b_price = token_b_price_feed.get_price_unchecked().price.try_into().unwrap();
+        //feed the prices through the circuit breaker, it doesn't pause this instruction
+        Self::check_circuit_breaker(swap_state, token_a_price, token_b_price, false)?;
 
         //transfer tokens from token_a_admin_wallet to vault
         let token_a_swap_tokens = if token_a_deposit > 0 {
@@ -373,7 +737,7 @@ impl Processor {
                 token_a_deposit,
 
             )?;
-            swap_state.token_a.get_market_value(token_a_deposit, token_a_price).to_imprecise().expect("a valid number") as u64
+            RoundDirection::Floor.round(&swap_state.token_a.get_market_value(token_a_deposit, token_a_price)?)?
 
         } else {
             0
@@ -389,24 +753,46 @@ impl Processor {
                 token_b_deposit,
 
             )?;
-            swap_state.token_b.get_market_value(token_b_deposit, token_b_price).to_imprecise().expect("a valid number") as u64
+            RoundDirection::Floor.round(&swap_state.token_b.get_market_value(token_b_deposit, token_b_price)?)?
         
         } else {
             0
         };
 
+        let swap_tokens = token_a_swap_tokens
+            .checked_add(token_b_swap_tokens)
+            .ok_or(ZionError::MathOverflow)?;
+
         //mint swap pool tokens to admin wallet
         token_mint_to(
             token_program_info, 
             swap_mint_info,
             admin_swap_wallet,
             swap_authority_info,
-            token_a_swap_tokens + token_b_swap_tokens,
+            swap_tokens,
             &[AUTHORITY_PREFIX.as_bytes(), &[swap_state.swap_authority_bump]],
 
         )?;
 
-        Ok(())
+        Event::Deposited(Deposited {
+            user: *admin_info.key,
+            token_a_amount: token_a_deposit.into(),
+            token_b_amount: token_b_deposit.into(),
+            swap_tokens: swap_tokens.into(),
+            token_a_price: token_a_price.into(),
+            token_b_price: token_b_price.into(),
+        }).emit();
+
+        Self::set_liquidity_return_data(LiquidityQuote {
+            token_a_amount: token_a_deposit,
+            token_b_amount: token_b_deposit,
+            swap_tokens,
+            //the pool doesn't charge fees yet
+            program_fee: 0,
+            swap_fee: 0,
+            token_a_price,
+            token_b_price,
+        })
     }
 
     ///User instruction to deposit tokens priced at the protocols value and not the market value.
@@ -434,6 +820,8 @@ impl Processor {
         let token_b_oracle_info = next_account_info(account_info_iter)?;
 
         let token_program_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
+        let price_history_info = Self::find_price_history(remaining_accounts);
        
         let token_program_id = *token_program_info.key;
 
@@ -455,8 +843,8 @@ impl Processor {
         //validate swap state key
         SwapState::validate_swap_state_key(swap_state_info.key)?;
 
-        let swap_state_data = swap_state_info.try_borrow_data()?;
-        let swap_state = SwapState::unpack_from_slice(&swap_state_data)?;
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
 
         swap_state.validate_accounts(
             swap_authority_info.key,
@@ -475,19 +863,33 @@ impl Processor {
         let token_a_fee_vault = Self::unpack_token_account(token_a_fee_vault_info)?;
 
         let token_b_vault = Self::unpack_token_account(token_b_vault_info)?;
-        let token_b_fee_vault = Self::unpack_token_account(token_b_vault_info)?;
+        let token_b_fee_vault = Self::unpack_token_account(token_b_fee_vault_info)?;
 
         let swap_mint = Self::unpack_mint(swap_mint_info)?;
 
         //load prices from oracle
-        let token_a_price_feed: PriceFeed = load_price_feed_from_account_info(&token_a_oracle_info ).unwrap();
-        let token_a_price = token_a_price_feed.get_price_unchecked().price.try_into().unwrap();
-
-        let token_b_price_feed: PriceFeed = load_price_feed_from_account_info(&token_b_oracle_info ).unwrap();
-        let token_b_price = token_b_price_feed.get_price_unchecked().price.try_into().unwrap();
+        let now = Clock::get()?.unix_timestamp;
+        let token_a_price = Self::get_token_price(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now)?;
+        let token_b_price = Self::get_token_price(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now)?;
+
+        //feed the prices through the circuit breaker
+        Self::check_circuit_breaker(swap_state, token_a_price, token_b_price, true)?;
+        Self::update_price_history(price_history_info, swap_state, token_a_price, token_b_price)?;
+
+        let quote = swap_state.quote_deposit(
+            token_a_deposit,
+            token_b_deposit,
+            token_a_vault.amount,
+            token_a_fee_vault.amount,
+            token_b_vault.amount,
+            token_b_fee_vault.amount,
+            swap_mint.supply,
+            token_a_price,
+            token_b_price,
+        )?;
 
         //transfer tokens from user token_a wallet to vault
-        let token_a_swap_tokens = if token_a_deposit > 0 {
+        if token_a_deposit > 0 {
             token_transfer(
                 token_program_info, 
                 token_a_user_info,
@@ -496,24 +898,10 @@ impl Processor {
                 token_a_deposit,
 
             )?;
-
-            swap_state.calculate_swap_tokens(
-                token_a_deposit,
-                token_a_vault.amount,
-                token_a_price,
-                token_a_fee_vault.amount,
-                token_b_vault.amount,
-                token_b_price,
-                token_b_fee_vault.amount,
-                swap_mint.supply
-            )
-
-        } else {
-            0
-        };
+        }
 
         //transfer tokens from user token_b wallet to vault
-        let token_b_swap_tokens = if token_b_deposit > 0 {
+        if token_b_deposit > 0 {
             token_transfer(
                 token_program_info, 
                 token_b_user_info,
@@ -522,20 +910,8 @@ impl Processor {
                 token_b_deposit,
 
             )?;
-
-            swap_state.calculate_swap_tokens(
-                token_b_deposit,
-                token_b_vault.amount,
-                token_b_price,
-                token_b_fee_vault.amount,
-                token_a_vault.amount,
-                token_a_price,
-                token_a_fee_vault.amount,
-                swap_mint.supply
-            )
-        } else {
-            0
-        };
+        }
+        let swap_tokens = quote.swap_tokens;
 
         //mint swap tokens to user swap wallet
         token_mint_to(
@@ -543,12 +919,20 @@ impl Processor {
             swap_mint_info,
             swap_token_user_info,
             swap_authority_info,
-            token_a_swap_tokens + token_b_swap_tokens,
+            swap_tokens,
             &[AUTHORITY_PREFIX.as_bytes(), &[swap_state.swap_authority_bump]],
 
         )?;
-        
 
+        Event::Deposited(Deposited {
+            user: *user.key,
+            token_a_amount: quote.token_a_amount.into(),
+            token_b_amount: quote.token_b_amount.into(),
+            swap_tokens: quote.swap_tokens.into(),
+            token_a_price: quote.token_a_price.into(),
+            token_b_price: quote.token_b_price.into(),
+        }).emit();
+        Self::set_liquidity_return_data(quote)?;
 
         Ok(())
     }
@@ -578,6 +962,7 @@ impl Processor {
         let token_b_oracle_info = next_account_info(account_info_iter)?;
 
         let token_program_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
        
         let token_program_id = *token_program_info.key;
 
@@ -600,8 +985,8 @@ impl Processor {
         //validate swap state key
         SwapState::validate_swap_state_key(swap_state_info.key)?;
 
-        let swap_state_data = swap_state_info.try_borrow_data()?;
-        let swap_state = SwapState::unpack_from_slice(&swap_state_data)?;
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
 
         swap_state.validate_accounts(
             swap_authority_info.key,
@@ -620,53 +1005,32 @@ impl Processor {
         let token_a_fee_vault = Self::unpack_token_account(token_a_fee_vault_info)?;
 
         let token_b_vault = Self::unpack_token_account(token_b_vault_info)?;
-        let token_b_fee_vault = Self::unpack_token_account(token_b_vault_info)?;
+        let token_b_fee_vault = Self::unpack_token_account(token_b_fee_vault_info)?;
 
         let swap_mint = Self::unpack_mint(swap_mint_info)?;
         let swap_token_user = Self::unpack_token_account(swap_token_user_info)?;
 
-        let token_a_price_feed: PriceFeed = load_price_feed_from_account_info(&token_a_oracle_info ).unwrap();
-        let token_a_price = token_a_price_feed.get_price_unchecked().price.try_into().unwrap();
-
-        let token_b_price_feed: PriceFeed = load_price_feed_from_account_info(&token_b_oracle_info ).unwrap();
-        let token_b_price = token_b_price_feed.get_price_unchecked().price.try_into().unwrap();
-
-        //calculate how many swap tokens are needed for token_a_withdraw amount
-        let token_a_swap_tokens = if token_a_withdraw > 0 {
-            swap_state.calculate_swap_tokens(
-                token_a_withdraw,
-                token_a_vault.amount,
-                token_a_price,
-                token_a_fee_vault.amount,
-                token_b_vault.amount,
-                token_b_price,
-                token_b_fee_vault.amount,
-                swap_mint.supply
-            )
-
-        } else {
-            0
-        };
-
-        
-        //calculate how many swap tokens are needed for token_abwithdraw amount
-        let token_b_swap_tokens = if token_b_withdraw > 0 {
-            swap_state.calculate_swap_tokens(
-                token_b_withdraw,
-                token_b_vault.amount,
-                token_b_price,
-                token_b_fee_vault.amount,
-                token_a_vault.amount,
-                token_a_price,
-                token_a_fee_vault.amount,
-                swap_mint.supply
-            )
-            
-        } else {
-            0
-        };
+        let now = Clock::get()?.unix_timestamp;
+        let token_a_price = Self::get_token_price(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now)?;
+        let token_b_price = Self::get_token_price(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now)?;
+
+        //feed the prices through the circuit breaker, it doesn't pause this instruction
+        Self::check_circuit_breaker(swap_state, token_a_price, token_b_price, false)?;
+
+        let quote = swap_state.quote_withdraw(
+            token_a_withdraw,
+            token_b_withdraw,
+            token_a_vault.amount,
+            token_a_fee_vault.amount,
+            token_b_vault.amount,
+            token_b_fee_vault.amount,
+            swap_mint.supply,
+            token_a_price,
+            token_b_price,
+        )?;
+        let swap_tokens = quote.swap_tokens;
 
-        if (token_a_swap_tokens + token_b_swap_tokens) < swap_token_user.amount {
+        if swap_tokens < swap_token_user.amount {
             if token_a_withdraw > 0 {
                 msg!("Withdrawing {} tokens from pool A",token_a_withdraw);
                 token_transfer_signed(
@@ -691,21 +1055,31 @@ impl Processor {
                 )?;
             }
 
-            msg!("Burning {} swap tokens", token_a_swap_tokens + token_b_swap_tokens);
+            msg!("Burning {} swap tokens", swap_tokens);
             token_burn(
                 token_program_info,
                 swap_token_user_info,
                 swap_mint_info,
                 user,
-                token_a_swap_tokens + token_b_swap_tokens,
+                swap_tokens,
                 &[AUTHORITY_PREFIX.as_bytes(), &[swap_state.swap_authority_bump]],
     
             )?;
 
         } else {
-            msg!("{} swap tokens required for withdrawl but only {} available", token_a_swap_tokens + token_b_swap_tokens, swap_token_user.amount);
+            msg!("{} swap tokens required for withdrawl but only {} available", swap_tokens, swap_token_user.amount);
             return Err(ZionError::InsufficientSwapTokens.into());
         }
+
+        Event::Withdrew(Withdrew {
+            user: *user.key,
+            token_a_amount: quote.token_a_amount.into(),
+            token_b_amount: quote.token_b_amount.into(),
+            swap_tokens: quote.swap_tokens.into(),
+            token_a_price: quote.token_a_price.into(),
+            token_b_price: quote.token_b_price.into(),
+        }).emit();
+        Self::set_liquidity_return_data(quote)?;
         
         Ok(())
 
@@ -733,6 +1107,8 @@ impl Processor {
         let destination_oracle_info = next_account_info(account_info_iter)?;
 
         let token_program_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
+        let price_history_info = Self::find_price_history(remaining_accounts);
        
         let token_program_id = *token_program_info.key;
 
@@ -755,8 +1131,8 @@ impl Processor {
         //validate swap state key
         SwapState::validate_swap_state_key(swap_state_info.key)?;
 
-        let swap_state_data = swap_state_info.try_borrow_data()?;
-        let swap_state = SwapState::unpack_from_slice(&swap_state_data)?;
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
 
         //validate swap authority key
         swap_state.validate_swap_state_authority(swap_authority_info.key)?;
@@ -767,17 +1143,14 @@ impl Processor {
             return Err(ZionError::InvalidSupply.into());
         }
 
-        //load prices from oracle
-        let source_price_feed: PriceFeed = load_price_feed_from_account_info(&source_oracle_info ).unwrap();
-        let destination_price_feed: PriceFeed = load_price_feed_from_account_info(&destination_oracle_info ).unwrap();
-
         //validate oracles
         if destination_oracle_info.key == source_oracle_info.key {
             return Err(ZionError::InvalidOracle.into());
         }
 
         //validate accounts
-        let (source_token, source_price, destination_token, destination_price) = if *source_vault_info.key == swap_state.token_a.vault {
+        let source_is_token_a = *source_vault_info.key == swap_state.token_a.vault;
+        if source_is_token_a {
             
             //validate vaults
             if source_vault_info.key != &swap_state.token_a.vault {
@@ -794,11 +1167,14 @@ impl Processor {
             if destination_fee_vault_info.key != &swap_state.token_b.fee_vault {
                 return Err(ZionError::InvalidVault.into());
             }
-            
-            let source_price: Price = source_price_feed.get_price_unchecked();
-            let destination_price: Price = destination_price_feed.get_price_unchecked();
 
-            (swap_state.token_a, source_price.price, swap_state.token_b, destination_price.price)
+            //validate oracles
+            if source_oracle_info.key != &swap_state.token_a.oracle {
+                return Err(ZionError::InvalidOracle.into());
+            }
+            if destination_oracle_info.key != &swap_state.token_b.oracle {
+                return Err(ZionError::InvalidOracle.into());
+            }
 
         } else {
 
@@ -818,22 +1194,40 @@ impl Processor {
                 return Err(ZionError::InvalidVault.into());
             }
 
-            let source_price: Price = source_price_feed.get_price_unchecked();
-            let destination_price: Price = destination_price_feed.get_price_unchecked();
-
-            (swap_state.token_b, destination_price.price, swap_state.token_a, source_price.price)
-        };
+            //validate oracles
+            if destination_oracle_info.key != &swap_state.token_a.oracle {
+                return Err(ZionError::InvalidOracle.into());
+            }
+            if source_oracle_info.key != &swap_state.token_b.oracle {
+                return Err(ZionError::InvalidOracle.into());
+            }
+        }
 
-        //calculate how mant destination tokens user receives for source_tokens
-        let destination_amount = SwapState::calculate_tokens_to_swap(
-            source_token,
+        //load prices from oracles
+        let now = Clock::get()?.unix_timestamp;
+        let token_a_oracle_info = if source_is_token_a { source_oracle_info } else { destination_oracle_info };
+        let token_b_oracle_info = if source_is_token_a { destination_oracle_info } else { source_oracle_info };
+        let with_ema = PriceMode::try_from(swap_state.price_mode)? == PriceMode::Ema;
+        let (token_a_price, token_a_ema) = Self::get_token_prices(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now, with_ema)?;
+        let (token_b_price, token_b_ema) = Self::get_token_prices(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now, with_ema)?;
+
+        //feed the prices through the circuit breaker
+        Self::check_circuit_breaker(swap_state, token_a_price, token_b_price, true)?;
+
+        let twap = Self::update_price_history(price_history_info, swap_state, token_a_price, token_b_price)?;
+
+        let quote = Self::quote_swap(
+            swap_state,
+            source_is_token_a,
+            token_a_price,
+            token_b_price,
+            twap,
+            token_a_ema.zip(token_b_ema),
             source_vault_data.amount,
-            source_price.try_into().unwrap(),
-            destination_token,
-            destination_price.try_into().unwrap(),
             destination_vault_data.amount,
             amount,
-        );
+        )?;
+        let destination_amount = quote.amount_out;
 
         msg!("Swapping {} tokens from source pool", amount);
         token_transfer(
@@ -855,6 +1249,24 @@ impl Processor {
             &[AUTHORITY_PREFIX.as_bytes(), &[swap_state.swap_authority_bump]],
         )?;
 
+        let (source_mint, destination_mint) = if source_is_token_a {
+            (swap_state.token_a.mint, swap_state.token_b.mint)
+        } else {
+            (swap_state.token_b.mint, swap_state.token_a.mint)
+        };
+        Event::Swapped(Swapped {
+            user: *user.key,
+            source_mint,
+            destination_mint,
+            amount_in: quote.amount_in.into(),
+            amount_out: quote.amount_out.into(),
+            program_fee: quote.program_fee.into(),
+            swap_fee: quote.swap_fee.into(),
+            source_price: quote.source_price.into(),
+            destination_price: quote.destination_price.into(),
+        }).emit();
+        Self::set_swap_return_data(quote)?;
+
         Ok(())
     }
 
@@ -872,7 +1284,7 @@ impl Processor {
         SwapState::validate_swap_state_key(swap_state_info.key)?;
 
         let swap_state_data = swap_state_info.try_borrow_data()?;
-        let swap_state = SwapState::unpack_from_slice(&swap_state_data)?;
+        let swap_state = SwapState::load(&swap_state_data)?;
 
         //validate admin
         if &swap_state.admin != admin_info.key {
@@ -893,13 +1305,468 @@ impl Processor {
         **admin_info.lamports.borrow_mut() = admin_lamports + lamports;
         **swap_state_info.lamports.borrow_mut() = 0;
 
+        Event::PoolClosed(PoolClosed {
+            admin: *admin_info.key,
+            swap_mint: swap_state.swap_mint,
+            lamports: lamports.into(),
+        }).emit();
+
         Ok(())
     } 
 
+    ///Instruction to rewrite a v1 swap state into the current layout
+    pub fn process_migrate_state(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let swap_state_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        //validate system program key
+        if !cmp_pubkeys(system_program_info.key, &system_program::id()) {
+            return Err(ZionError::InvalidSystemProgramKey.into());
+        }
+
+        //validate signer
+        if !admin_info.is_signer {
+            return Err(ZionError::InvalidSigner.into());
+        }
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+        if !cmp_pubkeys(swap_state_info.owner, &crate::id()) {
+            return Err(ZionError::SwapStateWrongOwner.into());
+        }
+
+        //only v1 accounts need to be migrated
+        let mut swap_state = {
+            let swap_state_data = swap_state_info.try_borrow_data()?;
+            if swap_state_data.len() != SwapState::LEN_V1 {
+                return Err(ZionError::InvalidStateVersion.into());
+            }
+            SwapState::unpack_v1(&swap_state_data)?
+        };
+
+        //validate admin
+        if &swap_state.admin != admin_info.key {
+            return Err(ZionError::MustBeAdmin.into());
+        }
+
+        msg!("Migrating swap state from v{} to v{}", swap_state.version, SwapState::VERSION);
+        realloc_account(
+            admin_info,
+            &Rent::get()?,
+            SwapState::LEN,
+            system_program_info,
+            swap_state_info,
+        )?;
+
+        swap_state.version = SwapState::VERSION;
+        SwapState::pack(swap_state, &mut swap_state_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    ///Admin instruction to resume a pool paused by the circuit breaker
+    pub fn process_reset_circuit_breaker(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let swap_state_info = next_account_info(account_info_iter)?;
+
+        //validate signer
+        if !admin_info.is_signer {
+            return Err(ZionError::InvalidSigner.into());
+        }
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
+
+        //validate admin
+        if &swap_state.admin != admin_info.key {
+            return Err(ZionError::MustBeAdmin.into());
+        }
+
+        msg!("Resetting circuit breaker tripped in slot {}", u64::from(swap_state.breaker.tripped_slot));
+        swap_state.breaker.reset();
+
+        Ok(())
+    }
+
+    ///Permissionless instruction that records the current oracle prices in the price history,
+    ///creating the price history account on the first call
+    pub fn process_crank(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer_info = next_account_info(account_info_iter)?;
+        let swap_state_info = next_account_info(account_info_iter)?;
+        let price_history_info = next_account_info(account_info_iter)?;
+        let token_a_oracle_info = next_account_info(account_info_iter)?;
+        let token_b_oracle_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
+
+        //validate system program key
+        if !cmp_pubkeys(system_program_info.key, &system_program::id()) {
+            return Err(ZionError::InvalidSystemProgramKey.into());
+        }
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
+
+        //validate oracles
+        if token_a_oracle_info.key != &swap_state.token_a.oracle {
+            return Err(ZionError::InvalidOracle.into());
+        }
+        if token_b_oracle_info.key != &swap_state.token_b.oracle {
+            return Err(ZionError::InvalidOracle.into());
+        }
+
+        //validate price history key
+        let (price_history_key, price_history_bump) = PriceHistory::find_address();
+        if !cmp_pubkeys(price_history_info.key, &price_history_key) {
+            return Err(ZionError::InvalidPriceHistory.into());
+        }
+
+        //create the price history on the first crank
+        if price_history_info.data_is_empty() {
+            create_pda_account(
+                payer_info,
+                &Rent::get()?,
+                PriceHistory::LEN,
+                &crate::id(),
+                system_program_info,
+                price_history_info,
+                &[PriceHistory::PREFIX.as_bytes(), &[price_history_bump]],
+            )?;
+
+            let mut price_history_data = price_history_info.try_borrow_mut_data()?;
+            let price_history: &mut PriceHistory = bytemuck::try_from_bytes_mut(&mut price_history_data[..PriceHistory::LEN])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            price_history.is_initialized = true.into();
+            price_history.bump = price_history_bump;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let token_a_price = Self::get_token_price(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now)?;
+        let token_b_price = Self::get_token_price(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now)?;
+
+        //feed the prices through the circuit breaker, a crank saves a trip the pausable instructions roll back
+        Self::check_circuit_breaker(swap_state, token_a_price, token_b_price, false)?;
+
+        if let Some((token_a_twap, token_b_twap)) = Self::update_price_history(Some(price_history_info), swap_state, token_a_price, token_b_price)? {
+            msg!("Recorded prices {} {}, TWAP {} {}", token_a_price, token_b_price, token_a_twap, token_b_twap);
+        }
+
+        Ok(())
+    }
+
+    ///Admin instruction to set the additional oracles and freshness limits of one of the tokens
+    pub fn process_set_oracle_config(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+        params: SetOracleConfig,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let swap_state_info = next_account_info(account_info_iter)?;
+        let extra_oracle_infos = account_info_iter.as_slice();
+
+        //validate signer
+        if !admin_info.is_signer {
+            return Err(ZionError::InvalidSigner.into());
+        }
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
+
+        //validate admin
+        if &swap_state.admin != admin_info.key {
+            return Err(ZionError::MustBeAdmin.into());
+        }
+
+        if extra_oracle_infos.len() > MAX_ORACLES - 1 {
+            return Err(ZionError::InvalidOracleConfig.into());
+        }
+
+        let mut oracle_config = OracleConfig {
+            num_extra_oracles: extra_oracle_infos.len() as u8,
+            quorum: params.quorum,
+            max_age_secs: params.max_age_secs.into(),
+            max_confidence_bps: params.max_confidence_bps.into(),
+            ..OracleConfig::default()
+        };
+        for (oracle, extra_oracle_info) in oracle_config.extra_oracles.iter_mut().zip(extra_oracle_infos) {
+            //validate oracle
+            Self::validate_oracle_account(extra_oracle_info, &Pubkey::default())?;
+            load_price_feed_from_account_info(extra_oracle_info).map_err(|_| ZionError::InvalidOracle)?;
+            *oracle = *extra_oracle_info.key;
+        }
+
+        //the fallback oracle only stands in for the primary oracle and isn't counted on its own
+        match params.token {
+            0 => {
+                oracle_config.validate(&swap_state.token_a.oracle)?;
+                if oracle_config.extra_oracles().contains(&swap_state.token_a_fallback_oracle) {
+                    return Err(ZionError::InvalidOracleConfig.into());
+                }
+                Self::validate_fallback_config(&swap_state.token_a_fallback_oracle, &oracle_config)?;
+                swap_state.token_a_oracles = oracle_config;
+            },
+            1 => {
+                oracle_config.validate(&swap_state.token_b.oracle)?;
+                if oracle_config.extra_oracles().contains(&swap_state.token_b_fallback_oracle) {
+                    return Err(ZionError::InvalidOracleConfig.into());
+                }
+                Self::validate_fallback_config(&swap_state.token_b_fallback_oracle, &oracle_config)?;
+                swap_state.token_b_oracles = oracle_config;
+            },
+            _ => return Err(ZionError::InvalidInstruction.into()),
+        }
+        msg!("Token {} priced from {} oracles, quorum {}", params.token, 1 + oracle_config.extra_oracles().len(), oracle_config.quorum());
+
+        Ok(())
+    }
+
+    ///Admin instruction to set the oracle used for one of the tokens when its primary oracle isn't fresh.
+    ///Passing no oracle account removes the fallback oracle
+    pub fn process_set_fallback_oracle(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+        token: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let swap_state_info = next_account_info(account_info_iter)?;
+        let fallback_oracle_info = next_account_info(account_info_iter).ok();
+
+        //validate signer
+        if !admin_info.is_signer {
+            return Err(ZionError::InvalidSigner.into());
+        }
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+
+        let mut swap_state_data = swap_state_info.try_borrow_mut_data()?;
+        let swap_state = SwapState::load_mut(&mut swap_state_data)?;
+
+        //validate admin
+        if &swap_state.admin != admin_info.key {
+            return Err(ZionError::MustBeAdmin.into());
+        }
+
+        let fallback_oracle = match fallback_oracle_info {
+            Some(fallback_oracle_info) => {
+                //validate oracle
+                Self::validate_oracle_account(fallback_oracle_info, &Pubkey::default())?;
+                load_price_feed_from_account_info(fallback_oracle_info).map_err(|_| ZionError::InvalidOracle)?;
+                *fallback_oracle_info.key
+            },
+            None => Pubkey::default(),
+        };
+
+        let (primary_oracle, oracle_config, stored_fallback_oracle) = match token {
+            0 => (&swap_state.token_a.oracle, &swap_state.token_a_oracles, &mut swap_state.token_a_fallback_oracle),
+            1 => (&swap_state.token_b.oracle, &swap_state.token_b_oracles, &mut swap_state.token_b_fallback_oracle),
+            _ => return Err(ZionError::InvalidInstruction.into()),
+        };
+
+        //the fallback has to be a different feed than the ones already pricing the token
+        if fallback_oracle == *primary_oracle || oracle_config.extra_oracles().contains(&fallback_oracle) {
+            return Err(ZionError::InvalidOracle.into());
+        }
+        Self::validate_fallback_config(&fallback_oracle, oracle_config)?;
+        *stored_fallback_oracle = fallback_oracle;
+
+        msg!("Token {} fallback oracle {}", token, fallback_oracle);
+
+        Ok(())
+    }
+
+    ///Read only instruction that returns the SwapQuote of a swap through the return data.
+    ///Direction zero swaps token a for token b, one token b for token a
+    pub fn process_quote(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+        direction: u8,
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_state_info = next_account_info(account_info_iter)?;
+        let token_a_vault_info = next_account_info(account_info_iter)?;
+        let token_a_oracle_info = next_account_info(account_info_iter)?;
+        let token_b_vault_info = next_account_info(account_info_iter)?;
+        let token_b_oracle_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
+        let price_history_info = Self::find_price_history(remaining_accounts);
+
+        let source_is_token_a = match direction {
+            0 => true,
+            1 => false,
+            _ => return Err(ZionError::InvalidInstruction.into()),
+        };
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+
+        let swap_state_data = swap_state_info.try_borrow_data()?;
+        let swap_state = SwapState::load(&swap_state_data)?;
+
+        //validate vaults
+        if token_a_vault_info.key != &swap_state.token_a.vault {
+            return Err(ZionError::InvalidVault.into());
+        }
+        if token_b_vault_info.key != &swap_state.token_b.vault {
+            return Err(ZionError::InvalidVault.into());
+        }
+
+        //validate oracles
+        if token_a_oracle_info.key != &swap_state.token_a.oracle {
+            return Err(ZionError::InvalidOracle.into());
+        }
+        if token_b_oracle_info.key != &swap_state.token_b.oracle {
+            return Err(ZionError::InvalidOracle.into());
+        }
+
+        let token_a_vault = Self::unpack_token_account(token_a_vault_info)?;
+        let token_b_vault = Self::unpack_token_account(token_b_vault_info)?;
+        let (source_supply, destination_supply) = if source_is_token_a {
+            (token_a_vault.amount, token_b_vault.amount)
+        } else {
+            (token_b_vault.amount, token_a_vault.amount)
+        };
+        if destination_supply == 0 {
+            return Err(ZionError::InvalidSupply.into());
+        }
+
+        //load prices from oracles
+        let now = Clock::get()?.unix_timestamp;
+        let with_ema = PriceMode::try_from(swap_state.price_mode)? == PriceMode::Ema;
+        let (token_a_price, token_a_ema) = Self::get_token_prices(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now, with_ema)?;
+        let (token_b_price, token_b_ema) = Self::get_token_prices(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now, with_ema)?;
+
+        Self::peek_circuit_breaker(swap_state, token_a_price, token_b_price, true)?;
+        let twap = Self::read_price_history(price_history_info, swap_state)?;
+
+        let quote = Self::quote_swap(
+            swap_state,
+            source_is_token_a,
+            token_a_price,
+            token_b_price,
+            twap,
+            token_a_ema.zip(token_b_ema),
+            source_supply,
+            destination_supply,
+            amount,
+        )?;
+
+        Self::set_swap_return_data(quote)
+    }
+
+    ///Read only instruction that returns the LiquidityQuote of a deposit or a withdraw through the return data
+    pub fn process_quote_liquidity(
+        _: &Pubkey,
+        accounts: &[AccountInfo],
+        token_a_amount: u64,
+        token_b_amount: u64,
+        is_deposit: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_state_info = next_account_info(account_info_iter)?;
+        let swap_mint_info = next_account_info(account_info_iter)?;
+
+        let token_a_vault_info = next_account_info(account_info_iter)?;
+        let token_a_fee_vault_info = next_account_info(account_info_iter)?;
+        let token_a_oracle_info = next_account_info(account_info_iter)?;
+
+        let token_b_vault_info = next_account_info(account_info_iter)?;
+        let token_b_fee_vault_info = next_account_info(account_info_iter)?;
+        let token_b_oracle_info = next_account_info(account_info_iter)?;
+        let remaining_accounts = account_info_iter.as_slice();
+
+        //validate swap state key
+        SwapState::validate_swap_state_key(swap_state_info.key)?;
+
+        let swap_state_data = swap_state_info.try_borrow_data()?;
+        let swap_state = SwapState::load(&swap_state_data)?;
+
+        swap_state.validate_accounts(
+            &swap_state.swap_authority,
+            swap_mint_info.key,
+            &swap_state.token_a.mint,
+            token_a_vault_info.key,
+            token_a_fee_vault_info.key,
+            token_a_oracle_info.key,
+            &swap_state.token_b.mint,
+            token_b_vault_info.key,
+            token_b_fee_vault_info.key,
+            token_b_oracle_info.key
+        )?;
+
+        let token_a_vault = Self::unpack_token_account(token_a_vault_info)?;
+        let token_a_fee_vault = Self::unpack_token_account(token_a_fee_vault_info)?;
+
+        let token_b_vault = Self::unpack_token_account(token_b_vault_info)?;
+        let token_b_fee_vault = Self::unpack_token_account(token_b_fee_vault_info)?;
+
+        let swap_mint = Self::unpack_mint(swap_mint_info)?;
+
+        //load prices from oracles
+        let now = Clock::get()?.unix_timestamp;
+        let token_a_price = Self::get_token_price(token_a_oracle_info, &swap_state.token_a_fallback_oracle, &swap_state.token_a_oracles, remaining_accounts, now)?;
+        let token_b_price = Self::get_token_price(token_b_oracle_info, &swap_state.token_b_fallback_oracle, &swap_state.token_b_oracles, remaining_accounts, now)?;
+
+        //deposits are paused by the circuit breaker, withdraws aren't
+        Self::peek_circuit_breaker(swap_state, token_a_price, token_b_price, is_deposit)?;
+
+        let quote = if is_deposit {
+            swap_state.quote_deposit(
+                token_a_amount,
+                token_b_amount,
+                token_a_vault.amount,
+                token_a_fee_vault.amount,
+                token_b_vault.amount,
+                token_b_fee_vault.amount,
+                swap_mint.supply,
+                token_a_price,
+                token_b_price,
+            )?
+        } else {
+            swap_state.quote_withdraw(
+                token_a_amount,
+                token_b_amount,
+                token_a_vault.amount,
+                token_a_fee_vault.amount,
+                token_b_vault.amount,
+                token_b_fee_vault.amount,
+                swap_mint.supply,
+                token_a_price,
+                token_b_price,
+            )?
+        };
+
+        Self::set_liquidity_return_data(quote)
+    }
+
 }
 
 ///compare two Pubkeys
 pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
     sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
 }
-
@@ -0,0 +1,245 @@
+use {
+    crate::instructions::ZionInstruction,
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{Pack, Sealed},
+        pubkey::Pubkey,
+    },
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+};
+
+///Returned by Swap with the amounts it moved and by Quote with the amounts Swap would move with the same accounts
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapQuote {
+    ///source tokens paid in
+    pub amount_in: u64,
+    ///destination tokens paid out
+    pub amount_out: u64,
+    ///source tokens taken as the program fee, zero until Swap charges fees
+    pub program_fee: u64,
+    ///source tokens taken as the swap fee, zero until Swap charges fees
+    pub swap_fee: u64,
+    ///oracle price the source tokens are valued at
+    pub source_price: u64,
+    ///oracle price the destination tokens are valued at
+    pub destination_price: u64,
+    ///price the pool pays per source token, amount_out * destination_price / amount_in
+    pub protocol_price: u64,
+    ///protocol price in basis points of the source price, above 10000 the pool pays a premium
+    pub premium_bps: u64,
+}
+impl Sealed for SwapQuote {}
+impl Pack for SwapQuote {
+    const LEN: usize = 64;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 64];
+        let (
+            amount_in,
+            amount_out,
+            program_fee,
+            swap_fee,
+            source_price,
+            destination_price,
+            protocol_price,
+            premium_bps,
+        ) = mut_array_refs![output, 8, 8, 8, 8, 8, 8, 8, 8];
+        *amount_in = self.amount_in.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+        *program_fee = self.program_fee.to_le_bytes();
+        *swap_fee = self.swap_fee.to_le_bytes();
+        *source_price = self.source_price.to_le_bytes();
+        *destination_price = self.destination_price.to_le_bytes();
+        *protocol_price = self.protocol_price.to_le_bytes();
+        *premium_bps = self.premium_bps.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 64];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            amount_in,
+            amount_out,
+            program_fee,
+            swap_fee,
+            source_price,
+            destination_price,
+            protocol_price,
+            premium_bps,
+        ) = array_refs![input, 8, 8, 8, 8, 8, 8, 8, 8];
+        Ok(Self {
+            amount_in: u64::from_le_bytes(*amount_in),
+            amount_out: u64::from_le_bytes(*amount_out),
+            program_fee: u64::from_le_bytes(*program_fee),
+            swap_fee: u64::from_le_bytes(*swap_fee),
+            source_price: u64::from_le_bytes(*source_price),
+            destination_price: u64::from_le_bytes(*destination_price),
+            protocol_price: u64::from_le_bytes(*protocol_price),
+            premium_bps: u64::from_le_bytes(*premium_bps),
+        })
+    }
+}
+
+///Returned by AdminDeposit, Deposit and Withdraw with the amounts they moved and by QuoteDeposit and QuoteWithdraw
+///with the amounts the instruction would move with the same accounts
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LiquidityQuote {
+    ///tokens of token a deposited or withdrawn
+    pub token_a_amount: u64,
+    ///tokens of token b deposited or withdrawn
+    pub token_b_amount: u64,
+    ///swap tokens minted for a deposit or burned for a withdraw
+    pub swap_tokens: u64,
+    ///swap tokens taken as the program fee, zero until the pool charges fees
+    pub program_fee: u64,
+    ///swap tokens taken as the swap fee, zero until the pool charges fees
+    pub swap_fee: u64,
+    ///oracle price token a is valued at
+    pub token_a_price: u64,
+    ///oracle price token b is valued at
+    pub token_b_price: u64,
+}
+impl Sealed for LiquidityQuote {}
+impl Pack for LiquidityQuote {
+    const LEN: usize = 56;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 56];
+        let (
+            token_a_amount,
+            token_b_amount,
+            swap_tokens,
+            program_fee,
+            swap_fee,
+            token_a_price,
+            token_b_price,
+        ) = mut_array_refs![output, 8, 8, 8, 8, 8, 8, 8];
+        *token_a_amount = self.token_a_amount.to_le_bytes();
+        *token_b_amount = self.token_b_amount.to_le_bytes();
+        *swap_tokens = self.swap_tokens.to_le_bytes();
+        *program_fee = self.program_fee.to_le_bytes();
+        *swap_fee = self.swap_fee.to_le_bytes();
+        *token_a_price = self.token_a_price.to_le_bytes();
+        *token_b_price = self.token_b_price.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 56];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            token_a_amount,
+            token_b_amount,
+            swap_tokens,
+            program_fee,
+            swap_fee,
+            token_a_price,
+            token_b_price,
+        ) = array_refs![input, 8, 8, 8, 8, 8, 8, 8];
+        Ok(Self {
+            token_a_amount: u64::from_le_bytes(*token_a_amount),
+            token_b_amount: u64::from_le_bytes(*token_b_amount),
+            swap_tokens: u64::from_le_bytes(*swap_tokens),
+            program_fee: u64::from_le_bytes(*program_fee),
+            swap_fee: u64::from_le_bytes(*swap_fee),
+            token_a_price: u64::from_le_bytes(*token_a_price),
+            token_b_price: u64::from_le_bytes(*token_b_price),
+        })
+    }
+}
+
+///Return data of an instruction, decoded by the instruction that set it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReturnData {
+    ///returned by Swap and Quote
+    Swap(SwapQuote),
+    ///returned by AdminDeposit, Deposit and QuoteDeposit
+    Deposit(LiquidityQuote),
+    ///returned by Withdraw and QuoteWithdraw
+    Withdraw(LiquidityQuote),
+}
+
+impl ReturnData {
+    ///Decode the return data of an instruction of this program, as returned by get_return_data or a
+    ///transaction simulation. None for instructions that don't return data
+    pub fn decode(
+        instruction: &ZionInstruction,
+        program_id: &Pubkey,
+        data: &[u8],
+    ) -> Result<Option<Self>, ProgramError> {
+        if program_id != &crate::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        let return_data = match instruction {
+            ZionInstruction::Swap(_) | ZionInstruction::Quote(_) => {
+                ReturnData::Swap(SwapQuote::unpack_from_slice(&Self::pad::<{ SwapQuote::LEN }>(data)?)?)
+            },
+            ZionInstruction::AdminDeposit(_) | ZionInstruction::Deposit(_) | ZionInstruction::QuoteDeposit(_) => {
+                ReturnData::Deposit(LiquidityQuote::unpack_from_slice(&Self::pad::<{ LiquidityQuote::LEN }>(data)?)?)
+            },
+            ZionInstruction::Withdraw(_) | ZionInstruction::QuoteWithdraw(_) => {
+                ReturnData::Withdraw(LiquidityQuote::unpack_from_slice(&Self::pad::<{ LiquidityQuote::LEN }>(data)?)?)
+            },
+            _ => return Ok(None),
+        };
+
+        Ok(Some(return_data))
+    }
+
+    ///the runtime drops trailing zero bytes of the return data, put them back
+    fn pad<const LEN: usize>(data: &[u8]) -> Result<[u8; LEN], ProgramError> {
+        if data.len() > LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut padded = [0u8; LEN];
+        padded[..data.len()].copy_from_slice(data);
+        Ok(padded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LiquidityQuote, ReturnData, SwapQuote};
+    use crate::instructions::{AdminDeposit, Deposit, Swap, ZionInstruction};
+    use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+
+    #[test]
+    fn test_decode_return_data() {
+        let swap = ZionInstruction::Swap(Swap { amount: 100 });
+        let quote = SwapQuote {
+            amount_in: 100,
+            amount_out: 95,
+            source_price: 20,
+            destination_price: 21,
+            protocol_price: 19,
+            ..SwapQuote::default()
+        };
+        let mut data = [0u8; SwapQuote::LEN];
+        SwapQuote::pack(quote, &mut data).unwrap();
+
+        //trailing zeros are dropped by the runtime
+        let trimmed = data.len() - data.iter().rev().take_while(|byte| **byte == 0).count();
+        assert_eq!(ReturnData::decode(&swap, &crate::id(), &data[..trimmed]), Ok(Some(ReturnData::Swap(quote))));
+        assert_eq!(ReturnData::decode(&swap, &crate::id(), &data), Ok(Some(ReturnData::Swap(quote))));
+
+        assert_eq!(ReturnData::decode(&swap, &crate::id(), &[]), Ok(None));
+
+        let deposit = ZionInstruction::Deposit(Deposit { token_a_deposit: 10, token_b_deposit: 0 });
+        let quote = LiquidityQuote { token_a_amount: 10, swap_tokens: 7, token_a_price: 3, token_b_price: 4, ..LiquidityQuote::default() };
+        let mut data = [0u8; LiquidityQuote::LEN];
+        LiquidityQuote::pack(quote, &mut data).unwrap();
+        assert_eq!(ReturnData::decode(&deposit, &crate::id(), &data), Ok(Some(ReturnData::Deposit(quote))));
+        assert_eq!(ReturnData::decode(&deposit, &crate::id(), &[0u8; LiquidityQuote::LEN + 1]), Err(ProgramError::InvalidAccountData));
+        assert_eq!(ReturnData::decode(&deposit, &Pubkey::new_unique(), &data), Err(ProgramError::IncorrectProgramId));
+
+        let admin_deposit = ZionInstruction::AdminDeposit(AdminDeposit { token_a_deposit: 10, token_b_deposit: 0 });
+        assert_eq!(ReturnData::decode(&admin_deposit, &crate::id(), &data), Ok(Some(ReturnData::Deposit(quote))));
+
+        assert_eq!(ReturnData::decode(&ZionInstruction::Crank(), &crate::id(), &data), Ok(None));
+    }
+}
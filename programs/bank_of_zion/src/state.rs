@@ -1,5 +1,9 @@
-use crate::error::ZionError;
-use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use crate::{
+    error::ZionError,
+    pod::{PodBool, PodI64, PodU64},
+    return_data::{LiquidityQuote, SwapQuote},
+};
+use bytemuck::{Pod, Zeroable};
 use solana_program::{
     program_error::ProgramError,
     program_pack::{ Pack, Sealed},
@@ -8,22 +12,27 @@ use solana_program::{
     program_memory::sol_memcmp,
     
 };
-use spl_math::precise_number::PreciseNumber;
+use spl_math::{precise_number::PreciseNumber, uint::U256};
 
 ///Prefix used in generating the PDA for the swap authority
 pub const AUTHORITY_PREFIX: &str = "swap_authority";
 
+///Basis points in 100%, upper bound for every basis point setting
+pub const MAX_BPS: u64 = 10_000;
+
 /// Program states.
 #[repr(C)]
-#[derive(Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct SwapState {
 
+    ///layout version of the account data, see [SwapState::VERSION](struct.SwapState.html)
+    pub version: u8,
     ///admin of the Swap Pool
     pub admin: Pubkey,
     ///bump of the SwapState pda
     pub bump: u8,
     /// is the pool initialized
-    pub is_initialized: bool,
+    pub is_initialized: PodBool,
     ///PDA that owns/controls the vaults and mints
     pub swap_authority: Pubkey,
     ///bump of the swap authority pda
@@ -36,80 +45,56 @@ pub struct SwapState {
     ///Second token in the swap pool
     pub token_b: Token,
     ///basis point fee applied to transactions that are given to the admin
-    pub program_fee: u64, //wip, next version
+    pub program_fee: PodU64, //wip, next version
 
     ///basis point fee applied to transactios that are given to the user
-    pub swap_fee: u64 //wip, next version
+    pub swap_fee: PodU64, //wip, next version
+
+    ///pricing curve of the pool, see [CurveType](enum.CurveType.html)
+    pub curve_type: u8,
+    ///amplification coefficient of the StableSwap curve, zero for the other curves
+    pub amp: PodU64,
+    ///shape of the premium for the OraclePremium curve
+    pub premium: PremiumParams,
+    ///largest swap payout in basis points of the destination vault, zero for no limit
+    pub max_trade_bps: PodU64,
+    ///largest deviation of the swap price from the oracle price in basis points, zero for no limit
+    pub max_price_deviation_bps: PodU64,
+    ///pauses swaps and deposits when an oracle price jumps
+    pub breaker: CircuitBreaker,
+    ///oracle prices swaps are priced at, see [PriceMode](enum.PriceMode.html)
+    pub price_mode: u8,
+    ///seconds of price history the TWAP is taken over
+    pub twap_window_secs: PodU64,
+    ///additional oracles and freshness limits for token a
+    pub token_a_oracles: OracleConfig,
+    ///additional oracles and freshness limits for token b
+    pub token_b_oracles: OracleConfig,
+    ///oracle used for token a when its primary oracle isn't fresh, default for none
+    pub token_a_fallback_oracle: Pubkey,
+    ///oracle used for token b when its primary oracle isn't fresh, default for none
+    pub token_b_fallback_oracle: Pubkey,
+
+    ///space for new fields, always zero
+    pub reserved: [u8; 165],
 }
 impl Sealed for SwapState {}
 impl Pack for SwapState {
-    const LEN: usize = 371;
+    //version + v1 fields + reserved space for new fields
+    const LEN: usize = 1024;
 
     fn pack_into_slice(&self, output: &mut [u8]) {
-        let output = array_mut_ref![output, 0, 371];
-        let (
-            admin,
-            bump,
-            is_initialized,
-            swap_authority,
-            swap_authority_bump,
-            swap_mint,
-            token_a,
-            token_b,
-            program_fee,
-            swap_fee,
-        ) = mut_array_refs![output, 32, 1, 1, 32, 1, 32, 128, 128, 8, 8];
-        admin.copy_from_slice(self.admin.as_ref());
-        *bump = self.bump.to_le_bytes();
-        is_initialized[0] = self.is_initialized as u8;
-        swap_authority.copy_from_slice(self.swap_authority.as_ref());
-        *swap_authority_bump = self.swap_authority_bump.to_le_bytes();
-        swap_mint.copy_from_slice(self.swap_mint.as_ref());
-        self.token_a.pack_into_slice(&mut token_a[..]);
-        self.token_b.pack_into_slice(&mut token_b[..]);
-        *program_fee = self.program_fee.to_le_bytes();
-        *swap_fee = self.swap_fee.to_le_bytes();
+        output[..Self::LEN].copy_from_slice(bytemuck::bytes_of(self));
     }
     
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![input, 0, 371];
-        #[allow(clippy::ptr_offset_with_cast)]
-        let (
-            admin,
-            bump,
-            is_initialized,
-            swap_authority,
-            swap_authority_bump,
-            swap_mint,
-            token_a,
-            token_b,
-            program_fee,
-            swap_fee,
-        ) = array_refs![input, 32, 1, 1, 32, 1, 32, 128, 128, 8, 8];
-        Ok(Self {
-            admin: Pubkey::new_from_array(*admin),
-            bump: u8::from_be_bytes(*bump),
-            is_initialized: match is_initialized {
-                [0] => false,
-                [1] => true,
-                _ => return Err(ProgramError::InvalidAccountData),
-            },
-            swap_authority: Pubkey::new_from_array(*swap_authority),
-            swap_authority_bump: u8::from_be_bytes(*swap_authority_bump),
-            swap_mint: Pubkey::new_from_array(*swap_mint),
-            token_a: Token::unpack_from_slice(token_a)?,
-            token_b: Token::unpack_from_slice(token_b)?,
-            program_fee: u64::from_le_bytes(*program_fee),
-            swap_fee: u64::from_le_bytes(*swap_fee),
-        })
+        Self::load(input).copied()
     }
 }
 
-impl Clone for SwapState {
-    fn clone(&self) -> Self {
-        let mut packed_self = [0u8; Self::LEN];
-        Self::pack_into_slice(self, &mut packed_self);
-        Self::unpack_from_slice(&packed_self).unwrap()
+impl Default for SwapState {
+    fn default() -> Self {
+        Self::zeroed()
     }
 }
 
@@ -118,6 +103,95 @@ impl SwapState {
     ///Prefix for generating the PDA for the swap state
     pub const PREFIX: &'static str = "swap_state";
 
+    ///current layout version, v1 is the original unversioned layout
+    pub const VERSION: u8 = 2;
+
+    ///version of the original unversioned layout
+    pub const VERSION_V1: u8 = 1;
+
+    ///length of the original unversioned layout
+    pub const LEN_V1: usize = 371;
+
+    ///read the swap state in place from the account data
+    pub fn load(input: &[u8]) -> Result<&Self, ProgramError> {
+        if input.len() == Self::LEN_V1 {
+            return Err(ZionError::StateMigrationRequired.into());
+        }
+        let swap_state: &Self = input
+            .get(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        swap_state.check_layout()?;
+
+        Ok(swap_state)
+    }
+
+    ///mutable version of [SwapState::load](struct.SwapState.html#method.load)
+    pub fn load_mut(input: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if input.len() == Self::LEN_V1 {
+            return Err(ZionError::StateMigrationRequired.into());
+        }
+        let swap_state: &mut Self = input
+            .get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        swap_state.check_layout()?;
+
+        Ok(swap_state)
+    }
+
+    ///any byte pattern is a valid Pod, reject the ones that aren't a valid swap state
+    fn check_layout(&self) -> Result<(), ProgramError> {
+        if self.version != Self::VERSION {
+            return Err(ZionError::InvalidStateVersion.into());
+        }
+        if !self.is_initialized.is_valid() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if CurveType::try_from(self.curve_type).is_err() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !self.breaker.is_tripped.is_valid() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if PriceMode::try_from(self.price_mode).is_err() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !self.token_a_oracles.has_valid_count() || !self.token_b_oracles.has_valid_count() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
+    ///unpack an account still in the original unversioned layout, use MigrateState to rewrite it
+    pub fn unpack_v1(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != Self::LEN_V1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        //the v1 layout is the current layout without the version byte and the reserved space
+        let mut swap_state = Self::zeroed();
+        bytemuck::bytes_of_mut(&mut swap_state)[1..1 + Self::LEN_V1].copy_from_slice(input);
+        swap_state.version = Self::VERSION_V1;
+        if !swap_state.is_initialized.is_valid() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(swap_state)
+    }
+
+    ///pricing curve the pool was initialized with
+    pub fn curve(&self) -> Result<PoolCurve, ZionError> {
+        let curve = match CurveType::try_from(self.curve_type)? {
+            CurveType::OraclePremium => PoolCurve::OraclePremium(OraclePremiumCurve { premium: self.premium }),
+            CurveType::ConstantProduct => PoolCurve::ConstantProduct(ConstantProductCurve),
+            CurveType::StableSwap => PoolCurve::StableSwap(StableSwapCurve { amp: self.amp.into() }),
+        };
+
+        Ok(curve)
+    }
+
     ///tmp var, delete it
     pub const DECIMALS: u8 = 3;
 
@@ -127,7 +201,7 @@ impl SwapState {
         SwapState::validate_swap_state_key(swap_state_key)?;
         self.validate_swap_state_owner(swap_state_owner)?;
 
-        if self.is_initialized {
+        if self.is_initialized.into() {
             return Err(ZionError::PoolAlreadyInitialized.into());
         };
 
@@ -213,9 +287,9 @@ impl SwapState {
     pub fn get_price_premium(
         vault_a_value: PreciseNumber,
         vault_b_value: PreciseNumber,
-    ) -> PreciseNumber {
-        let one = PreciseNumber::new(1 as u128).expect("one");
-        let zero = PreciseNumber::new(0 as u128).expect("zero");
+    ) -> Result<PreciseNumber, ZionError> {
+        let one = to_precise(1)?;
+        let zero = to_precise(0)?;
 
         //can't be zero or the math breaks, for now min is 1
         let tmp_vault_a_value = if vault_a_value.less_than_or_equal(&zero) {
@@ -231,10 +305,53 @@ impl SwapState {
             vault_b_value.clone()
         };
 
-        tmp_vault_b_value.checked_div(&tmp_vault_a_value).expect("a valid number")
+        tmp_vault_b_value.checked_div(&tmp_vault_a_value).ok_or(ZionError::MathOverflow)
     }
     
-    ///calculate how much token_a and token_b to be deposited aswell as how many swap tokens received
+    ///calculate the value of every recoverable token in the protocol at market price
+    pub fn get_total_protocol_value (
+        &self,
+        vault_a_supply: u64,
+        token_a_market_price: u64,
+        fee_vault_a_supply: u64,
+        vault_b_supply: u64,
+        token_b_market_price: u64,
+        fee_vault_b_supply: u64,
+    ) -> Result<PreciseNumber, ZionError> {
+        let vault_a_value = self.token_a.get_market_value(vault_a_supply, token_a_market_price)?;
+        let vault_b_value = self.token_b.get_market_value(vault_b_supply, token_b_market_price)?;
+        let fee_vault_a_value = self.token_a.get_market_value(fee_vault_a_supply, token_a_market_price)?;
+        let fee_vault_b_value = self.token_b.get_market_value(fee_vault_b_supply, token_b_market_price)?;
+
+        let total_protocol_value = 
+            vault_a_value.checked_add(&vault_b_value).ok_or(ZionError::MathOverflow)?
+                .checked_add(&fee_vault_a_value).ok_or(ZionError::MathOverflow)?
+                    .checked_add(&fee_vault_b_value).ok_or(ZionError::MathOverflow)?;
+
+        //can't be zero or the math breaks, for now min is 1
+        if total_protocol_value.less_than_or_equal(&to_precise(0)?) {
+            return to_precise(1);
+        }
+
+        Ok(total_protocol_value)
+    }
+
+    ///calculate the swap tokens worth value out of total_protocol_value
+    fn get_swap_tokens_for_value (
+        value: &PreciseNumber,
+        total_protocol_value: &PreciseNumber,
+        swap_supply: u64,
+        round: RoundDirection,
+    ) -> Result<u64, ZionError> {
+        //multiply before dividing so the share keeps its precision on large supplies
+        let swap_tokens = to_precise(swap_supply)?
+            .checked_mul(value).ok_or(ZionError::MathOverflow)?
+            .checked_div(total_protocol_value).ok_or(ZionError::MathOverflow)?;
+
+        round.round(&swap_tokens)
+    }
+
+    ///calculate how many swap tokens are minted for depositing tokens_deposit of token a, rounded down
     pub fn calculate_swap_tokens (
         &self,
         tokens_deposit: u64,
@@ -245,221 +362,1580 @@ impl SwapState {
         token_b_market_price: u64,
         fee_vault_b_supply: u64,
         swap_supply: u64,
-    ) -> u64 {
+    ) -> Result<u64, ZionError> {
 
-        let zero = PreciseNumber::new(0 as u128).expect("zero");
-        let one = PreciseNumber::new(1 as u128).expect("one");
-        let swap_supply = PreciseNumber::new(swap_supply as u128).expect("swap_supply");
+        //total value of recoverable funds in the protocol
+        let total_protocol_value = self.get_total_protocol_value(
+            vault_a_supply,
+            token_a_market_price,
+            fee_vault_a_supply,
+            vault_b_supply,
+            token_b_market_price,
+            fee_vault_b_supply,
+        )?;
 
-        //total value of tokens in vault a
-        let vault_a_value = self.token_a.get_market_value(vault_a_supply, token_a_market_price);
-        
-        //total value of tokens in vault b
-        let vault_b_value = self.token_b.get_market_value(vault_b_supply, token_b_market_price);
+        //value of tokens user is depositing as priced by the pool's curve
+        let curve_value = self.curve()?.deposit_value(
+            tokens_deposit,
+            vault_a_supply,
+            token_a_market_price,
+            vault_b_supply,
+            token_b_market_price,
+            &total_protocol_value,
+        )?;
+
+        //deposits are never credited more than the market price, whatever the curve
+        let market_value = self.token_a.get_market_value(tokens_deposit, token_a_market_price)?;
+        let tokens_deposit_value = if curve_value.less_than(&market_value) {
+            curve_value
+        } else {
+            market_value
+        };
 
-        let price_premium = Self::get_price_premium(vault_a_value.clone(), vault_b_value.clone());
 
-        let tokens_deposit = PreciseNumber::new(tokens_deposit as u128).expect("a valid number");
-        
-        //value of tokens user is depositing
-        let tokens_deposit_value = tokens_deposit.checked_mul(&price_premium).expect("a valid number");
-        
-        let fee_vault_a_value = self.token_a.get_market_value(fee_vault_a_supply, token_a_market_price);
-        let fee_vault_b_value = self.token_b.get_market_value(fee_vault_b_supply, token_b_market_price);
-        
-        //total value of recoverable funds in the protocol
-        let mut total_protocol_value = 
-            vault_a_value.checked_add(&vault_b_value).expect("a valid number")
-                .checked_add(&fee_vault_a_value).expect("a valid number")
-                    .checked_add(&fee_vault_b_value).expect("a valid number");
+        //share of the users deposit in the total value of funds in the protocol, paid out in swap tokens
+        Self::get_swap_tokens_for_value(
+            &tokens_deposit_value,
+            &total_protocol_value,
+            swap_supply,
+            RoundDirection::Floor,
+        )
 
-        //can't be zero or the math breaks, for now min is 1
-        if total_protocol_value.less_than_or_equal(&zero) {
-            total_protocol_value = one.clone();
+    }
+
+    ///calculate how many swap tokens are burned for withdrawing tokens_withdraw of token a, rounded up
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_withdraw_swap_tokens (
+        &self,
+        tokens_withdraw: u64,
+        vault_a_supply: u64,
+        token_a_market_price: u64,
+        fee_vault_a_supply: u64,
+        vault_b_supply: u64,
+        token_b_market_price: u64,
+        fee_vault_b_supply: u64,
+        swap_supply: u64,
+    ) -> Result<u64, ZionError> {
+        if tokens_withdraw > vault_a_supply {
+            return Err(ZionError::InvalidTokenAmount);
         }
-        
-        //percentage value of users deposit to total value of funds in the protocol
-        let percent = tokens_deposit_value.checked_div(&total_protocol_value).expect("a valid number");
-        
-        //miltiply % of user value contributed to total protocol value against total swap tokens to get how many tokens the user should receive
-        let swap_tokens_from_deposit = swap_supply.checked_mul(&percent).expect("a valid number");
-        
-        return swap_tokens_from_deposit.to_imprecise().expect("a valid number") as u64
 
+        let total_protocol_value = self.get_total_protocol_value(
+            vault_a_supply,
+            token_a_market_price,
+            fee_vault_a_supply,
+            vault_b_supply,
+            token_b_market_price,
+            fee_vault_b_supply,
+        )?;
+
+        let curve_value = self.curve()?.withdraw_value(
+            tokens_withdraw,
+            vault_a_supply,
+            token_a_market_price,
+            vault_b_supply,
+            token_b_market_price,
+            &total_protocol_value,
+        )?;
+
+        //withdrawals are never charged less than the market price, whatever the curve
+        let market_value = self.token_a.get_market_value(tokens_withdraw, token_a_market_price)?;
+        let tokens_withdraw_value = if curve_value.greater_than(&market_value) {
+            curve_value
+        } else {
+            market_value
+        };
+
+        Self::get_swap_tokens_for_value(
+            &tokens_withdraw_value,
+            &total_protocol_value,
+            swap_supply,
+            RoundDirection::Ceiling,
+        )
     }
     
-    ///calculate how many destination tokens a user receives when swapping source tokens
+    ///source and destination prices a swap is priced at, the reference prices are the TWAP or the EMA
+    ///depending on the price mode and are ignored in Spot mode
+    pub fn get_swap_prices (
+        &self,
+        source_spot_price: u64,
+        source_reference_price: u64,
+        destination_spot_price: u64,
+        destination_reference_price: u64,
+    ) -> Result<(u64, u64), ZionError> {
+        let prices = match PriceMode::try_from(self.price_mode)? {
+            PriceMode::Spot => (source_spot_price, destination_spot_price),
+
+            //source tokens are valued at the lower price and destination tokens at the higher one
+            PriceMode::Conservative => (
+                source_spot_price.min(source_reference_price),
+                destination_spot_price.max(destination_reference_price),
+            ),
+            PriceMode::Ema => (source_reference_price, destination_reference_price),
+        };
+
+        Ok(prices)
+    }
+
+    ///check a swap against the pool's trade size and price deviation limits
+    pub fn validate_trade (
+        &self,
+        token_amount: u64,
+        source_market_price: u64,
+        destination_amount: u64,
+        destination_market_price: u64,
+        destination_supply: u64,
+    ) -> Result<(), ZionError> {
+        let max_bps = U256::from(MAX_BPS);
+
+        //payout as a share of the destination vault
+        let max_trade_bps = u64::from(self.max_trade_bps);
+        if max_trade_bps != 0 {
+            let max_destination_amount = U256::from(destination_supply) * U256::from(max_trade_bps);
+            if U256::from(destination_amount) * max_bps > max_destination_amount {
+                return Err(ZionError::TradeTooLarge);
+            }
+        }
+
+        //value paid out against the value swapped in, both at the oracle price
+        let max_price_deviation_bps = u64::from(self.max_price_deviation_bps);
+        if max_price_deviation_bps != 0 {
+            let source_value = U256::from(token_amount) * U256::from(source_market_price);
+            let destination_value = U256::from(destination_amount) * U256::from(destination_market_price);
+            let deviation = abs_diff(source_value, destination_value);
+            if deviation * max_bps > source_value * U256::from(max_price_deviation_bps) {
+                return Err(ZionError::PriceDeviationTooHigh);
+            }
+        }
+
+        Ok(())
+    }
+
+    ///calculate how many destination tokens a user receives when swapping source tokens, rounded down
     pub fn calculate_tokens_to_swap (
-        source: Token,
+        &self,
         source_supply: u64,
         source_market_price: u64,
-        destination: Token,
         destination_market_price: u64,
         destination_supply: u64,
         token_amount: u64,
 
-    ) -> u64 {
-        let token_amount = PreciseNumber::new(token_amount as u128).expect("a valid number");
-       
-        //total value of tokens in vault a
-        let source_value = source.get_market_value(source_supply, source_market_price);
-        
-        //total value of tokens in vault b
-        let destination_value = destination.get_market_value(destination_supply, destination_market_price);
-        
-        let price_premium = Self::get_price_premium(source_value, destination_value);
-        
-        let source_value = Token::get_protocol_price(source_market_price, price_premium)
-            .checked_mul(&token_amount).expect("a valid number");
-        
-        let tokens_receive = source_value
-            .checked_div(&PreciseNumber::new(destination_market_price as u128).expect("a valid number"))
-            .expect("a valid number")
-            .floor().expect("a valid number");
-        
-        tokens_receive.to_imprecise().expect("a valid number") as u64
-        
-
+    ) -> Result<u64, ZionError> {
+        self.curve()?.swap(
+            source_supply,
+            source_market_price,
+            destination_market_price,
+            destination_supply,
+            token_amount,
+        )
     }
 
-}
+    ///Price a swap of amount source tokens given the oracle prices and the reference prices of the pool's price mode.
+    ///Swap, Quote and off-chain simulators all go through here so they agree to the token.
+    ///
+    ///The fees are always zero: Swap doesn't charge program_fee or swap_fee yet, fees are a known issue of this
+    ///version of the program. Quoting them would price a swap the program doesn't make
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_swap (
+        &self,
+        source_price: u64,
+        source_reference_price: u64,
+        destination_price: u64,
+        destination_reference_price: u64,
+        source_supply: u64,
+        destination_supply: u64,
+        amount: u64,
+    ) -> Result<SwapQuote, ZionError> {
+        let (swap_source_price, swap_destination_price) = self.get_swap_prices(
+            source_price,
+            source_reference_price,
+            destination_price,
+            destination_reference_price,
+        )?;
+
+        //calculate how mant destination tokens user receives for source_tokens
+        let destination_amount = self.calculate_tokens_to_swap(
+            source_supply,
+            swap_source_price,
+            swap_destination_price,
+            destination_supply,
+            amount,
+        )?;
+
+        //enforce the pool's trade size and price impact limits
+        self.validate_trade(
+            amount,
+            source_price,
+            destination_amount,
+            destination_price,
+            destination_supply,
+        )?;
+
+        //price the pool pays per source token and how far it is from the source price
+        let protocol_price = if amount == 0 {
+            0
+        } else {
+            destination_amount as u128 * swap_destination_price as u128 / amount as u128
+        };
+        let premium_bps = if swap_source_price == 0 {
+            0
+        } else {
+            protocol_price * MAX_BPS as u128 / swap_source_price as u128
+        };
 
-/// Program states.
-#[repr(C)]
-#[derive(Debug, Default, PartialEq)]
-pub struct Token {
-    ///Mint pubkey for the token
-    pub mint: Pubkey, //32
-    ///pubkey for the vault associated with this token
-    pub vault: Pubkey, //64
-    ///Token account where fee's are stored for this token
-    pub fee_vault: Pubkey, //96
-    ///basis point fee applied to transactions that
-    pub oracle: Pubkey //128
-}
+        Ok(SwapQuote {
+            amount_in: amount,
+            amount_out: destination_amount,
+            //Swap doesn't charge the pool's fees yet, see the doc comment
+            program_fee: 0,
+            swap_fee: 0,
+            source_price: swap_source_price,
+            destination_price: swap_destination_price,
+            protocol_price: u64::try_from(protocol_price).unwrap_or(u64::MAX),
+            premium_bps: u64::try_from(premium_bps).unwrap_or(u64::MAX),
+        })
+    }
 
-impl Sealed for Token {}
-impl Pack for Token {
-    const LEN: usize = 128;
+    ///Swap tokens minted for a deposit, shared by Deposit, QuoteDeposit and off-chain simulators
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_deposit (
+        &self,
+        token_a_deposit: u64,
+        token_b_deposit: u64,
+        vault_a_supply: u64,
+        fee_vault_a_supply: u64,
+        vault_b_supply: u64,
+        fee_vault_b_supply: u64,
+        swap_supply: u64,
+        token_a_price: u64,
+        token_b_price: u64,
+    ) -> Result<LiquidityQuote, ZionError> {
+        //both deposits are priced at the vault balances before the deposit
+        let token_a_swap_tokens = if token_a_deposit > 0 {
+            self.calculate_swap_tokens(
+                token_a_deposit,
+                vault_a_supply,
+                token_a_price,
+                fee_vault_a_supply,
+                vault_b_supply,
+                token_b_price,
+                fee_vault_b_supply,
+                swap_supply
+            )?
+        } else {
+            0
+        };
 
-    fn pack_into_slice(&self, output: &mut [u8]) {
-        let output = array_mut_ref![output, 0, 128];
-        let (
-            mint,
-            vault,
-            fee_vault,
-            oracle,
-        ) = mut_array_refs![output, 32, 32, 32, 32];
-        mint.copy_from_slice(self.mint.as_ref());
-        vault.copy_from_slice(self.vault.as_ref());
-        fee_vault.copy_from_slice(self.fee_vault.as_ref());
-        oracle.copy_from_slice(self.oracle.as_ref());
-    }
+        let token_b_swap_tokens = if token_b_deposit > 0 {
+            self.calculate_swap_tokens(
+                token_b_deposit,
+                vault_b_supply,
+                token_b_price,
+                fee_vault_b_supply,
+                vault_a_supply,
+                token_a_price,
+                fee_vault_a_supply,
+                swap_supply
+            )?
+        } else {
+            0
+        };
 
-    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![input, 0, 128];
-        #[allow(clippy::ptr_offset_with_cast)]
-        let (
-            mint,
-            vault,
-            fee_vault,
-            oracle,
-        ) = array_refs![input, 32, 32, 32, 32];
-        Ok(Self {
-            mint: Pubkey::new_from_array(*mint),
-            vault: Pubkey::new_from_array(*vault),
-            fee_vault: Pubkey::new_from_array(*fee_vault),
-            oracle: Pubkey::new_from_array(*oracle),
+        Ok(LiquidityQuote {
+            token_a_amount: token_a_deposit,
+            token_b_amount: token_b_deposit,
+            swap_tokens: token_a_swap_tokens.checked_add(token_b_swap_tokens).ok_or(ZionError::MathOverflow)?,
+            //the pool doesn't charge fees yet
+            program_fee: 0,
+            swap_fee: 0,
+            token_a_price,
+            token_b_price,
         })
     }
-}
-impl Clone for Token {
-    fn clone(&self) -> Self {
-        let mut packed_self = [0u8; Self::LEN];
-        Self::pack_into_slice(self, &mut packed_self);
-        Self::unpack_from_slice(&packed_self).unwrap()
-    }
-}
-
-impl Token {
 
-    ///validate the token accounts against the Token struct
-    pub fn validate_accounts (
+    ///Swap tokens burned for a withdraw, shared by Withdraw, QuoteWithdraw and off-chain simulators
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_withdraw (
         &self,
-        mint: &Pubkey,
-        vault: &Pubkey,
-        fee_vault: &Pubkey,
-        oracle: &Pubkey,
-    ) -> Result<(), ProgramError> {
-        if !cmp_pubkeys(&self.mint, mint) {
-            return Err(ZionError::InvalidMint.into());
-        };
-        if !cmp_pubkeys(&self.vault, vault) {
-            return Err(ZionError::InvalidVault.into());
-        };
-        if !cmp_pubkeys(&self.fee_vault, fee_vault) {
-            return Err(ZionError::InvalidFeeVault.into());
+        token_a_withdraw: u64,
+        token_b_withdraw: u64,
+        vault_a_supply: u64,
+        fee_vault_a_supply: u64,
+        vault_b_supply: u64,
+        fee_vault_b_supply: u64,
+        swap_supply: u64,
+        token_a_price: u64,
+        token_b_price: u64,
+    ) -> Result<LiquidityQuote, ZionError> {
+        //calculate how many swap tokens are needed for token_a_withdraw amount
+        let token_a_swap_tokens = if token_a_withdraw > 0 {
+            self.calculate_withdraw_swap_tokens(
+                token_a_withdraw,
+                vault_a_supply,
+                token_a_price,
+                fee_vault_a_supply,
+                vault_b_supply,
+                token_b_price,
+                fee_vault_b_supply,
+                swap_supply
+            )?
+        } else {
+            0
         };
-        if !cmp_pubkeys(&self.oracle, oracle) {
-            return Err(ZionError::InvalidOracle.into());
+
+        //calculate how many swap tokens are needed for token_b_withdraw amount
+        let token_b_swap_tokens = if token_b_withdraw > 0 {
+            self.calculate_withdraw_swap_tokens(
+                token_b_withdraw,
+                vault_b_supply,
+                token_b_price,
+                fee_vault_b_supply,
+                vault_a_supply,
+                token_a_price,
+                fee_vault_a_supply,
+                swap_supply
+            )?
+        } else {
+            0
         };
 
-        return Ok(())
+        Ok(LiquidityQuote {
+            token_a_amount: token_a_withdraw,
+            token_b_amount: token_b_withdraw,
+            swap_tokens: token_a_swap_tokens.checked_add(token_b_swap_tokens).ok_or(ZionError::MathOverflow)?,
+            //the pool doesn't charge fees yet
+            program_fee: 0,
+            swap_fee: 0,
+            token_a_price,
+            token_b_price,
+        })
     }
 
-    ///calculate the value of the tokens
-    fn calculate_market_value (
-        price: PreciseNumber,
-        supply: u64,
-        //what about decimals?
-    ) -> PreciseNumber {
-        let supply = PreciseNumber::new(supply as u128).expect("valid number");
-        let value = price.checked_mul(&supply).expect("valid number");
+}
 
-        //maybe remove this, change formula so its never 0
-        //if value.less_than_or_equal(&zero) {
-        //    return PreciseNumber::new(1 as u128).expect("zero");
-        //}
-        return value
+///pricing curves the pool can be initialized with
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveType {
+    ///oracle price scaled by the premium between the vault values, the original curve
+    OraclePremium = 0,
+    ///constant product x * y = k on the vault balances
+    ConstantProduct = 1,
+    ///StableSwap invariant on the vault balances with an amplification coefficient
+    StableSwap = 2,
+}
+
+impl TryFrom<u8> for CurveType {
+    type Error = ZionError;
+
+    fn try_from(curve_type: u8) -> Result<Self, Self::Error> {
+        match curve_type {
+            0 => Ok(CurveType::OraclePremium),
+            1 => Ok(CurveType::ConstantProduct),
+            2 => Ok(CurveType::StableSwap),
+            _ => Err(ZionError::InvalidCurve),
+        }
     }
-    
-    ///retrieve the value of the tokens
-    pub fn get_market_value (
+}
+
+///Pricing curve of the pool.
+///
+///Values are market values (price * tokens) so they can be compared with
+///[SwapState::get_total_protocol_value](struct.SwapState.html#method.get_total_protocol_value).
+///`vault_supply` is the vault tokens are deposited into or withdrawn from, `other_vault_supply` the other one
+pub trait Curve {
+    ///destination tokens paid out for token_amount source tokens, rounded down
+    fn swap(
         &self,
-        amount: u64,
-        market_price: u64
-    ) -> PreciseNumber {
-        let market_price = PreciseNumber::new(market_price as u128).expect("market_price");
-        Token::calculate_market_value(market_price, amount)
-    }
+        source_supply: u64,
+        source_market_price: u64,
+        destination_market_price: u64,
+        destination_supply: u64,
+        token_amount: u64,
+    ) -> Result<u64, ZionError>;
 
-    ///get the local price of the token
-    pub fn get_protocol_price (
-        price: u64,
-        premium: PreciseNumber
+    ///value credited for depositing tokens_deposit tokens
+    fn deposit_value(
+        &self,
+        tokens_deposit: u64,
+        vault_supply: u64,
+        market_price: u64,
+        other_vault_supply: u64,
+        other_market_price: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError>;
+
+    ///value charged for withdrawing tokens_withdraw tokens, the caller never charges less than the market value
+    fn withdraw_value(
+        &self,
+        tokens_withdraw: u64,
+        vault_supply: u64,
+        market_price: u64,
+        other_vault_supply: u64,
+        other_market_price: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError>;
+}
+
+///Curve of a pool, dispatched with a match so picking it doesn't allocate on the program's heap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PoolCurve {
+    ///oracle price scaled by the premium between the vault values
+    OraclePremium(OraclePremiumCurve),
+    ///constant product x * y = k on the vault balances
+    ConstantProduct(ConstantProductCurve),
+    ///StableSwap invariant on the vault balances with its amplification coefficient
+    StableSwap(StableSwapCurve),
+}
 
-    ) -> PreciseNumber {
-        let price = PreciseNumber::new(price as u128).expect("price");
-        price.checked_mul(&premium).expect("a valid number")
+impl Curve for PoolCurve {
+    fn swap(
+        &self,
+        source_supply: u64,
+        source_market_price: u64,
+        destination_market_price: u64,
+        destination_supply: u64,
+        token_amount: u64,
+    ) -> Result<u64, ZionError> {
+        match self {
+            PoolCurve::OraclePremium(curve) => curve.swap(source_supply, source_market_price, destination_market_price, destination_supply, token_amount),
+            PoolCurve::ConstantProduct(curve) => curve.swap(source_supply, source_market_price, destination_market_price, destination_supply, token_amount),
+            PoolCurve::StableSwap(curve) => curve.swap(source_supply, source_market_price, destination_market_price, destination_supply, token_amount),
+        }
     }
 
-}
+    fn deposit_value(
+        &self,
+        tokens_deposit: u64,
+        vault_supply: u64,
+        market_price: u64,
+        other_vault_supply: u64,
+        other_market_price: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        match self {
+            PoolCurve::OraclePremium(curve) => curve.deposit_value(tokens_deposit, vault_supply, market_price, other_vault_supply, other_market_price, total_protocol_value),
+            PoolCurve::ConstantProduct(curve) => curve.deposit_value(tokens_deposit, vault_supply, market_price, other_vault_supply, other_market_price, total_protocol_value),
+            PoolCurve::StableSwap(curve) => curve.deposit_value(tokens_deposit, vault_supply, market_price, other_vault_supply, other_market_price, total_protocol_value),
+        }
+    }
 
-///compare two Pubkeys
-pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
-    sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
+    fn withdraw_value(
+        &self,
+        tokens_withdraw: u64,
+        vault_supply: u64,
+        market_price: u64,
+        other_vault_supply: u64,
+        other_market_price: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        match self {
+            PoolCurve::OraclePremium(curve) => curve.withdraw_value(tokens_withdraw, vault_supply, market_price, other_vault_supply, other_market_price, total_protocol_value),
+            PoolCurve::ConstantProduct(curve) => curve.withdraw_value(tokens_withdraw, vault_supply, market_price, other_vault_supply, other_market_price, total_protocol_value),
+            PoolCurve::StableSwap(curve) => curve.withdraw_value(tokens_withdraw, vault_supply, market_price, other_vault_supply, other_market_price, total_protocol_value),
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Token;
-    use super::SwapState;
-    use solana_program:: { 
-        pubkey::Pubkey,
-    };
+///Tokens are priced at the oracle price scaled by the value of the other vault over the value of this one,
+///the scarcer token is worth more to the pool
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OraclePremiumCurve {
+    ///shape of the premium applied to the oracle price
+    pub premium: PremiumParams,
+}
 
-    ///assume both tokens have the same price
-    #[test]
+impl Curve for OraclePremiumCurve {
+    fn swap(
+        &self,
+        source_supply: u64,
+        source_market_price: u64,
+        destination_market_price: u64,
+        destination_supply: u64,
+        token_amount: u64,
+    ) -> Result<u64, ZionError> {
+        let token_amount = to_precise(token_amount)?;
+       
+        //total value of tokens in the source vault
+        let source_value = Token::calculate_market_value(to_precise(source_market_price)?, source_supply)?;
+        
+        //total value of tokens in the destination vault
+        let destination_value = Token::calculate_market_value(to_precise(destination_market_price)?, destination_supply)?;
+        
+        let price_premium = SwapState::get_price_premium(source_value, destination_value)?;
+
+        //a swap never pays more than the market price, a premium above it would be handed out
+        //again on the way back and could be walked out of the pool by swapping back and forth
+        let one = to_precise(1)?;
+        let price_premium = if price_premium.greater_than(&one) {
+            one
+        } else {
+            price_premium
+        };
+        
+        let source_value = Token::get_protocol_price(source_market_price, price_premium, &self.premium)?
+            .checked_mul(&token_amount).ok_or(ZionError::MathOverflow)?;
+        
+        let tokens_receive = source_value
+            .checked_div(&to_precise(destination_market_price)?)
+            .ok_or(ZionError::MathOverflow)?;
+        
+        RoundDirection::Floor.round(&tokens_receive)
+    }
+
+    fn deposit_value(
+        &self,
+        tokens_deposit: u64,
+        vault_supply: u64,
+        market_price: u64,
+        other_vault_supply: u64,
+        other_market_price: u64,
+        _: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        let vault_value = Token::calculate_market_value(to_precise(market_price)?, vault_supply)?;
+        let other_vault_value = Token::calculate_market_value(to_precise(other_market_price)?, other_vault_supply)?;
+        let price_premium = SwapState::get_price_premium(vault_value, other_vault_value)?;
+
+        //value of tokens user is depositing at the protocol price
+        Token::get_protocol_price(market_price, price_premium, &self.premium)?
+            .checked_mul(&to_precise(tokens_deposit)?).ok_or(ZionError::MathOverflow)
+    }
+
+    fn withdraw_value(
+        &self,
+        tokens_withdraw: u64,
+        vault_supply: u64,
+        market_price: u64,
+        other_vault_supply: u64,
+        other_market_price: u64,
+        _: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        let remaining_vault_supply = vault_supply
+            .checked_sub(tokens_withdraw)
+            .ok_or(ZionError::InvalidTokenAmount)?;
+
+        //the premium is taken from the pool left after the withdrawal, which is the premium a deposit
+        //of the same tokens was credited, so a deposit followed by a withdrawal can't profit
+        let remaining_vault_value = Token::calculate_market_value(to_precise(market_price)?, remaining_vault_supply)?;
+        let other_vault_value = Token::calculate_market_value(to_precise(other_market_price)?, other_vault_supply)?;
+        let price_premium = SwapState::get_price_premium(remaining_vault_value, other_vault_value)?;
+
+        Token::get_protocol_price(market_price, price_premium, &self.premium)?
+            .checked_mul(&to_precise(tokens_withdraw)?).ok_or(ZionError::MathOverflow)
+    }
+}
+
+///x * y = k on the vault balances, oracle prices are only used to value the pool for deposits and withdrawals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstantProductCurve;
+
+impl InvariantCurve for ConstantProductCurve {
+    ///sqrt(x * y), rounded down
+    fn invariant(&self, vault_supply: u64, other_vault_supply: u64) -> Result<u128, ZionError> {
+        Ok(sqrt(vault_supply as u128 * other_vault_supply as u128))
+    }
+}
+
+impl Curve for ConstantProductCurve {
+    fn swap(
+        &self,
+        source_supply: u64,
+        _: u64,
+        _: u64,
+        destination_supply: u64,
+        token_amount: u64,
+    ) -> Result<u64, ZionError> {
+        if source_supply == 0 || destination_supply == 0 {
+            return Err(ZionError::EmptyVault);
+        }
+
+        //destination * amount / (source + amount), the division rounds down
+        let new_source_supply = U256::from(source_supply)
+            .checked_add(U256::from(token_amount)).ok_or(ZionError::MathOverflow)?;
+        let tokens_receive = U256::from(destination_supply)
+            .checked_mul(U256::from(token_amount)).ok_or(ZionError::MathOverflow)?
+            .checked_div(new_source_supply).ok_or(ZionError::MathOverflow)?;
+
+        u256_to_u64(tokens_receive)
+    }
+
+    fn deposit_value(
+        &self,
+        tokens_deposit: u64,
+        vault_supply: u64,
+        _: u64,
+        other_vault_supply: u64,
+        _: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        self.invariant_deposit_value(tokens_deposit, vault_supply, other_vault_supply, total_protocol_value)
+    }
+
+    fn withdraw_value(
+        &self,
+        tokens_withdraw: u64,
+        vault_supply: u64,
+        _: u64,
+        other_vault_supply: u64,
+        _: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        self.invariant_withdraw_value(tokens_withdraw, vault_supply, other_vault_supply, total_protocol_value)
+    }
+}
+
+///StableSwap invariant for two tokens, flat around the balanced pool and constant product like at the edges.
+///The higher the amplification coefficient the flatter the curve
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StableSwapCurve {
+    ///amplification coefficient
+    pub amp: u64,
+}
+
+impl StableSwapCurve {
+    ///smallest amplification coefficient a pool can be initialized with
+    pub const MIN_AMP: u64 = 1;
+
+    ///largest amplification coefficient a pool can be initialized with
+    pub const MAX_AMP: u64 = 1_000_000;
+
+    ///newton iterations before giving up on converging
+    const ITERATIONS: usize = 64;
+
+    ///A * n^n with n = 2
+    fn ann(&self) -> Result<U256, ZionError> {
+        if self.amp < Self::MIN_AMP || self.amp > Self::MAX_AMP {
+            return Err(ZionError::InvalidCurve);
+        }
+        U256::from(self.amp).checked_mul(U256::from(4u8)).ok_or(ZionError::MathOverflow)
+    }
+
+    ///solve A * n^n * (x + y) + D = A * n^n * D + D^3 / (n^n * x * y) for D
+    fn compute_d(&self, x: u64, y: u64) -> Result<U256, ZionError> {
+        //an empty vault has no value left on the curve
+        if x == 0 || y == 0 {
+            return Ok(U256::zero());
+        }
+        let ann = self.ann()?;
+        let two = U256::from(2u8);
+        let x = U256::from(x);
+        let y = U256::from(y);
+        let sum = x.checked_add(y).ok_or(ZionError::MathOverflow)?;
+        let prod = x
+            .checked_mul(y).ok_or(ZionError::MathOverflow)?
+            .checked_mul(U256::from(4u8)).ok_or(ZionError::MathOverflow)?;
+
+        let mut d = sum;
+        for _ in 0..Self::ITERATIONS {
+            //D^3 / (n^n * x * y), in a single division so the iterations don't oscillate on the truncation
+            let d_p = d
+                .checked_mul(d).ok_or(ZionError::MathOverflow)?
+                .checked_mul(d).ok_or(ZionError::MathOverflow)?
+                .checked_div(prod).ok_or(ZionError::MathOverflow)?;
+
+            //(Ann * S + n * D_P) * D / ((Ann - 1) * D + (n + 1) * D_P)
+            let numerator = ann
+                .checked_mul(sum).ok_or(ZionError::MathOverflow)?
+                .checked_add(d_p.checked_mul(two).ok_or(ZionError::MathOverflow)?).ok_or(ZionError::MathOverflow)?
+                .checked_mul(d).ok_or(ZionError::MathOverflow)?;
+            let denominator = (ann - U256::one())
+                .checked_mul(d).ok_or(ZionError::MathOverflow)?
+                .checked_add(d_p.checked_mul(U256::from(3u8)).ok_or(ZionError::MathOverflow)?).ok_or(ZionError::MathOverflow)?;
+
+            let d_prev = d;
+            d = numerator.checked_div(denominator).ok_or(ZionError::MathOverflow)?;
+            if abs_diff(d, d_prev) <= U256::one() {
+                return Ok(d);
+            }
+        }
+
+        Ok(d)
+    }
+
+    ///solve the invariant for the other vault balance given one vault balance x and D
+    fn compute_y(&self, x: u64, d: U256) -> Result<U256, ZionError> {
+        let ann = self.ann()?;
+        let two = U256::from(2u8);
+        let x = U256::from(x);
+
+        //c = D^3 / (n^n * x * Ann), b = x + D / Ann
+        let c = d
+            .checked_mul(d).ok_or(ZionError::MathOverflow)?
+            .checked_mul(d).ok_or(ZionError::MathOverflow)?
+            .checked_div(
+                x.checked_mul(ann).ok_or(ZionError::MathOverflow)?
+                    .checked_mul(U256::from(4u8)).ok_or(ZionError::MathOverflow)?
+            ).ok_or(ZionError::MathOverflow)?;
+        let b = x
+            .checked_add(d.checked_div(ann).ok_or(ZionError::MathOverflow)?).ok_or(ZionError::MathOverflow)?;
+
+        //y = (y^2 + c) / (2y + b - D)
+        let mut y = d;
+        for _ in 0..Self::ITERATIONS {
+            let numerator = y
+                .checked_mul(y).ok_or(ZionError::MathOverflow)?
+                .checked_add(c).ok_or(ZionError::MathOverflow)?;
+            let denominator = y
+                .checked_mul(two).ok_or(ZionError::MathOverflow)?
+                .checked_add(b).ok_or(ZionError::MathOverflow)?
+                .checked_sub(d).ok_or(ZionError::MathOverflow)?;
+
+            let y_prev = y;
+            y = numerator.checked_div(denominator).ok_or(ZionError::MathOverflow)?;
+            if abs_diff(y, y_prev) <= U256::one() {
+                return Ok(y);
+            }
+        }
+
+        Ok(y)
+    }
+}
+
+impl InvariantCurve for StableSwapCurve {
+    fn invariant(&self, vault_supply: u64, other_vault_supply: u64) -> Result<u128, ZionError> {
+        let d = self.compute_d(vault_supply, other_vault_supply)?;
+        if d > U256::from(u128::MAX) {
+            return Err(ZionError::MathOverflow);
+        }
+
+        Ok(d.as_u128())
+    }
+}
+
+impl Curve for StableSwapCurve {
+    fn swap(
+        &self,
+        source_supply: u64,
+        _: u64,
+        _: u64,
+        destination_supply: u64,
+        token_amount: u64,
+    ) -> Result<u64, ZionError> {
+        if source_supply == 0 || destination_supply == 0 {
+            return Err(ZionError::EmptyVault);
+        }
+        let new_source_supply = source_supply
+            .checked_add(token_amount)
+            .ok_or(ZionError::MathOverflow)?;
+
+        //newton only gets within 1 of D, solve with the larger D so the new destination balance is never too low
+        let d = self.compute_d(source_supply, destination_supply)?
+            .checked_add(U256::one()).ok_or(ZionError::MathOverflow)?;
+        let new_destination_supply = self.compute_y(new_source_supply, d)?;
+
+        //and drop one more token for the rounding in compute_y
+        let tokens_receive = U256::from(destination_supply)
+            .saturating_sub(new_destination_supply)
+            .saturating_sub(U256::one());
+
+        u256_to_u64(tokens_receive)
+    }
+
+    fn deposit_value(
+        &self,
+        tokens_deposit: u64,
+        vault_supply: u64,
+        _: u64,
+        other_vault_supply: u64,
+        _: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        self.invariant_deposit_value(tokens_deposit, vault_supply, other_vault_supply, total_protocol_value)
+    }
+
+    fn withdraw_value(
+        &self,
+        tokens_withdraw: u64,
+        vault_supply: u64,
+        _: u64,
+        other_vault_supply: u64,
+        _: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        self.invariant_withdraw_value(tokens_withdraw, vault_supply, other_vault_supply, total_protocol_value)
+    }
+}
+
+///Curves defined by an invariant D of the vault balances that scales linearly with them.
+///
+///A single sided deposit or withdrawal moves D, the value credited or charged is the same share
+///of the pool as the share D moved by. D is only known to within 1 so deposits use the smaller
+///change and withdrawals the larger one
+trait InvariantCurve {
+    ///invariant of the vault balances
+    fn invariant(&self, vault_supply: u64, other_vault_supply: u64) -> Result<u128, ZionError>;
+
+    ///share of the pool a deposit moves D up by, rounded down
+    fn invariant_deposit_value(
+        &self,
+        tokens_deposit: u64,
+        vault_supply: u64,
+        other_vault_supply: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        let new_vault_supply = vault_supply
+            .checked_add(tokens_deposit)
+            .ok_or(ZionError::MathOverflow)?;
+
+        let d = self.invariant(vault_supply, other_vault_supply)?;
+        if d == 0 {
+            return Err(ZionError::EmptyVault);
+        }
+        let d = d.checked_add(1).ok_or(ZionError::MathOverflow)?;
+        let new_d = self.invariant(new_vault_supply, other_vault_supply)?.saturating_sub(1);
+
+        get_invariant_share(new_d.saturating_sub(d), d, total_protocol_value)
+    }
+
+    ///share of the pool a withdrawal moves D down by, rounded up
+    fn invariant_withdraw_value(
+        &self,
+        tokens_withdraw: u64,
+        vault_supply: u64,
+        other_vault_supply: u64,
+        total_protocol_value: &PreciseNumber,
+    ) -> Result<PreciseNumber, ZionError> {
+        let remaining_vault_supply = vault_supply
+            .checked_sub(tokens_withdraw)
+            .ok_or(ZionError::InvalidTokenAmount)?;
+
+        let d = self.invariant(vault_supply, other_vault_supply)?;
+        if d == 0 {
+            return Err(ZionError::EmptyVault);
+        }
+        let d = d.checked_add(1).ok_or(ZionError::MathOverflow)?;
+        let new_d = self.invariant(remaining_vault_supply, other_vault_supply)?.saturating_sub(1);
+
+        get_invariant_share(d.saturating_sub(new_d), d, total_protocol_value)
+    }
+}
+
+///total_protocol_value * change / invariant
+fn get_invariant_share(
+    change: u128,
+    invariant: u128,
+    total_protocol_value: &PreciseNumber,
+) -> Result<PreciseNumber, ZionError> {
+    let change = PreciseNumber::new(change).ok_or(ZionError::MathOverflow)?;
+    let invariant = PreciseNumber::new(invariant).ok_or(ZionError::MathOverflow)?;
+
+    total_protocol_value
+        .checked_mul(&change).ok_or(ZionError::MathOverflow)?
+        .checked_div(&invariant).ok_or(ZionError::MathOverflow)
+}
+
+///integer square root, rounded down
+fn sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    //newton's method from above converges on the floor of the root
+    let mut x = value;
+    let mut y = value / 2 + (value & 1);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+
+    x
+}
+
+///distance between two U256
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+///convert a U256 back into a token amount, failing instead of truncating
+fn u256_to_u64(value: U256) -> Result<u64, ZionError> {
+    if value > U256::from(u64::MAX) {
+        return Err(ZionError::ConversionFailed);
+    }
+
+    Ok(value.as_u64())
+}
+
+/// Program states.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Token {
+    ///Mint pubkey for the token
+    pub mint: Pubkey, //32
+    ///pubkey for the vault associated with this token
+    pub vault: Pubkey, //64
+    ///Token account where fee's are stored for this token
+    pub fee_vault: Pubkey, //96
+    ///basis point fee applied to transactions that
+    pub oracle: Pubkey //128
+}
+
+impl Sealed for Token {}
+impl Pack for Token {
+    const LEN: usize = 128;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..Self::LEN].copy_from_slice(bytemuck::bytes_of(self));
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        input
+            .get(..Self::LEN)
+            .map(bytemuck::pod_read_unaligned)
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+}
+
+impl Token {
+
+    ///validate the token accounts against the Token struct
+    pub fn validate_accounts (
+        &self,
+        mint: &Pubkey,
+        vault: &Pubkey,
+        fee_vault: &Pubkey,
+        oracle: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        if !cmp_pubkeys(&self.mint, mint) {
+            return Err(ZionError::InvalidMint.into());
+        };
+        if !cmp_pubkeys(&self.vault, vault) {
+            return Err(ZionError::InvalidVault.into());
+        };
+        if !cmp_pubkeys(&self.fee_vault, fee_vault) {
+            return Err(ZionError::InvalidFeeVault.into());
+        };
+        if !cmp_pubkeys(&self.oracle, oracle) {
+            return Err(ZionError::InvalidOracle.into());
+        };
+
+        return Ok(())
+    }
+
+    ///calculate the value of the tokens
+    fn calculate_market_value (
+        price: PreciseNumber,
+        supply: u64,
+        //what about decimals?
+    ) -> Result<PreciseNumber, ZionError> {
+        let supply = to_precise(supply)?;
+        let value = price.checked_mul(&supply).ok_or(ZionError::MathOverflow)?;
+
+        //maybe remove this, change formula so its never 0
+        //if value.less_than_or_equal(&zero) {
+        //    return PreciseNumber::new(1 as u128).expect("zero");
+        //}
+        return Ok(value)
+    }
+    
+    ///retrieve the value of the tokens
+    pub fn get_market_value (
+        &self,
+        amount: u64,
+        market_price: u64
+    ) -> Result<PreciseNumber, ZionError> {
+        let market_price = to_precise(market_price)?;
+        Token::calculate_market_value(market_price, amount)
+    }
+
+    ///get the local price of the token, the raw premium is shaped by the pool's premium params
+    pub fn get_protocol_price (
+        price: u64,
+        premium: PreciseNumber,
+        params: &PremiumParams,
+    ) -> Result<PreciseNumber, ZionError> {
+        let price = to_precise(price)?;
+        let premium = params.apply(premium)?;
+        price.checked_mul(&premium).ok_or(ZionError::MathOverflow)
+    }
+
+}
+
+///Shape of the premium the OraclePremium curve pays to pull the pool back toward balance.
+///
+///The raw premium is the ratio of the vault values, its deviation from 1 first has the dead-band
+///taken off, is then scaled by the slope and finally capped. Every field left at zero keeps the raw ratio
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct PremiumParams {
+    ///basis points of the deviation from balance applied to the price, zero is the same as 10000
+    pub slope_bps: PodU64,
+    ///largest premium above the market price in basis points, zero for no cap
+    pub max_premium_bps: PodU64,
+    ///largest discount below the market price in basis points, zero for no cap
+    pub max_discount_bps: PodU64,
+    ///deviation from balance in basis points that is ignored, zero for none
+    pub dead_band_bps: PodU64,
+}
+
+impl PremiumParams {
+    ///steepest slope a pool can be initialized with, 10x the raw ratio
+    pub const MAX_SLOPE_BPS: u64 = 10 * MAX_BPS;
+
+    ///validate the params before they are stored in the swap state
+    pub fn validate(&self) -> Result<(), ZionError> {
+        if u64::from(self.slope_bps) > Self::MAX_SLOPE_BPS {
+            return Err(ZionError::InvalidPremiumParams);
+        }
+        if u64::from(self.max_premium_bps) > Self::MAX_SLOPE_BPS {
+            return Err(ZionError::InvalidPremiumParams);
+        }
+
+        //a discount of more than 100% would be a negative price
+        if u64::from(self.max_discount_bps) > MAX_BPS || u64::from(self.dead_band_bps) > MAX_BPS {
+            return Err(ZionError::InvalidPremiumParams);
+        }
+
+        Ok(())
+    }
+
+    ///shape a raw premium, the result is never negative
+    pub fn apply(&self, premium: PreciseNumber) -> Result<PreciseNumber, ZionError> {
+        let one = to_precise(1)?;
+        let is_premium = premium.greater_than_or_equal(&one);
+        let deviation = if is_premium {
+            premium.checked_sub(&one)
+        } else {
+            one.checked_sub(&premium)
+        }.ok_or(ZionError::MathOverflow)?;
+
+        //the dead-band is taken off the deviation so the price doesn't jump at its edge
+        let dead_band = from_bps(self.dead_band_bps.into())?;
+        if deviation.less_than_or_equal(&dead_band) {
+            return Ok(one);
+        }
+        let deviation = deviation.checked_sub(&dead_band).ok_or(ZionError::MathOverflow)?;
+
+        let slope_bps = match u64::from(self.slope_bps) {
+            0 => MAX_BPS,
+            slope_bps => slope_bps,
+        };
+        let deviation = deviation
+            .checked_mul(&from_bps(slope_bps)?)
+            .ok_or(ZionError::MathOverflow)?;
+
+        let cap_bps = if is_premium {
+            u64::from(self.max_premium_bps)
+        } else {
+            u64::from(self.max_discount_bps)
+        };
+        let deviation = match cap_bps {
+            0 => deviation,
+            cap_bps => {
+                let cap = from_bps(cap_bps)?;
+                if deviation.greater_than(&cap) { cap } else { deviation }
+            },
+        };
+
+        if is_premium {
+            return one.checked_add(&deviation).ok_or(ZionError::MathOverflow);
+        }
+        if deviation.greater_than_or_equal(&one) {
+            return to_precise(0);
+        }
+        one.checked_sub(&deviation).ok_or(ZionError::MathOverflow)
+    }
+}
+
+///last oracle price the pool accepted for a token
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct AcceptedPrice {
+    ///oracle price, zero if no price was accepted yet
+    pub price: PodU64,
+    ///slot the price was accepted in
+    pub slot: PodU64,
+}
+
+impl AcceptedPrice {
+    ///true if price moved more than max_jump_bps away from the accepted price within window_slots
+    fn is_jump(&self, price: u64, slot: u64, max_jump_bps: u64, window_slots: u64) -> bool {
+        let accepted_price = u64::from(self.price);
+        if accepted_price == 0 {
+            return false;
+        }
+
+        //prices are allowed to drift, only moves within the window count
+        if window_slots != 0 && slot.saturating_sub(self.slot.into()) > window_slots {
+            return false;
+        }
+
+        let jump = (price as u128).abs_diff(accepted_price as u128);
+        jump * MAX_BPS as u128 > accepted_price as u128 * max_jump_bps as u128
+    }
+}
+
+///Result of feeding oracle prices through the [CircuitBreaker](struct.CircuitBreaker.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BreakerStatus {
+    ///prices were accepted, the pool is trading
+    Active,
+    ///a price jumped and tripped the breaker just now
+    Tripped,
+    ///the breaker was already tripped and hasn't cooled down yet
+    Paused,
+}
+
+///Pauses swaps and deposits when an oracle price jumps further than max_jump_bps from the last
+///accepted price within window_slots. The pool resumes when the admin resets the breaker or
+///cooldown_slots after it tripped
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct CircuitBreaker {
+    ///largest price move in basis points, zero disables the breaker
+    pub max_jump_bps: PodU64,
+    ///slots a move has to happen in to count as a jump, zero for any number of slots
+    pub window_slots: PodU64,
+    ///slots after tripping the pool resumes on its own, zero to wait for the admin
+    pub cooldown_slots: PodU64,
+    ///slot the breaker tripped in
+    pub tripped_slot: PodU64,
+    ///is the pool paused
+    pub is_tripped: PodBool,
+    ///last accepted price of token a
+    pub token_a_price: AcceptedPrice,
+    ///last accepted price of token b
+    pub token_b_price: AcceptedPrice,
+}
+
+impl CircuitBreaker {
+    ///check the oracle prices against the last accepted ones and accept them if they didn't jump
+    pub fn observe(&mut self, token_a_price: u64, token_b_price: u64, slot: u64) -> BreakerStatus {
+        if self.is_tripped.into() {
+            let cooldown_slots = u64::from(self.cooldown_slots);
+            let cooled_down = cooldown_slots != 0
+                && slot >= u64::from(self.tripped_slot).saturating_add(cooldown_slots);
+            if !cooled_down {
+                return BreakerStatus::Paused;
+            }
+            self.reset();
+        }
+
+        let max_jump_bps = u64::from(self.max_jump_bps);
+        let window_slots = u64::from(self.window_slots);
+        if max_jump_bps != 0
+            && (self.token_a_price.is_jump(token_a_price, slot, max_jump_bps, window_slots)
+                || self.token_b_price.is_jump(token_b_price, slot, max_jump_bps, window_slots))
+        {
+            self.is_tripped = true.into();
+            self.tripped_slot = slot.into();
+            return BreakerStatus::Tripped;
+        }
+
+        self.token_a_price = AcceptedPrice { price: token_a_price.into(), slot: slot.into() };
+        self.token_b_price = AcceptedPrice { price: token_b_price.into(), slot: slot.into() };
+        BreakerStatus::Active
+    }
+
+    ///resume trading, the next prices seen are accepted as they are
+    pub fn reset(&mut self) {
+        self.is_tripped = false.into();
+        self.tripped_slot = PodU64::from(0);
+        self.token_a_price = AcceptedPrice::default();
+        self.token_b_price = AcceptedPrice::default();
+    }
+}
+
+///oracle prices swaps are priced at
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PriceMode {
+    ///the oracle's current price
+    Spot = 0,
+    ///the lower of spot and TWAP for the source token and the higher for the destination token,
+    ///needs the price history account
+    Conservative = 1,
+    ///the EMA price of the oracle feeds the spot price is read from
+    Ema = 2,
+}
+
+impl TryFrom<u8> for PriceMode {
+    type Error = ZionError;
+
+    fn try_from(price_mode: u8) -> Result<Self, Self::Error> {
+        match price_mode {
+            0 => Ok(PriceMode::Spot),
+            1 => Ok(PriceMode::Conservative),
+            2 => Ok(PriceMode::Ema),
+            _ => Err(ZionError::InvalidPriceMode),
+        }
+    }
+}
+
+///most oracles a token can be priced from, the primary oracle included
+pub const MAX_ORACLES: usize = 5;
+
+///Additional oracles of a token and the limits its feeds have to meet. The token is priced at the
+///median of the fresh feeds among its primary and additional oracles
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct OracleConfig {
+    ///oracles used besides the token's primary oracle, only the first num_extra_oracles are set
+    pub extra_oracles: [Pubkey; MAX_ORACLES - 1],
+    ///number of additional oracles
+    pub num_extra_oracles: u8,
+    ///fresh feeds needed to price the token, zero is the same as one
+    pub quorum: u8,
+    ///largest age of a feed in seconds, zero for no limit
+    pub max_age_secs: PodU64,
+    ///largest confidence interval of a feed in basis points of its price, zero for no limit
+    pub max_confidence_bps: PodU64,
+}
+
+impl OracleConfig {
+    ///additional oracles in use
+    pub fn extra_oracles(&self) -> &[Pubkey] {
+        let count = (self.num_extra_oracles as usize).min(MAX_ORACLES - 1);
+        &self.extra_oracles[..count]
+    }
+
+    ///fresh feeds needed to price the token
+    pub fn quorum(&self) -> usize {
+        (self.quorum as usize).max(1)
+    }
+
+    fn has_valid_count(&self) -> bool {
+        (self.num_extra_oracles as usize) < MAX_ORACLES
+    }
+
+    ///validate the config against the token's primary oracle
+    pub fn validate(&self, primary_oracle: &Pubkey) -> Result<(), ZionError> {
+        if !self.has_valid_count() || self.quorum() > 1 + self.num_extra_oracles as usize {
+            return Err(ZionError::InvalidOracleConfig);
+        }
+        if u64::from(self.max_confidence_bps) > MAX_BPS {
+            return Err(ZionError::InvalidOracleConfig);
+        }
+
+        //every oracle is counted once and unused slots stay empty
+        let extra_oracles = self.extra_oracles();
+        for (i, oracle) in extra_oracles.iter().enumerate() {
+            if oracle == primary_oracle || extra_oracles[..i].contains(oracle) {
+                return Err(ZionError::InvalidOracleConfig);
+            }
+        }
+        if self.extra_oracles[extra_oracles.len()..].iter().any(|oracle| *oracle != Pubkey::default()) {
+            return Err(ZionError::InvalidOracleConfig);
+        }
+
+        Ok(())
+    }
+
+    ///price of a feed if it is positive, recent enough and its confidence interval narrow enough
+    pub fn accept(&self, price: i64, confidence: u64, publish_time: i64, now: i64) -> Option<u64> {
+        let price = u64::try_from(price).ok().filter(|price| *price > 0)?;
+
+        let max_age_secs = u64::from(self.max_age_secs);
+        if max_age_secs != 0 {
+            let age = now.saturating_sub(publish_time);
+            if age < 0 || age as u64 > max_age_secs {
+                return None;
+            }
+        }
+
+        let max_confidence_bps = u64::from(self.max_confidence_bps);
+        if max_confidence_bps != 0
+            && (confidence as u128) * (MAX_BPS as u128) > (price as u128) * (max_confidence_bps as u128) {
+            return None;
+        }
+
+        Some(price)
+    }
+
+    ///median of the accepted feed prices, fails when fewer than the quorum were accepted
+    pub fn aggregate(&self, prices: &mut [u64]) -> Result<u64, ZionError> {
+        if prices.is_empty() || prices.len() < self.quorum() {
+            return Err(ZionError::OracleQuorumNotMet);
+        }
+        prices.sort_unstable();
+
+        let mid = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            return Ok(prices[mid]);
+        }
+
+        //even number of feeds, average of the two middle prices
+        Ok(((prices[mid - 1] as u128 + prices[mid] as u128) / 2) as u64)
+    }
+}
+
+///size of the price history ring buffer
+pub const PRICE_HISTORY_OBSERVATIONS: usize = 64;
+
+///longest TWAP window, its observations are at least 23 minutes apart to cover it
+pub const MAX_TWAP_WINDOW_SECS: u64 = 86_400;
+
+///oracle prices of both tokens seen at the same time
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Observation {
+    ///oracle price of token a
+    pub token_a_price: PodU64,
+    ///oracle price of token b
+    pub token_b_price: PodU64,
+    ///slot the prices were seen in
+    pub slot: PodU64,
+    ///unix timestamp the prices were seen at
+    pub unix_timestamp: PodI64,
+}
+
+///Ring buffer of recent oracle observations the TWAP is taken from, one observation per slot at most and
+///observations spaced so the buffer always covers the TWAP window.
+///Written by swaps and deposits when the account is passed in and by the permissionless Crank instruction
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PriceHistory {
+    ///is the price history initialized
+    pub is_initialized: PodBool,
+    ///bump of the price history pda
+    pub bump: u8,
+    ///number of observations ever recorded, the next one is written at head % OBSERVATIONS
+    pub head: PodU64,
+    ///recorded observations
+    pub observations: [Observation; PRICE_HISTORY_OBSERVATIONS],
+}
+impl Sealed for PriceHistory {}
+impl Pack for PriceHistory {
+    const LEN: usize = 1 + 1 + 8 + 32 * Self::OBSERVATIONS;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..Self::LEN].copy_from_slice(bytemuck::bytes_of(self));
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        Self::load(input).copied()
+    }
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl PriceHistory {
+    ///Prefix for generating the PDA for the price history
+    pub const PREFIX: &'static str = "price_history";
+
+    ///size of the ring buffer
+    pub const OBSERVATIONS: usize = PRICE_HISTORY_OBSERVATIONS;
+
+    ///read the price history in place from the account data
+    pub fn load(input: &[u8]) -> Result<&Self, ProgramError> {
+        let price_history: &Self = input
+            .get(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        if !bool::from(price_history.is_initialized) || !price_history.is_initialized.is_valid() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(price_history)
+    }
+
+    ///mutable version of [PriceHistory::load](struct.PriceHistory.html#method.load)
+    pub fn load_mut(input: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        let price_history: &mut Self = input
+            .get_mut(..Self::LEN)
+            .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+            .ok_or(ProgramError::InvalidAccountData)?;
+        if !bool::from(price_history.is_initialized) || !price_history.is_initialized.is_valid() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(price_history)
+    }
+
+    ///address and bump of the price history pda
+    pub fn find_address() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PriceHistory::PREFIX.as_bytes()], &crate::id())
+    }
+
+    ///validate the pubkey of the price history
+    pub fn validate_price_history_key(price_history_key: &Pubkey) -> Result<(), ProgramError> {
+        let (key, _) = Self::find_address();
+        if !cmp_pubkeys(price_history_key, &key) {
+            return Err(ZionError::InvalidPriceHistory.into());
+        }
+
+        Ok(())
+    }
+
+    ///most recent observation
+    pub fn latest(&self) -> Option<&Observation> {
+        let head = u64::from(self.head) as usize;
+        if head == 0 {
+            return None;
+        }
+        self.observations.get((head - 1) % Self::OBSERVATIONS)
+    }
+
+    ///Shortest time between two observations for a TWAP window. Spaced at least this far apart the buffer spans
+    ///the whole window, filling it with observations can't push the start of the window out of the buffer
+    pub fn min_gap_secs(window_secs: u64) -> u64 {
+        let gaps = Self::OBSERVATIONS as u64 - 1;
+        let gap_secs = window_secs / gaps;
+        gap_secs + u64::from(gap_secs * gaps < window_secs)
+    }
+
+    ///record an observation, false if one was already recorded in this slot or less than min_gap_secs ago
+    pub fn record(&mut self, token_a_price: u64, token_b_price: u64, slot: u64, unix_timestamp: i64, min_gap_secs: u64) -> bool {
+        if let Some(latest) = self.latest() {
+            if slot <= u64::from(latest.slot) {
+                return false;
+            }
+            let min_gap_secs = i64::try_from(min_gap_secs).unwrap_or(i64::MAX);
+            if unix_timestamp < i64::from(latest.unix_timestamp).saturating_add(min_gap_secs) {
+                return false;
+            }
+        }
+
+        let head = u64::from(self.head);
+        self.observations[head as usize % Self::OBSERVATIONS] = Observation {
+            token_a_price: token_a_price.into(),
+            token_b_price: token_b_price.into(),
+            slot: slot.into(),
+            unix_timestamp: unix_timestamp.into(),
+        };
+        self.head = head.saturating_add(1).into();
+
+        true
+    }
+
+    ///time weighted average prices of token a and b over the last window_secs seconds,
+    ///every observation holds until the next one. None unless an observation was recorded by the start of the window
+    pub fn twap(&self, window_secs: u64, now: i64) -> Option<(u64, u64)> {
+        let latest = self.latest()?;
+        let window_start = now.saturating_sub(i64::try_from(window_secs).unwrap_or(i64::MAX));
+
+        let head = u64::from(self.head) as usize;
+        let recorded = head.min(Self::OBSERVATIONS);
+
+        let mut weighted_a: u128 = 0;
+        let mut weighted_b: u128 = 0;
+        let mut total_secs: u128 = 0;
+        let mut end = now;
+        let mut covered = false;
+        for i in 1..=recorded {
+            let observation = &self.observations[(head - i) % Self::OBSERVATIONS];
+            let timestamp = i64::from(observation.unix_timestamp);
+
+            let start = timestamp.max(window_start);
+            if end > start {
+                let secs = (end as i128 - start as i128) as u128;
+                weighted_a = weighted_a.saturating_add((u64::from(observation.token_a_price) as u128).saturating_mul(secs));
+                weighted_b = weighted_b.saturating_add((u64::from(observation.token_b_price) as u128).saturating_mul(secs));
+                total_secs += secs;
+            }
+            end = end.min(timestamp);
+
+            if timestamp <= window_start {
+                covered = true;
+                break;
+            }
+        }
+
+        //the recorded observations don't reach back to the start of the window
+        if !covered {
+            return None;
+        }
+
+        //no time has passed within the window, the latest observation is the average
+        if total_secs == 0 {
+            return Some((latest.token_a_price.into(), latest.token_b_price.into()));
+        }
+
+        Some(((weighted_a / total_secs) as u64, (weighted_b / total_secs) as u64))
+    }
+}
+
+///Direction a token amount is rounded in, always picked so the remainder stays in the pool.
+///
+///* Deposit & AdminDeposit: swap tokens minted round down
+///* Withdraw: swap tokens burned round up
+///* Swap: destination tokens paid out round down
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoundDirection {
+    ///round down, for amounts the pool pays out
+    Floor,
+    ///round up, for amounts the pool takes in
+    Ceiling,
+}
+
+impl RoundDirection {
+    ///round a PreciseNumber to a token amount
+    pub fn round(&self, value: &PreciseNumber) -> Result<u64, ZionError> {
+        let rounded = match self {
+            RoundDirection::Floor => value.floor(),
+            RoundDirection::Ceiling => value.ceiling(),
+        }.ok_or(ZionError::MathOverflow)?;
+
+        to_u64(&rounded)
+    }
+}
+
+///convert a token amount or price into a PreciseNumber
+pub fn to_precise(value: u64) -> Result<PreciseNumber, ZionError> {
+    PreciseNumber::new(value as u128).ok_or(ZionError::MathOverflow)
+}
+
+///convert basis points into a PreciseNumber fraction
+pub fn from_bps(bps: u64) -> Result<PreciseNumber, ZionError> {
+    to_precise(bps)?
+        .checked_div(&to_precise(MAX_BPS)?)
+        .ok_or(ZionError::MathOverflow)
+}
+
+///convert a PreciseNumber back into a token amount, failing instead of truncating
+pub fn to_u64(value: &PreciseNumber) -> Result<u64, ZionError> {
+    let value = value.to_imprecise().ok_or(ZionError::MathOverflow)?;
+    u64::try_from(value).map_err(|_| ZionError::ConversionFailed)
+}
+
+///compare two Pubkeys
+pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
+    sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Token;
+    use super::SwapState;
+    use super::{to_precise, BreakerStatus, CircuitBreaker, ConstantProductCurve, CurveType, InvariantCurve, OracleConfig, PremiumParams, PriceHistory, PriceMode, RoundDirection, StableSwapCurve, MAX_BPS, U256};
+    use crate::{error::ZionError, pod::PodU64};
+    use proptest::prelude::*;
+    use solana_program:: { 
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+
+    fn test_swap_state(curve_type: CurveType, amp: u64) -> SwapState {
+        SwapState {
+            version: SwapState::VERSION,
+            is_initialized: true.into(),
+            curve_type: curve_type as u8,
+            amp: PodU64::from(amp),
+            token_a: Token {
+                mint: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+                fee_vault: Pubkey::new_unique(),
+                oracle: Pubkey::new_unique(),
+            },
+            token_b: Token {
+                mint: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+                fee_vault: Pubkey::new_unique(),
+                oracle: Pubkey::new_unique(),
+            },
+            ..SwapState::default()
+        }
+    }
+
+    ///assume both tokens have the same price
+    #[test]
     fn test_calc_swap_token() {
         let token_a = Token {
             mint: Pubkey::new_unique(),
@@ -478,16 +1954,18 @@ mod tests {
         let token_b_price = 1;
 
         let swap_state = SwapState{
+            version: SwapState::VERSION,
             admin: Pubkey::new_unique(),
             bump: 0,
-            is_initialized: true,
+            is_initialized: true.into(),
             swap_authority: Pubkey::new_unique(),
             swap_authority_bump: 0,
             swap_mint:Pubkey::new_unique(),
             token_a,
             token_b,
-            program_fee: 100,
-            swap_fee: 100,
+            program_fee: PodU64::from(100),
+            swap_fee: PodU64::from(100),
+            ..SwapState::default()
         };
 
         let user_a_deposit_token_a = 10000000;
@@ -509,7 +1987,7 @@ mod tests {
             token_b_price,
             fee_vault_b,
             swap_supply
-        );
+        ).unwrap();
 
         //provides 10% of total protocol value, receives 10% of swap tokens
         assert!(user_a_swap_tokens==10000000); //aprox 5% of swap tokens 10000000/200000000
@@ -526,13 +2004,13 @@ mod tests {
             token_b_price,
             fee_vault_a,
             swap_supply
-        );
+        ).unwrap();
 
-        //token b is now in demand thus value of token b has increased
-        //user b receives more swap tokens than user a due to this
-        assert!(user_b_swap_tokens==11000000); // aprox 5.2 of swap tokens, 11000000/210000000
+        //token b is now in demand, but a deposit is never credited more than its market value
+        //user b receives the same share of swap tokens as user a
+        assert!(user_b_swap_tokens==10000000); // aprox 4.8 of swap tokens, 10000000/210000000
         
-        swap_supply += user_b_swap_tokens; //221000000
+        swap_supply += user_b_swap_tokens; //220000000
         vault_b_supply += user_b_deposit_token_b; //110000000
 
         //a bunch of swaps are dome and there are now 10000000 tokens in the fee vault
@@ -547,10 +2025,10 @@ mod tests {
             token_b_price,
             fee_vault_b,
             swap_supply
-        );
+        ).unwrap();
         
         //user c deposit of 10000000 tokens now only accounts for 3.1% of total protocol value due to the tokens in the fee vault
-        assert!(user_c_swap_tokens==6906250); //aprox  3.1% of swap tokens 6906250/221000000, 
+        assert!(user_c_swap_tokens==6875000); //aprox  3.1% of swap tokens 6875000/220000000, 
 
     }
 
@@ -575,44 +2053,992 @@ mod tests {
         let token_b_price = 1;
 
         let swap_state = SwapState{
+            version: SwapState::VERSION,
             admin: Pubkey::new_unique(),
             bump: 0,
-            is_initialized: true,
+            is_initialized: true.into(),
             swap_authority: Pubkey::new_unique(),
             swap_authority_bump: 0,
             swap_mint:Pubkey::new_unique(),
             token_a,
             token_b,
-            program_fee: 100,
-            swap_fee: 100,
+            program_fee: PodU64::from(100),
+            swap_fee: PodU64::from(100),
+            ..SwapState::default()
         };
 
         let source_tokens:u64 = 1000000;
-        let destination_tokens = SwapState::calculate_tokens_to_swap(
-            swap_state.token_a.clone(),
+        let destination_tokens = swap_state.calculate_tokens_to_swap(
             10000000,
             token_a_price,
-            swap_state.token_b.clone(),
             token_b_price,
             10000000,
             source_tokens
-        );
+        ).unwrap();
         assert!(destination_tokens==1000000);
 
-        let destination_tokens = SwapState::calculate_tokens_to_swap(
-            swap_state.token_a.clone(),
+        let destination_tokens = swap_state.calculate_tokens_to_swap(
             10000000,
             token_a_price,
-            swap_state.token_b.clone(),
             token_b_price, //this should cause the source token to be half the price, due to -50% premium
             5000000,
             1000000
-        );
+        ).unwrap();
 
         //local market price for source token is -50% so you should get 50% back in destination tokens
         assert!(destination_tokens==source_tokens/2); 
 
     }
-    
 
-}
\ No newline at end of file
+    ///results that don't fit in a u64 are an error instead of being truncated
+    #[test]
+    fn test_calc_tokens_to_swap_overflow() {
+        let swap_state = SwapState::default();
+
+        //both vaults hold the same value, every source token is worth u64::MAX destination tokens
+        let destination_tokens = swap_state.calculate_tokens_to_swap(
+            1,
+            u64::MAX,
+            1,
+            u64::MAX,
+            u64::MAX,
+        );
+        assert_eq!(destination_tokens, Err(ZionError::ConversionFailed));
+    }
+
+    ///mints round down and burns round up
+    #[test]
+    fn test_rounding_favors_pool() {
+        let swap_state = test_swap_state(CurveType::OraclePremium, 0);
+
+        //1 token is worth 0.015 swap tokens, nothing is minted for it
+        let minted = swap_state.calculate_swap_tokens(1, 100, 1, 0, 100, 1, 0, 3).unwrap();
+        assert_eq!(minted, 0);
+
+        //but withdrawing it costs a whole swap token
+        let burned = swap_state.calculate_withdraw_swap_tokens(1, 100, 1, 0, 100, 1, 0, 3).unwrap();
+        assert_eq!(burned, 1);
+
+        //can't withdraw more than the vault holds
+        assert_eq!(
+            swap_state.calculate_withdraw_swap_tokens(101, 100, 1, 0, 100, 1, 0, 3),
+            Err(ZionError::InvalidTokenAmount)
+        );
+    }
+
+    ///price of a token worth 1_000_000 at the market once the premium numerator / denominator is shaped
+    fn shaped_price(params: &PremiumParams, numerator: u64, denominator: u64) -> u64 {
+        let premium = to_precise(numerator).unwrap().checked_div(&to_precise(denominator).unwrap()).unwrap();
+        RoundDirection::Floor.round(&Token::get_protocol_price(1_000_000, premium, params).unwrap()).unwrap()
+    }
+
+    ///premium params reshape the raw vault value ratio
+    #[test]
+    fn test_premium_params() {
+        //zeroed params keep the raw ratio
+        let params = PremiumParams::default();
+        assert_eq!(shaped_price(&params, 1, 2), 500_000);
+        assert_eq!(shaped_price(&params, 2, 1), 2_000_000);
+
+        //a 10% dead-band ignores small imbalances and is taken off larger ones
+        let params = PremiumParams { dead_band_bps: PodU64::from(1_000), ..PremiumParams::default() };
+        assert_eq!(shaped_price(&params, 105, 100), 1_000_000);
+        assert_eq!(shaped_price(&params, 95, 100), 1_000_000);
+        assert_eq!(shaped_price(&params, 1, 2), 600_000);
+
+        //half the slope halves the deviation, double the slope doubles it
+        let params = PremiumParams { slope_bps: PodU64::from(5_000), ..PremiumParams::default() };
+        assert_eq!(shaped_price(&params, 1, 2), 750_000);
+        let params = PremiumParams { slope_bps: PodU64::from(20_000), ..PremiumParams::default() };
+        assert_eq!(shaped_price(&params, 3, 4), 500_000);
+        assert_eq!(shaped_price(&params, 1, 4), 0);
+
+        //premiums and discounts are capped separately
+        let params = PremiumParams {
+            max_premium_bps: PodU64::from(500),
+            max_discount_bps: PodU64::from(2_000),
+            ..PremiumParams::default()
+        };
+        assert_eq!(shaped_price(&params, 2, 1), 1_050_000);
+        assert_eq!(shaped_price(&params, 1, 2), 800_000);
+        assert_eq!(shaped_price(&params, 101, 100), 1_010_000);
+
+        //a discount can't be more than 100%
+        let params = PremiumParams { max_discount_bps: PodU64::from(10_001), ..PremiumParams::default() };
+        assert_eq!(params.validate(), Err(ZionError::InvalidPremiumParams));
+    }
+
+    ///a damped pool pushes back less on a 2:1 imbalance
+    #[test]
+    fn test_calc_tokens_to_swap_damped() {
+        let mut swap_state = test_swap_state(CurveType::OraclePremium, 0);
+        swap_state.premium.slope_bps = PodU64::from(5_000);
+
+        //source is valued at a 25% discount instead of 50%
+        let destination_tokens = swap_state.calculate_tokens_to_swap(10000000, 1, 1, 5000000, 1000000).unwrap();
+        assert_eq!(destination_tokens, 750000);
+    }
+
+    ///swaps are checked against the pool's trade limits
+    #[test]
+    fn test_validate_trade() {
+        let mut swap_state = test_swap_state(CurveType::OraclePremium, 0);
+
+        //no limits by default
+        assert_eq!(swap_state.validate_trade(1_000, 1, 1_000, 1, 1_000), Ok(()));
+
+        //at most 10% of the destination vault
+        swap_state.max_trade_bps = PodU64::from(1_000);
+        assert_eq!(swap_state.validate_trade(100, 1, 100, 1, 1_000), Ok(()));
+        assert_eq!(swap_state.validate_trade(101, 1, 101, 1, 1_000), Err(ZionError::TradeTooLarge));
+
+        //at most 5% away from the oracle price, in either direction
+        swap_state.max_trade_bps = PodU64::from(0);
+        swap_state.max_price_deviation_bps = PodU64::from(500);
+        assert_eq!(swap_state.validate_trade(100, 2, 190, 1, 1_000), Ok(()));
+        assert_eq!(swap_state.validate_trade(100, 2, 189, 1, 1_000), Err(ZionError::PriceDeviationTooHigh));
+        assert_eq!(swap_state.validate_trade(100, 2, 210, 1, 1_000), Ok(()));
+        assert_eq!(swap_state.validate_trade(100, 2, 211, 1, 1_000), Err(ZionError::PriceDeviationTooHigh));
+
+        //the 2:1 imbalance in test_calc_tokens_to_swap halves the price
+        let destination_tokens = swap_state.calculate_tokens_to_swap(10000000, 1, 1, 5000000, 1000000).unwrap();
+        assert_eq!(
+            swap_state.validate_trade(1000000, 1, destination_tokens, 1, 5000000),
+            Err(ZionError::PriceDeviationTooHigh)
+        );
+    }
+
+    ///jumping oracle prices pause the pool until it cools down or is reset
+    #[test]
+    fn test_circuit_breaker() {
+        //disabled by default, every price is accepted
+        let mut breaker = CircuitBreaker::default();
+        assert_eq!(breaker.observe(100, 100, 1), BreakerStatus::Active);
+        assert_eq!(breaker.observe(1_000, 1, 2), BreakerStatus::Active);
+
+        //10% within 100 slots, resume after 1000 slots
+        let mut breaker = CircuitBreaker {
+            max_jump_bps: PodU64::from(1_000),
+            window_slots: PodU64::from(100),
+            cooldown_slots: PodU64::from(1_000),
+            ..CircuitBreaker::default()
+        };
+        assert_eq!(breaker.observe(100, 100, 1), BreakerStatus::Active);
+        assert_eq!(breaker.observe(110, 91, 2), BreakerStatus::Active);
+
+        //the jumped price isn't accepted
+        assert_eq!(breaker.observe(110, 120, 3), BreakerStatus::Tripped);
+        assert_eq!(u64::from(breaker.token_b_price.price), 91);
+        assert_eq!(breaker.observe(110, 91, 4), BreakerStatus::Paused);
+
+        //cooled down, the current prices become the new baseline
+        assert_eq!(breaker.observe(110, 120, 1_002), BreakerStatus::Paused);
+        assert_eq!(breaker.observe(110, 120, 1_003), BreakerStatus::Active);
+        assert_eq!(u64::from(breaker.token_b_price.price), 120);
+
+        //moves slower than the window are drift, not jumps
+        assert_eq!(breaker.observe(200, 120, 1_200), BreakerStatus::Active);
+
+        //without a cool-down only a reset resumes the pool
+        breaker.cooldown_slots = PodU64::from(0);
+        assert_eq!(breaker.observe(400, 120, 1_201), BreakerStatus::Tripped);
+        assert_eq!(breaker.observe(400, 120, u64::MAX), BreakerStatus::Paused);
+        breaker.reset();
+        assert_eq!(breaker.observe(400, 120, u64::MAX), BreakerStatus::Active);
+    }
+
+    #[test]
+    fn test_price_history() {
+        let mut history = PriceHistory::default();
+        assert_eq!(history.twap(100, 0), None);
+
+        //one observation per slot
+        assert!(history.record(100, 10, 1, 0, 0));
+        assert!(!history.record(300, 30, 1, 5, 0));
+        assert!(history.record(200, 20, 2, 10, 0));
+
+        //observations closer than the min gap are skipped
+        let mut spaced = history;
+        assert!(!spaced.record(300, 30, 3, 14, 5));
+        assert!(spaced.record(300, 30, 3, 15, 5));
+
+        //every observation holds until the next one
+        assert_eq!(history.twap(20, 20), Some((150, 15)));
+        assert_eq!(history.twap(15, 20), Some((166, 16)));
+        assert_eq!(history.twap(5, 20), Some((200, 20)));
+
+        //a window longer than the history has no average
+        assert_eq!(history.twap(1_000, 20), None);
+        assert_eq!(history.twap(21, 20), None);
+
+        //no time passed since the latest observation
+        assert_eq!(history.twap(0, 10), Some((200, 20)));
+
+        //the ring buffer overwrites the oldest observation
+        let mut history = PriceHistory::default();
+        for i in 0..=PriceHistory::OBSERVATIONS as u64 {
+            assert!(history.record(i, i, i + 1, i as i64, 1));
+        }
+        assert_eq!(u64::from(history.head), PriceHistory::OBSERVATIONS as u64 + 1);
+        assert_eq!(u64::from(history.observations[0].slot), PriceHistory::OBSERVATIONS as u64 + 1);
+        assert_eq!(u64::from(history.latest().unwrap().token_a_price), PriceHistory::OBSERVATIONS as u64);
+        assert_eq!(history.twap(64, PriceHistory::OBSERVATIONS as i64 + 1).map(|(a, _)| a), Some(32));
+        assert_eq!(history.twap(65, PriceHistory::OBSERVATIONS as i64 + 1), None);
+
+        //observations at the min gap always cover the window, however often they are recorded
+        let window_secs = 600;
+        let min_gap_secs = PriceHistory::min_gap_secs(window_secs);
+        let mut history = PriceHistory::default();
+        for slot in 1..10_000u64 {
+            history.record(slot, slot, slot, slot as i64 * 2 / 5, min_gap_secs);
+            if slot as i64 * 2 / 5 >= window_secs as i64 {
+                assert!(history.twap(window_secs, slot as i64 * 2 / 5).is_some());
+            }
+        }
+        assert_eq!(PriceHistory::min_gap_secs(0), 0);
+        assert_eq!(PriceHistory::min_gap_secs(63), 1);
+        assert_eq!(PriceHistory::min_gap_secs(64), 2);
+    }
+
+    #[test]
+    fn test_oracle_config() {
+        let primary = Pubkey::new_unique();
+        let extra = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        //a single feed is enough by default
+        let mut config = OracleConfig::default();
+        assert_eq!(config.validate(&primary), Ok(()));
+        assert_eq!(config.aggregate(&mut [7]), Ok(7));
+        assert_eq!(config.aggregate(&mut []), Err(ZionError::OracleQuorumNotMet));
+
+        //median of the fresh feeds
+        config.extra_oracles[..2].copy_from_slice(&extra);
+        config.num_extra_oracles = 2;
+        config.quorum = 2;
+        assert_eq!(config.validate(&primary), Ok(()));
+        assert_eq!(config.aggregate(&mut [300, 100, 200]), Ok(200));
+        assert_eq!(config.aggregate(&mut [300, 100]), Ok(200));
+        assert_eq!(config.aggregate(&mut [u64::MAX, u64::MAX - 1]), Ok(u64::MAX - 1));
+        assert_eq!(config.aggregate(&mut [100]), Err(ZionError::OracleQuorumNotMet));
+
+        //quorum above the number of oracles, duplicate oracles and leftovers past the count are rejected
+        config.quorum = 4;
+        assert_eq!(config.validate(&primary), Err(ZionError::InvalidOracleConfig));
+        config.quorum = 2;
+        assert_eq!(config.validate(&extra[0]), Err(ZionError::InvalidOracleConfig));
+        config.extra_oracles[1] = extra[0];
+        assert_eq!(config.validate(&primary), Err(ZionError::InvalidOracleConfig));
+        config.extra_oracles[1] = extra[1];
+        config.num_extra_oracles = 1;
+        config.quorum = 1;
+        assert_eq!(config.validate(&primary), Err(ZionError::InvalidOracleConfig));
+
+        //freshness limits
+        let config = OracleConfig {
+            max_age_secs: PodU64::from(60),
+            max_confidence_bps: PodU64::from(100),
+            ..OracleConfig::default()
+        };
+        assert_eq!(config.accept(1_000, 10, 40, 100), Some(1_000));
+        assert_eq!(config.accept(1_000, 10, 39, 100), None);
+        assert_eq!(config.accept(1_000, 11, 100, 100), None);
+        assert_eq!(config.accept(0, 0, 100, 100), None);
+        assert_eq!(config.accept(-1_000, 0, 100, 100), None);
+        assert_eq!(OracleConfig::default().accept(1_000, u64::MAX, i64::MIN, i64::MAX), Some(1_000));
+    }
+
+    #[test]
+    fn test_get_swap_prices() {
+        let mut swap_state = test_swap_state(CurveType::OraclePremium, 0);
+        assert_eq!(swap_state.get_swap_prices(100, 90, 50, 60), Ok((100, 50)));
+
+        //source at the lower and destination at the higher price
+        swap_state.price_mode = PriceMode::Conservative as u8;
+        assert_eq!(swap_state.get_swap_prices(100, 90, 50, 60), Ok((90, 60)));
+        assert_eq!(swap_state.get_swap_prices(90, 100, 60, 50), Ok((90, 60)));
+
+        swap_state.price_mode = PriceMode::Ema as u8;
+        assert_eq!(swap_state.get_swap_prices(100, 90, 50, 60), Ok((90, 60)));
+
+        swap_state.price_mode = 3;
+        assert_eq!(swap_state.get_swap_prices(100, 90, 50, 60), Err(ZionError::InvalidPriceMode));
+    }
+
+    ///every curve, with an amplification coefficient for StableSwap
+    fn curve() -> impl Strategy<Value = (CurveType, u64)> {
+        prop_oneof![
+            Just((CurveType::OraclePremium, 0)),
+            Just((CurveType::ConstantProduct, 0)),
+            (StableSwapCurve::MIN_AMP..=10_000u64).prop_map(|amp| (CurveType::StableSwap, amp)),
+        ]
+    }
+
+    ///any valid premium params, only used by the OraclePremium curve
+    fn premium_params() -> impl Strategy<Value = PremiumParams> {
+        (
+            0..=PremiumParams::MAX_SLOPE_BPS,
+            0..=PremiumParams::MAX_SLOPE_BPS,
+            0..=MAX_BPS,
+            0..=MAX_BPS,
+        ).prop_map(|(slope_bps, max_premium_bps, max_discount_bps, dead_band_bps)| PremiumParams {
+            slope_bps: PodU64::from(slope_bps),
+            max_premium_bps: PodU64::from(max_premium_bps),
+            max_discount_bps: PodU64::from(max_discount_bps),
+            dead_band_bps: PodU64::from(dead_band_bps),
+        })
+    }
+
+    ///x * y = k pays out y * dx / (x + dx)
+    #[test]
+    fn test_constant_product_swap() {
+        let swap_state = test_swap_state(CurveType::ConstantProduct, 0);
+
+        //oracle prices don't move the curve
+        assert_eq!(swap_state.calculate_tokens_to_swap(1000, 1, 7, 1000, 100).unwrap(), 90);
+        assert_eq!(swap_state.calculate_tokens_to_swap(1000, 5, 1, 1000, 100).unwrap(), 90);
+
+        //the curve can't price an empty vault
+        assert_eq!(
+            swap_state.calculate_tokens_to_swap(0, 1, 1, 1000, 100),
+            Err(ZionError::EmptyVault)
+        );
+    }
+
+    ///a balanced StableSwap pool trades close to 1:1, a constant product pool doesn't
+    #[test]
+    fn test_stable_swap() {
+        let stable = test_swap_state(CurveType::StableSwap, 100);
+        let constant_product = test_swap_state(CurveType::ConstantProduct, 0);
+
+        let stable_tokens = stable.calculate_tokens_to_swap(1_000_000, 1, 1, 1_000_000, 10_000).unwrap();
+        let constant_product_tokens = constant_product.calculate_tokens_to_swap(1_000_000, 1, 1, 1_000_000, 10_000).unwrap();
+        assert!(stable_tokens < 10_000);
+        assert!(stable_tokens > 9_990);
+        assert!(stable_tokens > constant_product_tokens);
+
+        //amplification has to be in range
+        let invalid = test_swap_state(CurveType::StableSwap, 0);
+        assert_eq!(
+            invalid.calculate_tokens_to_swap(1_000_000, 1, 1, 1_000_000, 10_000),
+            Err(ZionError::InvalidCurve)
+        );
+    }
+
+    ///depositing into a balanced invariant pool is credited close to the market value
+    #[test]
+    fn test_invariant_curve_deposit() {
+        for (curve_type, amp) in [(CurveType::ConstantProduct, 0), (CurveType::StableSwap, 100)] {
+            let swap_state = test_swap_state(curve_type, amp);
+
+            //a pool worth 2_000_000 with 2_000_000 swap tokens, a 1% deposit
+            let minted = swap_state.calculate_swap_tokens(10_000, 1_000_000, 1, 0, 1_000_000, 1, 0, 2_000_000).unwrap();
+            assert!(minted <= 10_000);
+            assert!(minted > 9_900);
+
+            let burned = swap_state.calculate_withdraw_swap_tokens(10_000, 1_010_000, 1, 0, 1_000_000, 1, 0, 2_000_000 + minted).unwrap();
+            assert!(burned >= minted);
+        }
+    }
+
+    ///swap states with an unknown curve type are rejected
+    #[test]
+    fn test_invalid_curve_type() {
+        let mut swap_state = test_swap_state(CurveType::OraclePremium, 0);
+        swap_state.curve_type = 3;
+
+        let mut packed = vec![0u8; SwapState::LEN];
+        swap_state.pack_into_slice(&mut packed);
+        assert!(SwapState::unpack_from_slice(&packed).is_err());
+        assert_eq!(swap_state.curve().err(), Some(ZionError::InvalidCurve));
+    }
+
+    proptest! {
+        ///depositing tokens and withdrawing them again never burns fewer swap tokens than were minted
+        #[test]
+        fn test_deposit_withdraw_round_trip(
+            vault_a in 1..1_000_000_000_000u64,
+            vault_b in 1..1_000_000_000_000u64,
+            price_a in 1..1_000_000u64,
+            price_b in 1..1_000_000u64,
+            fee_vault_a in 0..1_000_000_000_000u64,
+            fee_vault_b in 0..1_000_000_000_000u64,
+            swap_supply in 1..1_000_000_000_000u64,
+            withdraw_bps in 1..=MAX_BPS,
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            //deposit what the withdrawn token b is worth in token a, rounded down to whole tokens
+            let withdraw = (vault_b as u128 * withdraw_bps as u128 / MAX_BPS as u128).max(1) as u64;
+            let deposit = withdraw as u128 * price_b as u128 / price_a as u128;
+            prop_assume!(deposit > 0 && deposit < 1_000_000_000_000);
+            let deposit = deposit as u64;
+
+            let minted = swap_state.calculate_swap_tokens(
+                deposit,
+                vault_a,
+                price_a,
+                fee_vault_a,
+                vault_b,
+                price_b,
+                fee_vault_b,
+                swap_supply,
+            );
+            prop_assume!(minted.is_ok());
+            let minted = minted.unwrap();
+            let swap_supply = swap_supply.checked_add(minted);
+            prop_assume!(swap_supply.is_some());
+
+            let burned = swap_state.calculate_withdraw_swap_tokens(
+                deposit,
+                vault_a + deposit,
+                price_a,
+                fee_vault_a,
+                vault_b,
+                price_b,
+                fee_vault_b,
+                swap_supply.unwrap(),
+            ).unwrap();
+
+            prop_assert!(burned >= minted, "minted {} but only burned {}", minted, burned);
+        }
+
+        ///depositing token a and withdrawing at least its worth of token b never burns fewer swap tokens than were minted
+        #[test]
+        fn test_deposit_a_withdraw_b_round_trip(
+            vault_a in 1..1_000_000_000_000u64,
+            vault_b in 1..1_000_000_000_000u64,
+            price_a in 1..1_000_000u64,
+            price_b in 1..1_000_000u64,
+            fee_vault_a in 0..1_000_000_000_000u64,
+            fee_vault_b in 0..1_000_000_000_000u64,
+            swap_supply in 1..1_000_000_000_000u64,
+            withdraw_bps in 1..=MAX_BPS,
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            //deposit what the withdrawn token b is worth in token a, rounded down to whole tokens
+            let withdraw = (vault_b as u128 * withdraw_bps as u128 / MAX_BPS as u128).max(1) as u64;
+            let deposit = withdraw as u128 * price_b as u128 / price_a as u128;
+            prop_assume!(deposit > 0 && deposit < 1_000_000_000_000);
+            let deposit = deposit as u64;
+
+            let minted = swap_state.calculate_swap_tokens(
+                deposit,
+                vault_a,
+                price_a,
+                fee_vault_a,
+                vault_b,
+                price_b,
+                fee_vault_b,
+                swap_supply,
+            );
+            prop_assume!(minted.is_ok());
+            let minted = minted.unwrap();
+            let swap_supply = swap_supply.checked_add(minted);
+            prop_assume!(swap_supply.is_some());
+
+            let burned = swap_state.calculate_withdraw_swap_tokens(
+                withdraw,
+                vault_b,
+                price_b,
+                fee_vault_b,
+                vault_a + deposit,
+                price_a,
+                fee_vault_a,
+                swap_supply.unwrap(),
+            ).unwrap();
+
+            prop_assert!(burned >= minted, "minted {} but only burned {}", minted, burned);
+        }
+
+        ///swapping tokens and swapping the output back never returns more than was swapped in
+        #[test]
+        fn test_swap_round_trip(
+            vault_a in 1..1_000_000_000_000u64,
+            vault_b in 1..1_000_000_000_000u64,
+            price_a in 1..1_000_000u64,
+            price_b in 1..1_000_000u64,
+            amount in 1..1_000_000_000_000u64,
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            let destination_tokens = swap_state.calculate_tokens_to_swap(
+                vault_a,
+                price_a,
+                price_b,
+                vault_b,
+                amount,
+            );
+            prop_assume!(destination_tokens.is_ok());
+            let destination_tokens = destination_tokens.unwrap();
+
+            //the destination vault has to be able to pay out
+            prop_assume!(destination_tokens <= vault_b);
+
+            let source_tokens = swap_state.calculate_tokens_to_swap(
+                vault_b - destination_tokens,
+                price_b,
+                price_a,
+                vault_a + amount,
+                destination_tokens,
+            ).unwrap();
+
+            prop_assert!(source_tokens <= amount, "swapped {} but got back {}", amount, source_tokens);
+        }
+
+        ///any sequence of deposits and withdrawals of either token never pays a liquidity provider out more than
+        ///they paid in at the oracle prices
+        #[test]
+        fn test_mixed_round_trip(
+            pool in test_pool(),
+            steps in prop::collection::vec(
+                (any::<bool>(), 0..1_000_000_000_000u64, 0..1_000_000_000_000u64, 0..=MAX_BPS, 1..=MAX_BPS),
+                1..20,
+            ),
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            let mut pool = pool;
+            let mut held = 0u64;
+            let mut paid_in = 0u128;
+            let mut paid_out = 0u128;
+            for (is_deposit, token_a, token_b, split_bps, size_bps) in steps {
+                if is_deposit {
+                    if let Ok(minted) = pool.deposit(&swap_state, token_a, token_b) {
+                        held += minted;
+                        paid_in += token_a as u128 * pool.price_a as u128 + token_b as u128 * pool.price_b as u128;
+                    }
+                    continue;
+                }
+
+                //ask for part of what the held swap tokens are worth, split between the tokens
+                let held_value = U256::from(pool.value()) * U256::from(held) / U256::from(pool.swap_supply);
+                let withdraw_value = (held_value * U256::from(size_bps) / U256::from(MAX_BPS)).as_u128();
+                let withdraw_a_value = withdraw_value * split_bps as u128 / MAX_BPS as u128;
+                let withdraw_a = (withdraw_a_value / pool.price_a as u128) as u64;
+                let withdraw_b = ((withdraw_value - withdraw_a_value) / pool.price_b as u128) as u64;
+                if withdraw_a > pool.vault_a || withdraw_b > pool.vault_b {
+                    continue;
+                }
+
+                //only withdrawals the held swap tokens pay for can be made
+                match pool.quote_withdraw(&swap_state, withdraw_a, withdraw_b) {
+                    Ok(burned) if burned <= held => {
+                        pool.withdraw(&swap_state, withdraw_a, withdraw_b).unwrap();
+                        held -= burned;
+                        paid_out += withdraw_a as u128 * pool.price_a as u128 + withdraw_b as u128 * pool.price_b as u128;
+                    },
+                    _ => {},
+                }
+            }
+
+            prop_assert!(paid_out <= paid_in, "paid in {} but paid out {}", paid_in, paid_out);
+        }
+    }
+
+    ///pool balances the invariant tests act on, moved the way the processor moves the vaults and the swap mint
+    #[derive(Clone, Copy, Debug)]
+    struct TestPool {
+        vault_a: u64,
+        fee_vault_a: u64,
+        vault_b: u64,
+        fee_vault_b: u64,
+        swap_supply: u64,
+        price_a: u64,
+        price_b: u64,
+    }
+
+    ///what a user does to the pool, withdraws are in basis points of the vaults so they can be paid out
+    #[derive(Clone, Copy, Debug)]
+    enum Action {
+        Swap { a_to_b: bool, amount: u64 },
+        Deposit { token_a: u64, token_b: u64 },
+        Withdraw { token_a_bps: u64, token_b_bps: u64 },
+        Prices { price_a: u64, price_b: u64 },
+    }
+
+    impl TestPool {
+        ///value of every token the pool holds at the oracle prices
+        fn value(&self) -> u128 {
+            (self.vault_a as u128 + self.fee_vault_a as u128) * self.price_a as u128
+                + (self.vault_b as u128 + self.fee_vault_b as u128) * self.price_b as u128
+        }
+
+        ///value per swap token is lower than before's, compared without dividing
+        fn value_per_swap_token_below(&self, before: &TestPool) -> bool {
+            U256::from(self.value()) * U256::from(before.swap_supply)
+                < U256::from(before.value()) * U256::from(self.swap_supply)
+        }
+
+        ///invariant of the vault balances, only for the invariant curves
+        fn invariant(&self, swap_state: &SwapState) -> u128 {
+            match CurveType::try_from(swap_state.curve_type).unwrap() {
+                CurveType::ConstantProduct => ConstantProductCurve.invariant(self.vault_a, self.vault_b).unwrap(),
+                CurveType::StableSwap => StableSwapCurve { amp: u64::from(swap_state.amp) }
+                    .invariant(self.vault_a, self.vault_b)
+                    .unwrap(),
+                CurveType::OraclePremium => unreachable!(),
+            }
+        }
+
+        fn swap(&mut self, swap_state: &SwapState, a_to_b: bool, amount: u64) -> Result<u64, ZionError> {
+            let (source_supply, source_price, destination_supply, destination_price) = if a_to_b {
+                (self.vault_a, self.price_a, self.vault_b, self.price_b)
+            } else {
+                (self.vault_b, self.price_b, self.vault_a, self.price_a)
+            };
+
+            let quote = swap_state.quote_swap(
+                source_price,
+                source_price,
+                destination_price,
+                destination_price,
+                source_supply,
+                destination_supply,
+                amount,
+            )?;
+            let new_source_supply = source_supply.checked_add(amount).ok_or(ZionError::MathOverflow)?;
+            let new_destination_supply = destination_supply
+                .checked_sub(quote.amount_out)
+                .ok_or(ZionError::InvalidTokenAmount)?;
+
+            if a_to_b {
+                self.vault_a = new_source_supply;
+                self.vault_b = new_destination_supply;
+            } else {
+                self.vault_b = new_source_supply;
+                self.vault_a = new_destination_supply;
+            }
+            Ok(quote.amount_out)
+        }
+
+        fn deposit(&mut self, swap_state: &SwapState, token_a: u64, token_b: u64) -> Result<u64, ZionError> {
+            let quote = self.quote_deposit(swap_state, token_a, token_b)?;
+            self.vault_a = self.vault_a.checked_add(token_a).ok_or(ZionError::MathOverflow)?;
+            self.vault_b = self.vault_b.checked_add(token_b).ok_or(ZionError::MathOverflow)?;
+            self.swap_supply = self.swap_supply.checked_add(quote).ok_or(ZionError::MathOverflow)?;
+            Ok(quote)
+        }
+
+        fn quote_deposit(&self, swap_state: &SwapState, token_a: u64, token_b: u64) -> Result<u64, ZionError> {
+            self.vault_a.checked_add(token_a).ok_or(ZionError::MathOverflow)?;
+            self.vault_b.checked_add(token_b).ok_or(ZionError::MathOverflow)?;
+            let quote = swap_state.quote_deposit(
+                token_a,
+                token_b,
+                self.vault_a,
+                self.fee_vault_a,
+                self.vault_b,
+                self.fee_vault_b,
+                self.swap_supply,
+                self.price_a,
+                self.price_b,
+            )?;
+            self.swap_supply.checked_add(quote.swap_tokens).ok_or(ZionError::MathOverflow)?;
+            Ok(quote.swap_tokens)
+        }
+
+        fn quote_withdraw(&self, swap_state: &SwapState, token_a: u64, token_b: u64) -> Result<u64, ZionError> {
+            let quote = swap_state.quote_withdraw(
+                token_a,
+                token_b,
+                self.vault_a,
+                self.fee_vault_a,
+                self.vault_b,
+                self.fee_vault_b,
+                self.swap_supply,
+                self.price_a,
+                self.price_b,
+            )?;
+
+            //somebody has to be left holding the pool
+            if quote.swap_tokens >= self.swap_supply {
+                return Err(ZionError::InsufficientSwapTokens);
+            }
+            Ok(quote.swap_tokens)
+        }
+
+        fn withdraw(&mut self, swap_state: &SwapState, token_a: u64, token_b: u64) -> Result<u64, ZionError> {
+            let quote = self.quote_withdraw(swap_state, token_a, token_b)?;
+            self.vault_a -= token_a;
+            self.vault_b -= token_b;
+            self.swap_supply -= quote;
+            Ok(quote)
+        }
+
+        ///apply an action, the pool is left as it was when the program would reject it
+        fn apply(&mut self, swap_state: &SwapState, action: Action) -> Result<(), ZionError> {
+            match action {
+                Action::Swap { a_to_b, amount } => {
+                    self.swap(swap_state, a_to_b, amount)?;
+                },
+                Action::Deposit { token_a, token_b } => {
+                    self.deposit(swap_state, token_a, token_b)?;
+                },
+                Action::Withdraw { token_a_bps, token_b_bps } => {
+                    let token_a = (self.vault_a as u128 * token_a_bps as u128 / MAX_BPS as u128) as u64;
+                    let token_b = (self.vault_b as u128 * token_b_bps as u128 / MAX_BPS as u128) as u64;
+                    self.withdraw(swap_state, token_a, token_b)?;
+                },
+                Action::Prices { price_a, price_b } => {
+                    self.price_a = price_a;
+                    self.price_b = price_b;
+                },
+            }
+
+            Ok(())
+        }
+    }
+
+    ///any pool with tokens in both vaults and swap tokens outstanding
+    fn test_pool() -> impl Strategy<Value = TestPool> {
+        (
+            1..1_000_000_000_000u64,
+            0..1_000_000_000_000u64,
+            1..1_000_000_000_000u64,
+            0..1_000_000_000_000u64,
+            1..1_000_000_000_000u64,
+            1..1_000_000u64,
+            1..1_000_000u64,
+        ).prop_map(|(vault_a, fee_vault_a, vault_b, fee_vault_b, swap_supply, price_a, price_b)| TestPool {
+            vault_a,
+            fee_vault_a,
+            vault_b,
+            fee_vault_b,
+            swap_supply,
+            price_a,
+            price_b,
+        })
+    }
+
+    ///any action, including moving the oracle prices
+    fn action() -> impl Strategy<Value = Action> {
+        prop_oneof![
+            (any::<bool>(), 1..1_000_000_000_000u64).prop_map(|(a_to_b, amount)| Action::Swap { a_to_b, amount }),
+            (0..1_000_000_000_000u64, 0..1_000_000_000_000u64)
+                .prop_map(|(token_a, token_b)| Action::Deposit { token_a, token_b }),
+            (0..MAX_BPS, 0..MAX_BPS)
+                .prop_map(|(token_a_bps, token_b_bps)| Action::Withdraw { token_a_bps, token_b_bps }),
+            (1..1_000_000u64, 1..1_000_000u64).prop_map(|(price_a, price_b)| Action::Prices { price_a, price_b }),
+        ]
+    }
+
+    proptest! {
+        ///deposits and withdrawals never lower the value per swap token of the LPs already in the pool,
+        ///whatever the curve and whatever state earlier actions left the pool in
+        #[test]
+        fn test_liquidity_never_dilutes(
+            pool in test_pool(),
+            actions in prop::collection::vec(action(), 1..20),
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            let mut pool = pool;
+            for action in actions {
+                let before = pool;
+                if pool.apply(&swap_state, action).is_err() {
+                    continue;
+                }
+
+                if matches!(action, Action::Deposit { .. } | Action::Withdraw { .. }) {
+                    prop_assert!(
+                        !pool.value_per_swap_token_below(&before),
+                        "{:?} moved the value per swap token from {}/{} to {}/{}",
+                        action, before.value(), before.swap_supply, pool.value(), pool.swap_supply
+                    );
+                }
+            }
+        }
+
+        ///depositing and withdrawing straight away, in any mix of the two tokens, never pays out more than was
+        ///paid in at the oracle prices
+        #[test]
+        fn test_deposit_then_withdraw_never_profits(
+            pool in test_pool(),
+            actions in prop::collection::vec(action(), 0..10),
+            deposit_a in 0..1_000_000_000_000u64,
+            deposit_b in 0..1_000_000_000_000u64,
+            withdraw_a_bps in 0..=MAX_BPS,
+            excess_bps in 0..500u64,
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            let mut pool = pool;
+            //whatever state the earlier actions leave the pool in
+            for action in actions {
+                let _ = pool.apply(&swap_state, action);
+            }
+
+            let minted = pool.deposit(&swap_state, deposit_a, deposit_b);
+            prop_assume!(minted.is_ok());
+            let minted = minted.unwrap();
+            let deposit_value = deposit_a as u128 * pool.price_a as u128 + deposit_b as u128 * pool.price_b as u128;
+
+            //ask for the value deposited, or a little more, split between the tokens
+            let withdraw_value = deposit_value * (MAX_BPS + excess_bps) as u128 / MAX_BPS as u128;
+            let withdraw_a_value = withdraw_value * withdraw_a_bps as u128 / MAX_BPS as u128;
+            let withdraw_a = u64::try_from(withdraw_a_value / pool.price_a as u128).unwrap_or(u64::MAX);
+            let withdraw_b = u64::try_from((withdraw_value - withdraw_a_value) / pool.price_b as u128).unwrap_or(u64::MAX);
+
+            //only withdrawals the minted swap tokens pay for can be made
+            if let Ok(burned) = pool.quote_withdraw(&swap_state, withdraw_a, withdraw_b) {
+                if burned <= minted {
+                    let withdrawn_value = withdraw_a as u128 * pool.price_a as u128 + withdraw_b as u128 * pool.price_b as u128;
+                    prop_assert!(
+                        withdrawn_value <= deposit_value,
+                        "deposited {} for {} swap tokens and withdrew {} for {}",
+                        deposit_value, minted, withdrawn_value, burned
+                    );
+                }
+            }
+        }
+
+        ///swaps priced off the oracle never lower the pool's value at the oracle prices, swaps don't charge
+        ///fees yet so none of it may go. The invariant curves price swaps off the vault balances and pay
+        ///above the oracle price when the pool is off it, that's what arbitrage brings back, their swaps
+        ///never lower the invariant instead
+        #[test]
+        fn test_swaps_keep_pool_value(
+            pool in test_pool(),
+            actions in prop::collection::vec(action(), 1..20),
+            (curve_type, amp) in curve(),
+            premium in premium_params(),
+        ) {
+            let mut swap_state = test_swap_state(curve_type, amp);
+            swap_state.premium = premium;
+
+            let mut pool = pool;
+            for action in actions {
+                let before = pool;
+                if pool.apply(&swap_state, action).is_err() {
+                    continue;
+                }
+                if !matches!(action, Action::Swap { .. }) {
+                    continue;
+                }
+
+                if curve_type == CurveType::OraclePremium {
+                    prop_assert!(
+                        pool.value() >= before.value(),
+                        "{:?} moved the pool value from {} to {}",
+                        action, before.value(), pool.value()
+                    );
+                } else {
+                    //D is only known to within 1
+                    prop_assert!(
+                        pool.invariant(&swap_state) + 1 >= before.invariant(&swap_state),
+                        "{:?} moved the invariant from {} to {}",
+                        action, before.invariant(&swap_state), pool.invariant(&swap_state)
+                    );
+                }
+            }
+        }
+
+        ///a constant product pool holding the same value of both tokens at the oracle prices never pays out
+        ///more than a swap is worth at them
+        #[test]
+        fn test_balanced_constant_product_swaps_keep_pool_value(
+            vault_value in 1_000_000..1_000_000_000_000_000u64,
+            fee_vault_a in 0..1_000_000_000_000u64,
+            fee_vault_b in 0..1_000_000_000_000u64,
+            price_a in 1..1_000_000u64,
+            price_b in 1..1_000_000u64,
+            a_to_b in any::<bool>(),
+            amount in 1..1_000_000_000_000u64,
+        ) {
+            let swap_state = test_swap_state(CurveType::ConstantProduct, 0);
+            let mut pool = TestPool {
+                vault_a: vault_value / price_a,
+                fee_vault_a,
+                vault_b: vault_value / price_b,
+                fee_vault_b,
+                swap_supply: 1_000_000,
+                price_a,
+                price_b,
+            };
+
+            let before = pool;
+            prop_assume!(pool.swap(&swap_state, a_to_b, amount).is_ok());
+            prop_assert!(
+                pool.value() >= before.value(),
+                "swapping {} moved the pool value from {} to {}",
+                amount, before.value(), pool.value()
+            );
+        }
+    }
+
+    ///v1 accounts are rejected until they are migrated to the versioned layout
+    #[test]
+    fn test_unpack_v1_and_migrate() {
+        let swap_state = SwapState{
+            version: SwapState::VERSION,
+            admin: Pubkey::new_unique(),
+            bump: 254,
+            is_initialized: true.into(),
+            swap_authority: Pubkey::new_unique(),
+            swap_authority_bump: 253,
+            swap_mint:Pubkey::new_unique(),
+            token_a: Token {
+                mint: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+                fee_vault: Pubkey::new_unique(),
+                oracle: Pubkey::new_unique(),
+            },
+            token_b: Token {
+                mint: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+                fee_vault: Pubkey::new_unique(),
+                oracle: Pubkey::new_unique(),
+            },
+            program_fee: PodU64::from(100),
+            swap_fee: PodU64::from(30),
+            ..SwapState::default()
+        };
+
+        let mut packed = vec![0u8; SwapState::LEN];
+        swap_state.pack_into_slice(&mut packed);
+        assert_eq!(SwapState::unpack_from_slice(&packed).unwrap(), swap_state);
+
+        //the v1 layout is the versioned layout without the version byte and the reserved space
+        let v1_data = packed[1..1 + SwapState::LEN_V1].to_vec();
+        assert_eq!(
+            SwapState::unpack_from_slice(&v1_data),
+            Err(ZionError::StateMigrationRequired.into())
+        );
+
+        let mut migrated = SwapState::unpack_v1(&v1_data).unwrap();
+        assert_eq!(migrated.version, SwapState::VERSION_V1);
+
+        migrated.version = SwapState::VERSION;
+        let mut migrated_data = vec![0u8; SwapState::LEN];
+        migrated.pack_into_slice(&mut migrated_data);
+        assert_eq!(migrated_data, packed);
+        assert_eq!(SwapState::unpack_from_slice(&migrated_data).unwrap(), swap_state);
+
+        //unknown versions are rejected
+        migrated_data[0] = SwapState::VERSION + 1;
+        assert_eq!(
+            SwapState::unpack_from_slice(&migrated_data),
+            Err(ZionError::InvalidStateVersion.into())
+        );
+    }
+
+}
@@ -0,0 +1,170 @@
+#![cfg(feature = "test-bpf")]
+
+use {
+    bank_of_zion::{
+        error::ZionError,
+        instructions::migrate_state,
+        processor::Processor,
+        pod::PodU64,
+        state::{SwapState, Token},
+    },
+    solana_program::{
+        instruction::InstructionError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+    solana_program_test::{processor, ProgramTest, ProgramTestBanksClientExt},
+    solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+};
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "bank_of_zion",
+        bank_of_zion::id(),
+        processor!(Processor::process),
+    )
+}
+
+fn swap_state_key() -> Pubkey {
+    Pubkey::find_program_address(&[SwapState::PREFIX.as_bytes()], &bank_of_zion::id()).0
+}
+
+fn swap_state(admin: &Pubkey) -> SwapState {
+    SwapState {
+        version: SwapState::VERSION,
+        admin: *admin,
+        bump: 255,
+        is_initialized: true.into(),
+        swap_authority: Pubkey::new_unique(),
+        swap_authority_bump: 254,
+        swap_mint: Pubkey::new_unique(),
+        token_a: Token {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+        },
+        token_b: Token {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+        },
+        program_fee: PodU64::from(100),
+        swap_fee: PodU64::from(30),
+        ..SwapState::default()
+    }
+}
+
+///the v1 layout is the current layout without the version byte and the reserved space
+fn v1_account(swap_state: &SwapState) -> Account {
+    let mut packed = vec![0u8; SwapState::LEN];
+    swap_state.pack_into_slice(&mut packed);
+
+    Account {
+        lamports: Rent::default().minimum_balance(SwapState::LEN_V1),
+        data: packed[1..1 + SwapState::LEN_V1].to_vec(),
+        owner: bank_of_zion::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_migrate_v1_state() {
+    let admin = Keypair::new();
+    let expected = swap_state(&admin.pubkey());
+
+    let mut program_test = program_test();
+    program_test.add_account(swap_state_key(), v1_account(&expected));
+    program_test.add_account(
+        admin.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_state(&admin.pubkey(), &swap_state_key())],
+        Some(&payer.pubkey()),
+        &[&payer, &admin],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = banks_client
+        .get_account(swap_state_key())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), SwapState::LEN);
+    assert!(account.lamports >= Rent::default().minimum_balance(SwapState::LEN));
+    assert_eq!(SwapState::unpack_from_slice(&account.data).unwrap(), expected);
+
+    //a migrated account can't be migrated again
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_state(&admin.pubkey(), &swap_state_key())],
+        Some(&payer.pubkey()),
+        &[&payer, &admin],
+        banks_client
+            .get_new_latest_blockhash(&recent_blockhash)
+            .await
+            .unwrap(),
+    );
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ZionError::InvalidStateVersion as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_v1_state_must_be_admin() {
+    let admin = Keypair::new();
+    let attacker = Keypair::new();
+
+    let mut program_test = program_test();
+    program_test.add_account(swap_state_key(), v1_account(&swap_state(&admin.pubkey())));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_state(&attacker.pubkey(), &swap_state_key())],
+        Some(&payer.pubkey()),
+        &[&payer, &attacker],
+        recent_blockhash,
+    );
+    let err = banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ZionError::MustBeAdmin as u32)
+        )
+    );
+
+    //the account is left untouched
+    let account = banks_client
+        .get_account(swap_state_key())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), SwapState::LEN_V1);
+}
@@ -0,0 +1,185 @@
+use {
+    crate::arbitrage::find_arbitrage,
+    bank_of_zion::state::MAX_BPS,
+    bank_of_zion_client::{simulator::{PoolSimulator, PoolSnapshot}, SwapDirection},
+    rand::Rng,
+    rand_chacha::ChaCha8Rng,
+};
+
+///Tokens an agent holds outside the pool
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Wallet {
+    ///tokens of token a
+    pub token_a: u64,
+    ///tokens of token b
+    pub token_b: u64,
+    ///swap tokens, the agent's share of the pool
+    pub swap_tokens: u64,
+}
+
+impl Wallet {
+    ///value at the snapshot's oracle prices, swap tokens are worth their share of the pool
+    pub fn value(&self, snapshot: &PoolSnapshot) -> u128 {
+        let pool_share = if snapshot.swap_supply == 0 {
+            0
+        } else {
+            snapshot.value() * self.swap_tokens as u128 / snapshot.swap_supply as u128
+        };
+        self.token_a as u128 * snapshot.token_a_price as u128
+            + self.token_b as u128 * snapshot.token_b_price as u128
+            + pool_share
+    }
+
+    ///balance of the token a swap in direction pays with
+    pub fn source_balance(&self, direction: SwapDirection) -> u64 {
+        match direction {
+            SwapDirection::AToB => self.token_a,
+            SwapDirection::BToA => self.token_b,
+        }
+    }
+}
+
+///What an agent does to the pool in a step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    ///swap amount source tokens
+    Swap {
+        ///direction of the swap
+        direction: SwapDirection,
+        ///source tokens paid in
+        amount: u64,
+    },
+    ///deposit tokens for swap tokens
+    Deposit {
+        ///tokens of token a
+        token_a: u64,
+        ///tokens of token b
+        token_b: u64,
+    },
+    ///withdraw tokens for swap tokens
+    Withdraw {
+        ///tokens of token a
+        token_a: u64,
+        ///tokens of token b
+        token_b: u64,
+    },
+}
+
+///Participant of a scenario, decides a move a step from the pool and its wallet.
+///Every random choice has to come from rng so a seed replays the same scenario
+pub trait Agent {
+    ///name the agent is reported under
+    fn name(&self) -> &str;
+
+    ///move for this step, None to sit it out
+    fn decide(&mut self, simulator: &PoolSimulator, wallet: &Wallet, rng: &mut ChaCha8Rng) -> Option<Move>;
+}
+
+///Takes the most profitable swap at the oracle prices, as much of it as its wallet pays for
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arbitrageur {
+    ///name in the report
+    pub name: String,
+}
+
+impl Agent for Arbitrageur {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decide(&mut self, simulator: &PoolSimulator, wallet: &Wallet, _: &mut ChaCha8Rng) -> Option<Move> {
+        let arbitrage = find_arbitrage(simulator)?;
+        let amount = arbitrage.amount.min(wallet.source_balance(arbitrage.direction));
+        if amount == 0 {
+            return None;
+        }
+
+        Some(Move::Swap {
+            direction: arbitrage.direction,
+            amount,
+        })
+    }
+}
+
+///Swaps a random share of its balance in a random direction, whatever the price
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomTrader {
+    ///name in the report
+    pub name: String,
+    ///chance to trade in a step in basis points
+    pub activity_bps: u64,
+    ///largest trade in basis points of its source balance
+    pub max_trade_bps: u64,
+}
+
+impl Agent for RandomTrader {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decide(&mut self, _: &PoolSimulator, wallet: &Wallet, rng: &mut ChaCha8Rng) -> Option<Move> {
+        if rng.gen_range(0..MAX_BPS) >= self.activity_bps {
+            return None;
+        }
+
+        let direction = if rng.gen_bool(0.5) { SwapDirection::AToB } else { SwapDirection::BToA };
+        let max_amount = (wallet.source_balance(direction) as u128 * self.max_trade_bps as u128 / MAX_BPS as u128) as u64;
+        if max_amount == 0 {
+            return None;
+        }
+
+        Some(Move::Swap {
+            direction,
+            amount: rng.gen_range(1..=max_amount),
+        })
+    }
+}
+
+///Deposits a random share of its tokens or withdraws a random share of its position
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiquidityProvider {
+    ///name in the report
+    pub name: String,
+    ///chance to act in a step in basis points
+    pub activity_bps: u64,
+    ///largest deposit in basis points of its balances and largest withdraw in basis points of its position
+    pub max_share_bps: u64,
+}
+
+impl Agent for LiquidityProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decide(&mut self, simulator: &PoolSimulator, wallet: &Wallet, rng: &mut ChaCha8Rng) -> Option<Move> {
+        if rng.gen_range(0..MAX_BPS) >= self.activity_bps || self.max_share_bps == 0 {
+            return None;
+        }
+        let share_bps = rng.gen_range(1..=self.max_share_bps) as u128;
+
+        if rng.gen_bool(0.5) {
+            let token_a = (wallet.token_a as u128 * share_bps / MAX_BPS as u128) as u64;
+            let token_b = (wallet.token_b as u128 * share_bps / MAX_BPS as u128) as u64;
+            if token_a == 0 && token_b == 0 {
+                return None;
+            }
+            return Some(Move::Deposit { token_a, token_b });
+        }
+
+        //both tokens in proportion to the vaults, worth share_bps of its position
+        let snapshot = &simulator.snapshot;
+        if snapshot.swap_supply == 0 {
+            return None;
+        }
+        let position = wallet.swap_tokens as u128 * share_bps / MAX_BPS as u128;
+        let token_a = (snapshot.token_a_vault as u128 * position / snapshot.swap_supply as u128) as u64;
+        let token_b = (snapshot.token_b_vault as u128 * position / snapshot.swap_supply as u128) as u64;
+
+        //the program only withdraws for less than the whole balance
+        let quote = simulator.quote_withdraw(token_a, token_b).ok()?;
+        if (token_a == 0 && token_b == 0) || quote.swap_tokens >= wallet.swap_tokens {
+            return None;
+        }
+        Some(Move::Withdraw { token_a, token_b })
+    }
+}
@@ -0,0 +1,82 @@
+use bank_of_zion_client::{simulator::PoolSimulator, SwapDirection};
+
+///Swap that takes value out of the pool at the oracle prices
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arbitrage {
+    ///direction of the swap
+    pub direction: SwapDirection,
+    ///source tokens paid in
+    pub amount: u64,
+    ///value paid out less the value paid in, both at the oracle prices
+    pub profit: i128,
+}
+
+///Value a swap of amount source tokens pays out less the value paid in at the oracle prices,
+///None when the pool refuses the swap
+pub fn swap_profit(simulator: &PoolSimulator, direction: SwapDirection, amount: u64) -> Option<i128> {
+    let simulation = simulator.quote_swap(direction, amount).ok()?;
+    let snapshot = &simulator.snapshot;
+    let (source_price, destination_price) = match direction {
+        SwapDirection::AToB => (snapshot.token_a_price, snapshot.token_b_price),
+        SwapDirection::BToA => (snapshot.token_b_price, snapshot.token_a_price),
+    };
+
+    Some(simulation.quote.amount_out as i128 * destination_price as i128 - amount as i128 * source_price as i128)
+}
+
+///Most profitable swap in either direction, None when every swap the pool accepts loses value
+pub fn find_arbitrage(simulator: &PoolSimulator) -> Option<Arbitrage> {
+    [SwapDirection::AToB, SwapDirection::BToA]
+        .into_iter()
+        .filter_map(|direction| best_swap(simulator, direction))
+        .max_by_key(|arbitrage| arbitrage.profit)
+}
+
+///Most profitable amount in one direction
+fn best_swap(simulator: &PoolSimulator, direction: SwapDirection) -> Option<Arbitrage> {
+    let profit = |amount: u64| swap_profit(simulator, direction, amount).unwrap_or(i128::MIN);
+
+    //profit rises then falls with the amount, bracket the peak by doubling
+    let mut best_amount = 0;
+    let mut best_profit = i128::MIN;
+    let mut amount = 1u64;
+    for _ in 0..u64::BITS {
+        let amount_profit = profit(amount);
+        if amount_profit > best_profit {
+            best_amount = amount;
+            best_profit = amount_profit;
+        }
+        amount = amount.saturating_mul(2);
+    }
+    if best_profit == i128::MIN {
+        return None;
+    }
+
+    //then narrow it down between the neighbouring doublings
+    let mut low = best_amount / 2;
+    let mut high = best_amount.saturating_mul(2);
+    while high - low > 2 {
+        let third = (high - low) / 3;
+        if profit(low + third) < profit(high - third) {
+            low += third;
+        } else {
+            high -= third;
+        }
+    }
+    for amount in low..=high {
+        let amount_profit = profit(amount);
+        if amount_profit > best_profit {
+            best_amount = amount;
+            best_profit = amount_profit;
+        }
+    }
+
+    if best_profit <= 0 {
+        return None;
+    }
+    Some(Arbitrage {
+        direction,
+        amount: best_amount,
+        profit: best_profit,
+    })
+}
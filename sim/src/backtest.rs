@@ -0,0 +1,242 @@
+use {
+    crate::{
+        arbitrage::find_arbitrage,
+        series::{Action, ActionKind, PricePoint},
+    },
+    bank_of_zion::{error::ZionError, state::MAX_BPS},
+    bank_of_zion_client::{
+        simulator::{PoolSimulator, PoolSnapshot, SwapSimulation},
+        SwapDirection,
+    },
+    serde::Serialize,
+};
+
+///State of the pool after a price point and the actions that ran at it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Row {
+    ///timestamp of the price point
+    pub timestamp: i64,
+    ///oracle price of token a
+    pub token_a_price: u64,
+    ///oracle price of token b
+    pub token_b_price: u64,
+    ///balance of the token a vault
+    pub token_a_vault: u64,
+    ///balance of the token b vault
+    pub token_b_vault: u64,
+    ///supply of the swap mint
+    pub swap_supply: u64,
+    ///value of the vaults and fee vaults at the oracle prices
+    pub pool_value: u128,
+    ///pool value per swap token
+    pub value_per_lp_token: f64,
+    ///value of the starting swap supply's share of the pool
+    pub lp_value: u128,
+    ///value of the starting balances had they been held outside the pool
+    pub hold_value: u128,
+    ///lp value against hold value in basis points, negative for a loss
+    pub impermanent_loss_bps: f64,
+    ///value arbitrage swaps took out of the pool so far
+    pub arbitrage_profit: i128,
+    ///program and swap fees charged so far, at the source oracle price
+    pub fee_income: u128,
+    ///actions the pool refused so far
+    pub rejected_actions: u64,
+}
+
+///Replays oracle prices and user actions against a pool, keeping the totals the rows report
+#[derive(Clone, Debug)]
+pub struct Backtest {
+    ///pool the actions run against
+    pub simulator: PoolSimulator,
+    ///balances the pool started with, held value is measured against them
+    pub start: PoolSnapshot,
+    ///take the most profitable arbitrage at every price point before the actions run
+    pub arbitrage_every_price: bool,
+    ///value arbitrage swaps took out of the pool so far
+    pub arbitrage_profit: i128,
+    ///program and swap fees charged so far, at the source oracle price
+    pub fee_income: u128,
+    ///actions the pool refused so far, with the time and the reason
+    pub rejected: Vec<(Action, ZionError)>,
+}
+
+impl Backtest {
+    ///Backtest starting from the simulator's balances
+    pub fn new(simulator: PoolSimulator, arbitrage_every_price: bool) -> Self {
+        Self {
+            start: simulator.snapshot,
+            simulator,
+            arbitrage_every_price,
+            arbitrage_profit: 0,
+            fee_income: 0,
+            rejected: Vec::new(),
+        }
+    }
+
+    ///Run the actions at the last price point at or before them and return a row per price point,
+    ///actions before the first price point run at it
+    pub fn run(&mut self, prices: &[PricePoint], actions: &[Action]) -> Vec<Row> {
+        let mut rows = Vec::with_capacity(prices.len());
+        let mut actions = actions.iter().peekable();
+
+        for (i, price) in prices.iter().enumerate() {
+            self.simulator.set_prices(price.token_a_price, price.token_b_price);
+            if self.arbitrage_every_price {
+                self.arbitrage();
+            }
+
+            let next_timestamp = prices.get(i + 1).map(|next| next.timestamp);
+            while let Some(action) = actions.next_if(|action| !matches!(next_timestamp, Some(next) if action.timestamp >= next)) {
+                if let Err(err) = self.apply(action) {
+                    self.rejected.push((*action, err));
+                }
+            }
+
+            rows.push(self.row(price.timestamp));
+        }
+
+        rows
+    }
+
+    ///Run one action at the current prices
+    pub fn apply(&mut self, action: &Action) -> Result<(), ZionError> {
+        match action.action {
+            ActionKind::SwapAToB => self.swap(SwapDirection::AToB, action.amount_a).map(|_| ()),
+            ActionKind::SwapBToA => self.swap(SwapDirection::BToA, action.amount_b).map(|_| ()),
+            ActionKind::Deposit => self.simulator.deposit(action.amount_a, action.amount_b).map(|_| ()),
+            ActionKind::Withdraw => self.simulator.withdraw(action.amount_a, action.amount_b).map(|_| ()),
+            ActionKind::Arbitrage => {
+                self.arbitrage();
+                Ok(())
+            },
+        }
+    }
+
+    ///Take the most profitable arbitrage, if there is one
+    pub fn arbitrage(&mut self) {
+        if let Some(arbitrage) = find_arbitrage(&self.simulator) {
+            if self.swap(arbitrage.direction, arbitrage.amount).is_ok() {
+                self.arbitrage_profit += arbitrage.profit;
+            }
+        }
+    }
+
+    ///Swap and count the fees it charged
+    fn swap(&mut self, direction: SwapDirection, amount: u64) -> Result<SwapSimulation, ZionError> {
+        let simulation = self.simulator.swap(direction, amount)?;
+        let quote = &simulation.quote;
+        self.fee_income += (quote.program_fee as u128 + quote.swap_fee as u128) * quote.source_price as u128;
+        Ok(simulation)
+    }
+
+    ///Row for the pool as it is now
+    pub fn row(&self, timestamp: i64) -> Row {
+        let snapshot = &self.simulator.snapshot;
+        let pool_value = snapshot.value();
+
+        //the starting balances valued at today's prices
+        let hold_value = PoolSnapshot {
+            token_a_price: snapshot.token_a_price,
+            token_b_price: snapshot.token_b_price,
+            ..self.start
+        }.value();
+        let lp_value = if snapshot.swap_supply == 0 {
+            0
+        } else {
+            pool_value * self.start.swap_supply as u128 / snapshot.swap_supply as u128
+        };
+
+        Row {
+            timestamp,
+            token_a_price: snapshot.token_a_price,
+            token_b_price: snapshot.token_b_price,
+            token_a_vault: snapshot.token_a_vault,
+            token_b_vault: snapshot.token_b_vault,
+            swap_supply: snapshot.swap_supply,
+            pool_value,
+            value_per_lp_token: if snapshot.swap_supply == 0 { 0.0 } else { pool_value as f64 / snapshot.swap_supply as f64 },
+            lp_value,
+            hold_value,
+            impermanent_loss_bps: if hold_value == 0 {
+                0.0
+            } else {
+                (lp_value as f64 - hold_value as f64) * MAX_BPS as f64 / hold_value as f64
+            },
+            arbitrage_profit: self.arbitrage_profit,
+            fee_income: self.fee_income,
+            rejected_actions: self.rejected.len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Backtest,
+        crate::{
+            config::PoolConfig,
+            series::{Action, ActionKind, PricePoint},
+        },
+        bank_of_zion::{instructions::InitializeParams, state::CurveType},
+    };
+
+    fn backtest(curve_type: CurveType) -> Backtest {
+        let config = PoolConfig {
+            params: InitializeParams {
+                curve_type: curve_type as u8,
+                ..InitializeParams::default()
+            },
+            token_a_vault: 1_000_000,
+            token_b_vault: 1_000_000,
+            swap_supply: 2_000_000,
+        };
+        Backtest::new(config.simulator(100, 100).unwrap(), true)
+    }
+
+    fn price(timestamp: i64, token_a_price: u64, token_b_price: u64) -> PricePoint {
+        PricePoint { timestamp, token_a_price, token_b_price }
+    }
+
+    #[test]
+    fn test_flat_prices_keep_value() {
+        let mut backtest = backtest(CurveType::ConstantProduct);
+        let rows = backtest.run(&[price(0, 100, 100), price(60, 100, 100)], &[]);
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            assert_eq!(row.arbitrage_profit, 0);
+            assert_eq!(row.lp_value, row.hold_value);
+            assert_eq!(row.impermanent_loss_bps, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_price_move_is_arbitraged() {
+        let mut backtest = backtest(CurveType::ConstantProduct);
+        let rows = backtest.run(&[price(0, 100, 100), price(60, 150, 100)], &[]);
+
+        //token a got more valuable, arbitrageurs buy it from the pool until the curve catches up
+        let moved = rows[1];
+        assert!(moved.arbitrage_profit > 0);
+        assert!(moved.token_a_vault < 1_000_000);
+        assert!(moved.token_b_vault > 1_000_000);
+        assert!(moved.lp_value < moved.hold_value);
+        assert!(moved.impermanent_loss_bps < 0.0);
+    }
+
+    #[test]
+    fn test_actions_run_at_their_price() {
+        let mut backtest = backtest(CurveType::ConstantProduct);
+        backtest.arbitrage_every_price = false;
+        let actions = [
+            Action { timestamp: 30, action: ActionKind::SwapAToB, amount_a: 10_000, amount_b: 0 },
+            Action { timestamp: 90, action: ActionKind::Withdraw, amount_a: 5_000_000, amount_b: 0 },
+        ];
+        let rows = backtest.run(&[price(0, 100, 100), price(60, 100, 100), price(120, 100, 100)], &actions);
+
+        assert_eq!(rows[0].token_a_vault, 1_010_000);
+        assert_eq!(rows[1].rejected_actions, 1);
+        assert_eq!(rows[2].rejected_actions, 1);
+        assert_eq!(backtest.rejected[0].0, actions[1]);
+    }
+}
@@ -0,0 +1,155 @@
+//! zion-backtest, replays historical oracle prices and user actions against a pool's curve settings
+
+use {
+    bank_of_zion::instructions::InitializeParams,
+    clap::Parser,
+    std::{error::Error, fs::File, io::{self, Write}, path::PathBuf, process::exit},
+    zion_sim::{
+        backtest::{Backtest, Row},
+        config::PoolConfig,
+        series::{read_actions, read_prices},
+    },
+};
+
+///Replay oracle prices and user actions through the pool's math and print LP value, arbitrage profit,
+///fee income and impermanent loss after every price point
+#[derive(Debug, Parser)]
+#[clap(name = "zion-backtest", version)]
+pub struct Cli {
+    ///CSV of oracle prices with the columns timestamp, token_a_price and token_b_price
+    #[clap(long)]
+    pub prices: PathBuf,
+    ///CSV or JSON list of actions with timestamp, action, amount_a and amount_b,
+    ///action is one of swap_a_to_b, swap_b_to_a, deposit, withdraw and arbitrage
+    #[clap(long)]
+    pub actions: Option<PathBuf>,
+    ///take the most profitable arbitrage at every price point, the way arbitrageurs would
+    #[clap(long)]
+    pub arbitrage: bool,
+    ///file the rows are written to, stdout when left out
+    #[clap(long, short = 'o')]
+    pub output: Option<PathBuf>,
+    ///write the rows as JSON instead of CSV
+    #[clap(long)]
+    pub json: bool,
+
+    ///starting balance of the token a vault
+    #[clap(long)]
+    pub token_a_vault: u64,
+    ///starting balance of the token b vault
+    #[clap(long)]
+    pub token_b_vault: u64,
+    ///starting supply of the swap mint, defaults to the sum of the vault balances
+    #[clap(long, default_value = "0")]
+    pub swap_supply: u64,
+
+    ///basis point fee given to the admin
+    #[clap(long, default_value = "0")]
+    pub program_fee: u64,
+    ///basis point fee given to the liquidity providers
+    #[clap(long, default_value = "0")]
+    pub swap_fee: u64,
+    ///pricing curve, 0 oracle premium, 1 constant product, 2 stable swap
+    #[clap(long, default_value = "0")]
+    pub curve_type: u8,
+    ///amplification coefficient of the StableSwap curve
+    #[clap(long, default_value = "0")]
+    pub amp: u64,
+    ///OraclePremium curve: basis points of the deviation from balance applied to the price
+    #[clap(long, default_value = "0")]
+    pub premium_slope_bps: u64,
+    ///OraclePremium curve: largest premium in basis points
+    #[clap(long, default_value = "0")]
+    pub max_premium_bps: u64,
+    ///OraclePremium curve: largest discount in basis points
+    #[clap(long, default_value = "0")]
+    pub max_discount_bps: u64,
+    ///OraclePremium curve: ignored deviation from balance in basis points
+    #[clap(long, default_value = "0")]
+    pub dead_band_bps: u64,
+    ///largest swap payout in basis points of the destination vault
+    #[clap(long, default_value = "0")]
+    pub max_trade_bps: u64,
+    ///largest deviation of the swap price from the oracle price in basis points
+    #[clap(long, default_value = "0")]
+    pub max_price_deviation_bps: u64,
+}
+
+impl Cli {
+    fn pool_config(&self) -> PoolConfig {
+        PoolConfig {
+            params: InitializeParams {
+                program_fee: self.program_fee,
+                swap_fee: self.swap_fee,
+                curve_type: self.curve_type,
+                amp: self.amp,
+                premium_slope_bps: self.premium_slope_bps,
+                max_premium_bps: self.max_premium_bps,
+                max_discount_bps: self.max_discount_bps,
+                dead_band_bps: self.dead_band_bps,
+                max_trade_bps: self.max_trade_bps,
+                max_price_deviation_bps: self.max_price_deviation_bps,
+                ..InitializeParams::default()
+            },
+            token_a_vault: self.token_a_vault,
+            token_b_vault: self.token_b_vault,
+            swap_supply: self.swap_supply,
+        }
+    }
+}
+
+fn write_rows(rows: &[Row], output: Box<dyn Write>, json: bool) -> Result<(), Box<dyn Error>> {
+    if json {
+        serde_json::to_writer_pretty(output, rows)?;
+        return Ok(())
+    }
+
+    let mut writer = csv::Writer::from_writer(output);
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let prices = read_prices(&cli.prices)?;
+    let first = prices.first().ok_or("the price series is empty")?;
+    let actions = match &cli.actions {
+        Some(path) => read_actions(path)?,
+        None => Vec::new(),
+    };
+
+    let simulator = cli.pool_config().simulator(first.token_a_price, first.token_b_price)
+        .map_err(|err| format!("invalid pool settings: {}", err))?;
+    let mut backtest = Backtest::new(simulator, cli.arbitrage);
+    let rows = backtest.run(&prices, &actions);
+
+    let output: Box<dyn Write> = match &cli.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    write_rows(&rows, output, cli.json)?;
+
+    //summary on stderr so it stays out of the rows
+    for (action, err) in &backtest.rejected {
+        eprintln!("rejected {:?} at {}: {}", action.action, action.timestamp, err);
+    }
+    if let Some(last) = rows.last() {
+        eprintln!("lp value:           {}", last.lp_value);
+        eprintln!("hold value:         {}", last.hold_value);
+        eprintln!("impermanent loss:   {:.2} bps", last.impermanent_loss_bps);
+        eprintln!("arbitrage profit:   {}", last.arbitrage_profit);
+        eprintln!("fee income:         {}", last.fee_income);
+        eprintln!("rejected actions:   {}", last.rejected_actions);
+    }
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(cli) {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
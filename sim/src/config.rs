@@ -0,0 +1,61 @@
+use {
+    bank_of_zion::{instructions::InitializeParams, state::SwapState},
+    bank_of_zion_client::simulator::{PoolSimulator, PoolSnapshot},
+    solana_program::program_error::ProgramError,
+};
+
+///Settings of a pool and the balances it starts with
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoolConfig {
+    ///settings the pool would be initialized with, only the fee, curve and trade limit fields are used
+    pub params: InitializeParams,
+    ///starting balance of the token a vault
+    pub token_a_vault: u64,
+    ///starting balance of the token b vault
+    pub token_b_vault: u64,
+    ///starting supply of the swap mint, zero for the sum of the vault balances
+    pub swap_supply: u64,
+}
+
+impl PoolConfig {
+    ///Swap state of the pool, the params are validated the way Initialize validates them
+    pub fn swap_state(&self) -> Result<SwapState, ProgramError> {
+        let params = &self.params;
+        params.validate()?;
+
+        Ok(SwapState {
+            version: SwapState::VERSION,
+            is_initialized: true.into(),
+            program_fee: params.program_fee.into(),
+            swap_fee: params.swap_fee.into(),
+            curve_type: params.curve_type,
+            amp: params.amp.into(),
+            premium: params.premium_params(),
+            max_trade_bps: params.max_trade_bps.into(),
+            max_price_deviation_bps: params.max_price_deviation_bps.into(),
+            price_mode: params.price_mode,
+            twap_window_secs: params.twap_window_secs.into(),
+            ..SwapState::default()
+        })
+    }
+
+    ///Simulator for the pool priced at the given oracle prices
+    pub fn simulator(&self, token_a_price: u64, token_b_price: u64) -> Result<PoolSimulator, ProgramError> {
+        let swap_supply = match self.swap_supply {
+            0 => self.token_a_vault.saturating_add(self.token_b_vault),
+            swap_supply => swap_supply,
+        };
+
+        let mut simulator = PoolSimulator::new(
+            self.swap_state()?,
+            PoolSnapshot {
+                token_a_vault: self.token_a_vault,
+                token_b_vault: self.token_b_vault,
+                swap_supply,
+                ..PoolSnapshot::default()
+            },
+        );
+        simulator.set_prices(token_a_price, token_b_price);
+        Ok(simulator)
+    }
+}
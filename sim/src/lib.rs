@@ -0,0 +1,19 @@
+#![deny(missing_docs)]
+
+//! Off-chain simulations of a bank_of_zion pool, priced by the program's SwapState math through
+//! the client's PoolSimulator so the results hold for the deployed program
+
+///Curve settings and starting balances of a simulated pool
+pub mod config;
+///Oracle price series and user actions read from CSV or JSON
+pub mod series;
+///Most profitable swap against the oracle prices
+pub mod arbitrage;
+///Replays a price series and user actions against a pool
+pub mod backtest;
+///Oracle prices scripted by step
+pub mod oracle;
+///Arbitrageurs, liquidity providers and random traders
+pub mod agents;
+///Runs agents against a pool and checks the invariants the pool has to keep
+pub mod scenario;
@@ -0,0 +1,55 @@
+use {
+    crate::series::PricePoint,
+    bank_of_zion::state::MAX_BPS,
+    rand::{Rng, SeedableRng},
+    rand_chacha::ChaCha8Rng,
+};
+
+///Oracle prices by step, each keyframe holds until the next one
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OracleScript {
+    ///prices with the step they start at as their timestamp, sorted by step
+    pub keyframes: Vec<PricePoint>,
+}
+
+impl OracleScript {
+    ///Script from keyframes whose timestamps are the steps they start at
+    pub fn new(mut keyframes: Vec<PricePoint>) -> Self {
+        keyframes.sort_by_key(|keyframe| keyframe.timestamp);
+        Self { keyframes }
+    }
+
+    ///Prices that never move
+    pub fn constant(token_a_price: u64, token_b_price: u64) -> Self {
+        Self::new(vec![PricePoint { timestamp: 0, token_a_price, token_b_price }])
+    }
+
+    ///Both prices move by up to max_move_bps every interval steps, the same seed gives the same walk
+    pub fn random_walk(seed: u64, steps: u64, interval: u64, token_a_price: u64, token_b_price: u64, max_move_bps: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut walk = |price: u64| {
+            let move_bps = rng.gen_range(0..=2 * max_move_bps) as i128 - max_move_bps as i128;
+            let moved = price as i128 + price as i128 * move_bps / MAX_BPS as i128;
+            u64::try_from(moved.max(1)).unwrap_or(u64::MAX)
+        };
+
+        let mut keyframes = vec![PricePoint { timestamp: 0, token_a_price, token_b_price }];
+        for step in (interval.max(1)..steps).step_by(interval.max(1) as usize) {
+            let last = keyframes[keyframes.len() - 1];
+            keyframes.push(PricePoint {
+                timestamp: step as i64,
+                token_a_price: walk(last.token_a_price),
+                token_b_price: walk(last.token_b_price),
+            });
+        }
+
+        Self { keyframes }
+    }
+
+    ///Prices at a step, None before the first keyframe
+    pub fn price_at(&self, step: u64) -> Option<(u64, u64)> {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.timestamp <= step as i64);
+        let keyframe = self.keyframes.get(next.checked_sub(1)?)?;
+        Some((keyframe.token_a_price, keyframe.token_b_price))
+    }
+}
@@ -0,0 +1,329 @@
+use {
+    crate::{
+        agents::{Agent, Move, Wallet},
+        oracle::OracleScript,
+    },
+    bank_of_zion::{error::ZionError, state::MAX_BPS},
+    bank_of_zion_client::{
+        simulator::{PoolSimulator, PoolSnapshot},
+        SwapDirection,
+    },
+    rand::SeedableRng,
+    rand_chacha::ChaCha8Rng,
+    spl_math::uint::U256,
+    std::fmt,
+};
+
+///Kinds of invariant a scenario checks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    ///a deposit or withdraw lowered the value per swap token of the other LPs
+    Dilution,
+    ///the value per swap token dropped from one step to the next while the prices stood still
+    LpValueDrop,
+    ///tokens or swap tokens were created or destroyed instead of moving between the pool and the agents
+    Conservation,
+}
+
+///An invariant that didn't hold
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    ///step it happened in
+    pub step: u64,
+    ///agent whose move broke it, None for the checks at the end of a step
+    pub agent: Option<String>,
+    ///invariant that broke
+    pub kind: ViolationKind,
+    ///values involved
+    pub detail: String,
+}
+
+///Agent taking part in a scenario and the tokens it holds
+pub struct Participant {
+    ///decides the moves
+    pub agent: Box<dyn Agent>,
+    ///tokens it holds now
+    pub wallet: Wallet,
+    ///tokens it started with
+    pub start: Wallet,
+}
+
+///Outcome of a scenario
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    ///steps run
+    pub steps: u64,
+    ///moves the agents made
+    pub moves: u64,
+    ///moves the pool refused
+    pub rejected: u64,
+    ///invariants that didn't hold, in the order they broke
+    pub violations: Vec<Violation>,
+    ///pool after the last step
+    pub snapshot: PoolSnapshot,
+    ///value each agent gained or lost at the last step's oracle prices, swap tokens at their share of the pool
+    pub pnl: Vec<(String, i128)>,
+}
+
+impl Report {
+    ///no invariant broke
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    ///violations of one kind
+    pub fn violations_of(&self, kind: ViolationKind) -> impl Iterator<Item = &Violation> {
+        self.violations.iter().filter(move |violation| violation.kind == kind)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "steps: {}, moves: {}, rejected: {}", self.steps, self.moves, self.rejected)?;
+        writeln!(
+            f,
+            "pool: token a {}, token b {}, swap supply {}",
+            self.snapshot.token_a_vault, self.snapshot.token_b_vault, self.snapshot.swap_supply
+        )?;
+        for (name, pnl) in &self.pnl {
+            writeln!(f, "  {}: {:+}", name, pnl)?;
+        }
+        writeln!(f, "violations: {}", self.violations.len())?;
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "  step {} {:?} by {}: {}",
+                violation.step,
+                violation.kind,
+                violation.agent.as_deref().unwrap_or("-"),
+                violation.detail
+            )?;
+        }
+        Ok(())
+    }
+}
+
+///Agents acting on a pool for a number of steps while a scripted oracle moves the prices,
+///the invariants are checked after every move and every step.
+///
+///Each step the oracle prices are set, then the agents move in the order they were added,
+///add the arbitrageurs last so they act on what the other agents did
+pub struct Scenario {
+    ///pool the agents act on
+    pub simulator: PoolSimulator,
+    ///prices by step
+    pub oracle: OracleScript,
+    ///agents in the order they act
+    pub participants: Vec<Participant>,
+    ///seed of every random choice the agents make
+    pub seed: u64,
+    ///steps to run
+    pub steps: u64,
+    ///drop in value per swap token tolerated between steps without a price move in basis points, arbitrageurs
+    ///search for their amount rather than solve for it and can stop a little short of the best one
+    pub tolerance_bps: u64,
+}
+
+impl Scenario {
+    ///Scenario without agents
+    pub fn new(simulator: PoolSimulator, oracle: OracleScript, seed: u64, steps: u64) -> Self {
+        Self {
+            simulator,
+            oracle,
+            participants: Vec::new(),
+            seed,
+            steps,
+            tolerance_bps: 1,
+        }
+    }
+
+    ///Add an agent holding wallet, it acts after the agents added before it
+    pub fn agent<A: Agent + 'static>(mut self, agent: A, wallet: Wallet) -> Self {
+        self.participants.push(Participant {
+            agent: Box::new(agent),
+            wallet,
+            start: wallet,
+        });
+        self
+    }
+
+    ///Run every step and report the violations
+    pub fn run(&mut self) -> Report {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut report = Report::default();
+        let totals = self.totals();
+        let mut previous: Option<PoolSnapshot> = None;
+
+        for step in 0..self.steps {
+            if let Some((token_a_price, token_b_price)) = self.oracle.price_at(step) {
+                self.simulator.set_prices(token_a_price, token_b_price);
+            }
+
+            for participant in &mut self.participants {
+                let decision = participant.agent.decide(&self.simulator, &participant.wallet, &mut rng);
+                let action = match decision {
+                    Some(action) => action,
+                    None => continue,
+                };
+                report.moves += 1;
+
+                let before = self.simulator.snapshot;
+                if execute(&mut self.simulator, &mut participant.wallet, action).is_err() {
+                    report.rejected += 1;
+                    continue;
+                }
+
+                //liquidity moves are priced so the other LPs keep their value per swap token
+                let after = &self.simulator.snapshot;
+                if matches!(action, Move::Deposit { .. } | Move::Withdraw { .. }) && value_per_lp_token_dropped(&before, after, 0) {
+                    report.violations.push(Violation {
+                        step,
+                        agent: Some(participant.agent.name().to_string()),
+                        kind: ViolationKind::Dilution,
+                        detail: format!("{:?} moved the value per swap token {}", action, describe_move(&before, after)),
+                    });
+                }
+            }
+
+            let snapshot = self.simulator.snapshot;
+            if self.totals() != totals {
+                report.violations.push(Violation {
+                    step,
+                    agent: None,
+                    kind: ViolationKind::Conservation,
+                    detail: format!("token a, token b and swap tokens held went from {:?} to {:?}", totals, self.totals()),
+                });
+            }
+
+            //without a price move nobody should be able to take value out of the LPs
+            if let Some(previous) = previous.filter(|previous| {
+                previous.token_a_price == snapshot.token_a_price && previous.token_b_price == snapshot.token_b_price
+            }) {
+                if value_per_lp_token_dropped(&previous, &snapshot, self.tolerance_bps) {
+                    report.violations.push(Violation {
+                        step,
+                        agent: None,
+                        kind: ViolationKind::LpValueDrop,
+                        detail: format!("the value per swap token went {} at unchanged prices", describe_move(&previous, &snapshot)),
+                    });
+                }
+            }
+            previous = Some(snapshot);
+        }
+
+        report.steps = self.steps;
+        report.snapshot = self.simulator.snapshot;
+        report.pnl = self.participants.iter().map(|participant| {
+            let snapshot = &self.simulator.snapshot;
+            let pnl = participant.wallet.value(snapshot) as i128 - participant.start.value(snapshot) as i128;
+            (participant.agent.name().to_string(), pnl)
+        }).collect();
+        report
+    }
+
+    ///token a, token b and swap tokens in the pool and the wallets, swap tokens held outside the agents included
+    fn totals(&self) -> (u128, u128, i128) {
+        let snapshot = &self.simulator.snapshot;
+        let mut token_a = snapshot.token_a_vault as u128 + snapshot.token_a_fee_vault as u128;
+        let mut token_b = snapshot.token_b_vault as u128 + snapshot.token_b_fee_vault as u128;
+        //swap tokens the agents hold less the supply, constant as long as minting and burning go through the agents
+        let mut swap_tokens = -(snapshot.swap_supply as i128);
+        for participant in &self.participants {
+            token_a += participant.wallet.token_a as u128;
+            token_b += participant.wallet.token_b as u128;
+            swap_tokens += participant.wallet.swap_tokens as i128;
+        }
+        (token_a, token_b, swap_tokens)
+    }
+}
+
+///Make a move for a wallet, the wallet has to hold what the move pays in
+pub fn execute(simulator: &mut PoolSimulator, wallet: &mut Wallet, action: Move) -> Result<(), ZionError> {
+    match action {
+        Move::Swap { direction, amount } => {
+            if wallet.source_balance(direction) < amount {
+                return Err(ZionError::InvalidTokenAmount);
+            }
+            let simulation = simulator.swap(direction, amount)?;
+            let (source, destination) = match direction {
+                SwapDirection::AToB => (&mut wallet.token_a, &mut wallet.token_b),
+                SwapDirection::BToA => (&mut wallet.token_b, &mut wallet.token_a),
+            };
+            *source -= amount;
+            *destination += simulation.quote.amount_out;
+        },
+        Move::Deposit { token_a, token_b } => {
+            if wallet.token_a < token_a || wallet.token_b < token_b {
+                return Err(ZionError::InvalidTokenAmount);
+            }
+            let quote = simulator.deposit(token_a, token_b)?;
+            wallet.token_a -= token_a;
+            wallet.token_b -= token_b;
+            wallet.swap_tokens += quote.swap_tokens;
+        },
+        Move::Withdraw { token_a, token_b } => {
+            //the program only withdraws for less than the whole balance
+            if simulator.quote_withdraw(token_a, token_b)?.swap_tokens >= wallet.swap_tokens {
+                return Err(ZionError::InsufficientSwapTokens);
+            }
+            let quote = simulator.withdraw(token_a, token_b)?;
+            wallet.token_a += token_a;
+            wallet.token_b += token_b;
+            wallet.swap_tokens -= quote.swap_tokens;
+        },
+    }
+
+    Ok(())
+}
+
+///value per swap token of after is lower than before's by more than tolerance_bps, compared without dividing
+pub fn value_per_lp_token_dropped(before: &PoolSnapshot, after: &PoolSnapshot, tolerance_bps: u64) -> bool {
+    if before.swap_supply == 0 || after.swap_supply == 0 {
+        return false;
+    }
+
+    //after_value / after_supply < before_value / before_supply * (1 - tolerance)
+    let after_value = U256::from(after.value()) * U256::from(before.swap_supply) * U256::from(MAX_BPS);
+    let before_value = U256::from(before.value())
+        * U256::from(after.swap_supply)
+        * U256::from(MAX_BPS.saturating_sub(tolerance_bps));
+    after_value < before_value
+}
+
+fn describe_move(before: &PoolSnapshot, after: &PoolSnapshot) -> String {
+    format!(
+        "from {}/{} to {}/{}",
+        before.value(), before.swap_supply, after.value(), after.swap_supply
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::value_per_lp_token_dropped,
+        bank_of_zion_client::simulator::PoolSnapshot,
+    };
+
+    fn pool(token_a_vault: u64, swap_supply: u64) -> PoolSnapshot {
+        PoolSnapshot {
+            token_a_vault,
+            swap_supply,
+            token_a_price: 1,
+            ..PoolSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn test_value_per_lp_token_dropped() {
+        //20_000 / 10_000 against 19_999 / 10_000, half a basis point lower
+        assert!(value_per_lp_token_dropped(&pool(20_000, 10_000), &pool(19_999, 10_000), 0));
+        assert!(!value_per_lp_token_dropped(&pool(20_000, 10_000), &pool(19_999, 10_000), 1));
+
+        //more value for more swap tokens at the same ratio, or a higher ratio, isn't a drop
+        assert!(!value_per_lp_token_dropped(&pool(20_000, 10_000), &pool(40_000, 20_000), 0));
+        assert!(!value_per_lp_token_dropped(&pool(20_000, 10_000), &pool(20_001, 10_000), 0));
+
+        //an empty pool has no value per swap token to drop
+        assert!(!value_per_lp_token_dropped(&pool(20_000, 0), &pool(0, 10_000), 0));
+    }
+}
@@ -0,0 +1,70 @@
+use {
+    serde::Deserialize,
+    std::{error::Error, fs::File, path::Path},
+};
+
+///Oracle prices of both tokens at one point in time, a CSV row with the columns timestamp, token_a_price and token_b_price
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct PricePoint {
+    ///seconds, orders the prices and lines the actions up with them
+    pub timestamp: i64,
+    ///oracle price of token a
+    pub token_a_price: u64,
+    ///oracle price of token b
+    pub token_b_price: u64,
+}
+
+///What a user does to the pool
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    ///swap amount_a of token a for token b
+    SwapAToB,
+    ///swap amount_b of token b for token a
+    SwapBToA,
+    ///deposit amount_a of token a and amount_b of token b
+    Deposit,
+    ///withdraw amount_a of token a and amount_b of token b
+    Withdraw,
+    ///take the most profitable swap at the oracle prices, if there is one
+    Arbitrage,
+}
+
+///A user action, a CSV row or a JSON object with timestamp, action, amount_a and amount_b.
+///The amounts can be left out when the action doesn't use them
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct Action {
+    ///seconds, the action runs at the last price point at or before it
+    pub timestamp: i64,
+    ///what the user does
+    pub action: ActionKind,
+    ///tokens of token a
+    #[serde(default)]
+    pub amount_a: u64,
+    ///tokens of token b
+    #[serde(default)]
+    pub amount_b: u64,
+}
+
+///Read a CSV of prices, sorted by timestamp
+pub fn read_prices(path: &Path) -> Result<Vec<PricePoint>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_path(path)?;
+    let mut prices = reader.deserialize().collect::<Result<Vec<PricePoint>, _>>()?;
+    prices.sort_by_key(|price| price.timestamp);
+    Ok(prices)
+}
+
+///Read a JSON array of actions if the file ends in .json and a CSV otherwise, sorted by timestamp.
+///Actions with the same timestamp keep the order they are listed in
+pub fn read_actions(path: &Path) -> Result<Vec<Action>, Box<dyn Error>> {
+    let mut actions: Vec<Action> = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => serde_json::from_reader(File::open(path)?)?,
+        _ => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)?
+            .deserialize()
+            .collect::<Result<_, _>>()?,
+    };
+    actions.sort_by_key(|action| action.timestamp);
+    Ok(actions)
+}
@@ -0,0 +1,114 @@
+use {
+    bank_of_zion::{instructions::InitializeParams, state::CurveType},
+    zion_sim::{
+        agents::{Arbitrageur, LiquidityProvider, RandomTrader, Wallet},
+        config::PoolConfig,
+        oracle::OracleScript,
+        scenario::{Scenario, ViolationKind},
+    },
+};
+
+const STEPS: u64 = 200;
+
+fn pool(curve_type: CurveType, amp: u64, token_a_vault: u64, token_b_vault: u64) -> PoolConfig {
+    PoolConfig {
+        params: InitializeParams {
+            curve_type: curve_type as u8,
+            amp,
+            ..InitializeParams::default()
+        },
+        token_a_vault,
+        token_b_vault,
+        swap_supply: 0,
+    }
+}
+
+fn wallet(token_a: u64, token_b: u64) -> Wallet {
+    Wallet {
+        token_a,
+        token_b,
+        swap_tokens: 0,
+    }
+}
+
+///traders and LPs first, arbitrageurs last, under a walk that moves the prices every 10 steps
+fn mixed_scenario(curve_type: CurveType, amp: u64, seed: u64) -> Scenario {
+    let oracle = OracleScript::random_walk(seed, STEPS, 10, 2_000, 3_100, 300);
+    let simulator = pool(curve_type, amp, 50_000_000, 32_000_000).simulator(2_000, 3_100).unwrap();
+
+    Scenario::new(simulator, oracle, seed, STEPS)
+        .agent(
+            RandomTrader { name: "trader".to_string(), activity_bps: 6_000, max_trade_bps: 500 },
+            wallet(5_000_000, 3_000_000),
+        )
+        .agent(
+            RandomTrader { name: "whale".to_string(), activity_bps: 500, max_trade_bps: 5_000 },
+            wallet(20_000_000, 12_000_000),
+        )
+        .agent(
+            LiquidityProvider { name: "lp".to_string(), activity_bps: 3_000, max_share_bps: 2_000 },
+            wallet(10_000_000, 6_000_000),
+        )
+        .agent(
+            Arbitrageur { name: "arbitrageur".to_string() },
+            wallet(100_000_000, 60_000_000),
+        )
+}
+
+#[test]
+fn test_scenarios_are_deterministic() {
+    for curve_type in [CurveType::OraclePremium, CurveType::ConstantProduct] {
+        let first = mixed_scenario(curve_type, 0, 7).run();
+        let second = mixed_scenario(curve_type, 0, 7).run();
+        assert_eq!(first, second, "{:?}", curve_type);
+        assert!(first.moves > 0);
+    }
+}
+
+#[test]
+fn test_tokens_are_conserved() {
+    for (curve_type, amp) in [
+        (CurveType::OraclePremium, 0),
+        (CurveType::ConstantProduct, 0),
+        (CurveType::StableSwap, 100),
+    ] {
+        for seed in 0..3 {
+            let report = mixed_scenario(curve_type, amp, seed).run();
+            assert_eq!(
+                report.violations_of(ViolationKind::Conservation).count(),
+                0,
+                "{:?} seed {}\n{}",
+                curve_type,
+                seed,
+                report
+            );
+        }
+    }
+}
+
+#[test]
+fn test_constant_product_keeps_lp_value_without_price_moves() {
+    //every trade grows k and the arbitrageur takes the pool back to the cheapest point on the curve,
+    //so at unchanged prices the value per swap token can only grow
+    for seed in 0..3 {
+        let oracle = OracleScript::constant(2_000, 3_100);
+        //balanced at the oracle prices, the arbitrageur has nothing to take at the start
+        let simulator = pool(CurveType::ConstantProduct, 0, 31_000_000, 20_000_000).simulator(2_000, 3_100).unwrap();
+        let mut scenario = Scenario::new(simulator, oracle, seed, STEPS)
+            .agent(
+                RandomTrader { name: "trader".to_string(), activity_bps: 8_000, max_trade_bps: 1_000 },
+                wallet(5_000_000, 3_000_000),
+            )
+            .agent(
+                Arbitrageur { name: "arbitrageur".to_string() },
+                wallet(100_000_000, 60_000_000),
+            );
+
+        let report = scenario.run();
+        assert!(report.is_clean(), "seed {}\n{}", seed, report);
+
+        //the traders pay for their trades, the arbitrageur can't take more than they paid
+        let pnl: i128 = report.pnl.iter().map(|(_, pnl)| pnl).sum();
+        assert!(pnl <= 0, "seed {}\n{}", seed, report);
+    }
+}